@@ -0,0 +1,306 @@
+//! Asynchronous I/O, section 14.5.3
+//!
+//! `AioCb` owns an aiocb together with its buffer. The aiocb is boxed because the
+//! system keeps using its address until the request is done, and the buffer is only
+//! handed out while no request is in progress. Dropping an `AioCb` with a request in
+//! progress cancels it and waits until it's really done. glibc can't cancel a request
+//! a helper thread already started, so e.g. a read from an idle pipe blocks the drop.
+//!
+//! Like in the book a control block can be used for one request after the other, on
+//! different descriptors. The descriptor has to stay open until the request is done.
+//!
+//! glibc implements POSIX AIO in user space: a pool of threads does ordinary
+//! pread/pwrite calls. How the completion of a request is noticed is chosen per request
+//! with `Notify`.
+//!
+//! glibc also runs the notification of a canceled request, so the closure of
+//! `Notify::Thread` is kept in a slot shared with the notification thread: `cancel`
+//! takes it out and drops it, the thread runs it only if it's still there.
+
+use libc::{aiocb, c_int, c_void, off_t, timespec};
+use libc::{EINPROGRESS, EINVAL, O_SYNC, SIGEV_NONE, SIGEV_SIGNAL};
+use libc::{AIO_ALLDONE, AIO_CANCELED, AIO_NOTCANCELED};
+use libc::{aio_cancel, aio_error, aio_fsync, aio_read, aio_return, aio_suspend, aio_write};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::AsRawFd;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::{mem, ptr};
+use LibcResult;
+
+/// how the completion of a request is reported
+pub enum Notify {
+    /// SIGEV_NONE: check with `AioCb::poll`, wait with `suspend`
+    None,
+    /// SIGEV_SIGNAL: the signal is sent when the request is done. Signals aren't
+    /// queued, so one signal can stand for several requests
+    Signal(c_int),
+    /// SIGEV_THREAD: the closure runs in a new thread when the request is done, Linux
+    /// with glibc only. A canceled request (AIO_CANCELED) drops it without running it
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    Thread(Box<dyn FnOnce() + Send>),
+}
+
+/// result of `AioCb::cancel`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Canceled {
+    /// AIO_CANCELED: the request fails with ECANCELED
+    Canceled,
+    /// AIO_NOTCANCELED: it's already being processed and will complete
+    NotCanceled,
+    /// AIO_ALLDONE: it was done before the call
+    AllDone,
+}
+
+/// asynchronous I/O control block with its buffer
+pub struct AioCb {
+    cb: Box<aiocb>,
+    buf: Vec<u8>,
+    in_progress: bool,
+    // the closure of the last Notify::Thread request
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    closure: Option<Arc<ClosureSlot>>,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type ClosureSlot = Mutex<Option<Box<dyn FnOnce() + Send>>>;
+
+// glibc's struct sigevent with the SIGEV_THREAD part of its union, libc only has
+// sigev_notify_thread_id
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+#[repr(C)]
+struct SigeventThread {
+    sigev_value: libc::sigval,
+    sigev_signo: c_int,
+    sigev_notify: c_int,
+    sigev_notify_function: Option<extern "C" fn(libc::sigval)>,
+    sigev_notify_attributes: *mut libc::pthread_attr_t,
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const _: () = {
+    assert!(mem::size_of::<SigeventThread>() <= mem::size_of::<libc::sigevent>());
+    assert!(mem::align_of::<SigeventThread>() <= mem::align_of::<libc::sigevent>());
+    assert!(mem::offset_of!(SigeventThread, sigev_notify) ==
+            mem::offset_of!(libc::sigevent, sigev_notify));
+    assert!(mem::offset_of!(SigeventThread, sigev_notify_function) ==
+            mem::offset_of!(libc::sigevent, sigev_notify_thread_id));
+};
+
+// the function glibc starts for SIGEV_THREAD, the value is a reference to the slot
+// with the closure
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+extern "C" fn notify_thread(value: libc::sigval) {
+    let slot = unsafe { Arc::from_raw(value.sival_ptr as *const ClosureSlot) };
+    let f = slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(f) = f {
+        f();
+    }
+}
+
+impl AioCb {
+    /// control block using `buf`: reads fill all of it, writes write all of it
+    pub fn new(buf: Vec<u8>) -> AioCb {
+        AioCb {
+            cb: Box::new(unsafe { mem::zeroed() }),
+            buf: buf,
+            in_progress: false,
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            closure: None,
+        }
+    }
+
+    /// aio_read: read into the buffer from `offset` of `fd`
+    pub fn read<F: AsRawFd>(&mut self, fd: &F, offset: off_t, notify: Notify) -> Result<()> {
+        self.submit(fd, offset, notify, |cb| unsafe { aio_read(cb) })
+    }
+
+    /// aio_write: write the buffer at `offset` of `fd` (with O_APPEND at the end)
+    pub fn write<F: AsRawFd>(&mut self, fd: &F, offset: off_t, notify: Notify) -> Result<()> {
+        self.submit(fd, offset, notify, |cb| unsafe { aio_write(cb) })
+    }
+
+    /// aio_fsync(O_SYNC): done when all requests for `fd` submitted before it are
+    /// written to the disk, the buffer isn't used
+    pub fn fsync<F: AsRawFd>(&mut self, fd: &F, notify: Notify) -> Result<()> {
+        self.submit(fd, 0, notify, |cb| unsafe { aio_fsync(O_SYNC, cb) })
+    }
+
+    fn submit<F, S>(&mut self, fd: &F, offset: off_t, notify: Notify, start: S) -> Result<()>
+        where F: AsRawFd,
+              S: FnOnce(*mut aiocb) -> c_int
+    {
+        if self.in_progress {
+            return Err(Error::new(ErrorKind::InvalidInput, "request already in progress"));
+        }
+        self.cb.aio_fildes = fd.as_raw_fd();
+        self.cb.aio_offset = offset;
+        self.cb.aio_buf = self.buf.as_mut_ptr() as *mut c_void;
+        self.cb.aio_nbytes = self.buf.len();
+        self.cb.aio_sigevent.sigev_value.sival_ptr = ptr::null_mut();
+        match notify {
+            Notify::None => self.cb.aio_sigevent.sigev_notify = SIGEV_NONE,
+            Notify::Signal(signo) => {
+                self.cb.aio_sigevent.sigev_notify = SIGEV_SIGNAL;
+                self.cb.aio_sigevent.sigev_signo = signo;
+            }
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            Notify::Thread(f) => self.set_thread(f),
+        }
+        if let Err(e) = start(&mut *self.cb).check_not_negative() {
+            self.drop_closure();
+            return Err(e);
+        }
+        self.in_progress = true;
+        Ok(())
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    fn set_thread(&mut self, f: Box<dyn FnOnce() + Send>) {
+        let slot = Arc::new(Mutex::new(Some(f)));
+        // checked against libc::sigevent above
+        let ev = unsafe {
+            &mut *(&mut self.cb.aio_sigevent as *mut libc::sigevent as *mut SigeventThread)
+        };
+        ev.sigev_notify = libc::SIGEV_THREAD;
+        ev.sigev_value.sival_ptr = Arc::into_raw(slot.clone()) as *mut c_void;
+        ev.sigev_notify_function = Some(notify_thread);
+        ev.sigev_notify_attributes = ptr::null_mut();
+        self.closure = Some(slot);
+    }
+
+    // the reference of the notification thread of a request that never started
+    fn drop_closure(&mut self) {
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        {
+            let ev = &mut self.cb.aio_sigevent;
+            if ev.sigev_notify == libc::SIGEV_THREAD && !ev.sigev_value.sival_ptr.is_null() {
+                drop(unsafe { Arc::from_raw(ev.sigev_value.sival_ptr as *const ClosureSlot) });
+                ev.sigev_value.sival_ptr = ptr::null_mut();
+            }
+            self.closure = None;
+        }
+    }
+
+    // the closure of a canceled request, whether or not the system still notifies
+    fn cancel_closure(&mut self) {
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        {
+            if let Some(slot) = self.closure.take() {
+                let f = slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+                drop(f);
+            }
+        }
+    }
+
+    pub fn in_progress(&self) -> bool {
+        self.in_progress
+    }
+
+    /// aio_error and aio_return: None while the request is in progress, then the
+    /// number of bytes transferred (0 at end of file) or its error
+    pub fn poll(&mut self) -> Option<Result<usize>> {
+        if !self.in_progress {
+            return Some(Err(Error::from_raw_os_error(EINVAL)));
+        }
+        match unsafe { aio_error(&*self.cb) } {
+            EINPROGRESS => None,
+            -1 => Some(Err(Error::last_os_error())),
+            err => {
+                // aio_return has to be called once for every request, also a failed one
+                let n = unsafe { aio_return(&mut *self.cb) };
+                self.in_progress = false;
+                if err == 0 {
+                    Some(Ok(n as usize))
+                } else {
+                    Some(Err(Error::from_raw_os_error(err)))
+                }
+            }
+        }
+    }
+
+    /// block until the request is done, its result as for `poll`
+    pub fn wait(&mut self) -> Result<usize> {
+        loop {
+            if let Some(res) = self.poll() {
+                return res;
+            }
+            match suspend(&[&*self], None) {
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+                Ok(_) => {}
+            }
+        }
+    }
+
+    /// aio_cancel for the request, a canceled request still has to be `poll`ed
+    pub fn cancel(&mut self) -> Result<Canceled> {
+        if !self.in_progress {
+            return Ok(Canceled::AllDone);
+        }
+        match unsafe { aio_cancel(self.cb.aio_fildes, &mut *self.cb) }.check_not_negative()? {
+            AIO_CANCELED => {
+                self.cancel_closure();
+                Ok(Canceled::Canceled)
+            }
+            AIO_NOTCANCELED => Ok(Canceled::NotCanceled),
+            AIO_ALLDONE => Ok(Canceled::AllDone),
+            n => Err(Error::other(format!("unexpected aio_cancel result {}", n))),
+        }
+    }
+
+    /// the buffer, None while a request is in progress
+    pub fn buffer(&self) -> Option<&Vec<u8>> {
+        if self.in_progress {
+            None
+        } else {
+            Some(&self.buf)
+        }
+    }
+
+    /// the buffer, e.g. to resize it for the next request. None while a request is in
+    /// progress
+    pub fn buffer_mut(&mut self) -> Option<&mut Vec<u8>> {
+        if self.in_progress {
+            None
+        } else {
+            Some(&mut self.buf)
+        }
+    }
+}
+
+impl Drop for AioCb {
+    fn drop(&mut self) {
+        if self.in_progress {
+            // the buffer and the aiocb must not be freed under a running request
+            let _ = self.cancel();
+            let _ = self.wait();
+        }
+    }
+}
+
+/// aio_suspend: block until at least one of the requests of `cbs` is done, false if
+/// `timeout` expired first. Control blocks without a request in progress are ignored,
+/// if none is left it returns true right away
+pub fn suspend(cbs: &[&AioCb], timeout: Option<Duration>) -> Result<bool> {
+    let list: Vec<*const aiocb> = cbs.iter()
+        .filter(|cb| cb.in_progress)
+        .map(|cb| &*cb.cb as *const aiocb)
+        .collect();
+    if list.is_empty() {
+        return Ok(true);
+    }
+    let ts = timeout.map(|t| {
+        timespec {
+            tv_sec: t.as_secs() as _,
+            tv_nsec: t.subsec_nanos() as _,
+        }
+    });
+    let tsptr = ts.as_ref().map_or(ptr::null(), |ts| ts as *const timespec);
+    match unsafe { aio_suspend(list.as_ptr(), list.len() as c_int, tsptr) }.check_not_negative() {
+        Ok(_) => Ok(true),
+        Err(ref e) if e.raw_os_error() == Some(libc::EAGAIN) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
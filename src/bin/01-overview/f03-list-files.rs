@@ -13,17 +13,17 @@
 /// $ rm -d /tmp/apue
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use apue::{array_to_string, LibcResult, LibcPtrResult};
 use apue::my_libc::readdir;
+use apue::cstr::with_cstr;
 use libc::{opendir, closedir};
 
 fn main() {
     let dir = std::env::args().nth(1).expect("please specify a path");
     unsafe {
-        let dp = opendir(cstr!(dir.clone()));
+        let dp = with_cstr(&dir, |path| opendir(path.as_ptr())).expect("invalid path");
         assert!(!dp.is_null(), format!("can't open directory {:?}", dir));
         while let Ok(dirp) = readdir(dp).check_not_null() {
             println!("{}", array_to_string(&(*dirp).d_name));
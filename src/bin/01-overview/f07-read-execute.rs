@@ -13,7 +13,7 @@
 /// % %
 
 extern crate libc;
-#[macro_use(cstr, as_char)]
+#[macro_use(as_char)]
 extern crate apue;
 
 use libc::{STDIN_FILENO, c_char, printf, strlen, fgets, fdopen, fork, waitpid};
@@ -27,7 +27,7 @@ fn main() {
         let mut buf: [c_char; MAXLINE] = std::mem::uninitialized();
         let stdin = fdopen(STDIN_FILENO, &('r' as c_char));
         let mut status = 0;
-        printf(cstr!("%% ")); // print prompt (printf requires %% to print %)
+        printf(b"%% \0".as_ptr() as *const _); // print prompt (printf requires %% to print %)
         while !fgets(as_char!(buf), MAXLINE as _, stdin).is_null() {
             let len = strlen(as_char!(buf));
             if buf[len - 1] == '\n' as _ {
@@ -41,7 +41,7 @@ fn main() {
             } else {
                 // parent
                 waitpid(pid, &mut status, 0).check_not_negative().expect("waitpid error");
-                printf(cstr!("%% "));
+                printf(b"%% \0".as_ptr() as *const _);
             }
         }
     }
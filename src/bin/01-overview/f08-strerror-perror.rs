@@ -7,25 +7,56 @@
 ///   why it is in the separate errno crate.
 /// - Without the extra fflush, on OSX the perror() output is printed first, only then the fprintf.
 ///   On linux it's the "right" way around.
+/// - the `sys_errlist` array isn't exported by newer glibc anymore, `apue::strerror` uses
+///   strerror_r instead
 ///
-/// $ f08-strerror-perror 2>&1 | grep EACCES
-/// EACCES: Permission denied
-
+/// Prints the table of all errnos of the system (`apue::error::Errno::all`), or only
+/// those given as arguments (names or numbers), followed by perror for ENOENT.
+///
+/// $ f08-strerror-perror 2>&1 | grep '^EACCES '
+/// EACCES            13 Permission denied
+/// $ f08-strerror-perror EFOO
+/// unknown errno EFOO
+/// ERROR: return code 1
+///
+/// EWOULDBLOCK is an alias of EAGAIN:
+///
+/// linux only:
+/// $ f08-strerror-perror EWOULDBLOCK 2 2>&1 | sed 's/^.*f08-strerror-perror:/f08-strerror-perror:/'
+/// EAGAIN            11 Resource temporarily unavailable
+/// ENOENT             2 No such file or directory
+/// f08-strerror-perror: No such file or directory
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 extern crate errno;
-use errno::{Errno, set_errno};
 
-use libc::{EACCES, ENOENT, STDERR_FILENO, c_char, fdopen, fprintf, strerror, perror, fflush};
+use apue::cstr::with_cstr;
+use apue::error::Errno;
+use errno::set_errno;
+use libc::{ENOENT, perror};
+use std::io::Write;
 
 fn main() {
+    let errnos: Vec<Errno> = if std::env::args().len() > 1 {
+        std::env::args()
+            .skip(1)
+            .map(|arg| {
+                arg.parse().unwrap_or_else(|e| {
+                    println!("{}", e);
+                    std::process::exit(1);
+                })
+            })
+            .collect()
+    } else {
+        Errno::all()
+    };
+    for e in errnos {
+        println!("{:<16} {:>3} {}", e.name().unwrap_or("?"), e.number(), e.message());
+    }
+    std::io::stdout().flush().unwrap();
     unsafe {
-        let stderr = fdopen(STDERR_FILENO, &('w' as c_char));
-        fprintf(stderr, cstr!("EACCES: %s\n"), strerror(EACCES));
-        fflush(stderr);
-        set_errno(Errno(ENOENT));
-        perror(cstr!(std::env::current_exe().unwrap().to_str().unwrap()));
+        set_errno(errno::Errno(ENOENT));
+        with_cstr(std::env::current_exe().unwrap(), |exe| perror(exe.as_ptr())).unwrap();
     }
 }
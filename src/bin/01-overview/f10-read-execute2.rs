@@ -7,7 +7,7 @@
 
 
 extern crate libc;
-#[macro_use(cstr, as_char)]
+#[macro_use(as_char)]
 extern crate apue;
 
 use libc::{STDIN_FILENO, SIGINT, SIG_ERR, c_char, c_int, printf, strlen, fgets, fdopen, fork,
@@ -17,8 +17,8 @@ use apue::my_libc::execlp;
 
 extern "C" fn sig_int(_: c_int) {
     unsafe {
-        printf(cstr!("interrupted..\n"));
-        printf(cstr!("%% "));
+        printf(b"interrupted..\n\0".as_ptr() as *const _);
+        printf(b"%% \0".as_ptr() as *const _);
     }
 }
 
@@ -33,7 +33,7 @@ fn main() {
         if signal(SIGINT, s as usize) == SIG_ERR {
             panic!("signal error");
         }
-        printf(cstr!("%% ")); // print prompt (printf requires %% to print %)
+        printf(b"%% \0".as_ptr() as *const _); // print prompt (printf requires %% to print %)
         while !fgets(as_char!(buf), MAXLINE as _, stdin).is_null() {
             let len = strlen(as_char!(buf));
             if buf[len - 1] == '\n' as _ {
@@ -47,7 +47,7 @@ fn main() {
                 } else {
                     // parent
                     if waitpid(pid, &mut status, 0).check_not_negative().is_ok() {
-                        printf(cstr!("%% "));
+                        printf(b"%% \0".as_ptr() as *const _);
                     } else {
                         panic!("waitpid error");
                     }
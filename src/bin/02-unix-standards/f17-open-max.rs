@@ -6,41 +6,23 @@
 /// how to reference that (AFAIK extern blocks can only reference functions, not constants)
 /// On Linux it returns 1024 (which matches `ulimit -n`)
 ///
+/// The function itself is `apue::limits::open_max`, which falls back to a guess of 256
+/// if sysconf reports the limit as indeterminate.
+///
 /// $ f17-open-max | grep succeeded
 /// sysconf succeeded..
+/// $ [ "$(f17-open-max | tail -1)" = "$(ulimit -n)" ] && echo same
+/// same
 
-extern crate libc;
-extern crate errno;
 extern crate apue;
 
-use libc::{_SC_OPEN_MAX, sysconf};
-use errno::errno;
-use apue::LibcResult;
-
-const OPEN_MAX_GUESS: i64 = 256;
-
-unsafe fn open_max(openmax: &mut i64) -> i64 {
-    if *openmax == 0 {
-        *openmax = match sysconf(_SC_OPEN_MAX).check_not_negative() {
-            Ok(val) => {
-                println!("sysconf succeeded..");
-                val
-            }
-            Err(_) => {
-                println!("sysconf failed..");
-                match errno().0 {
-                    0 => OPEN_MAX_GUESS, // indeterminate so just a guess
-                    _ => panic!("pathconf error for _PC_PATH_MAX"),
-                }
-            }
-        }
-    }
-    *openmax
-}
+use apue::limits::{open_max, sysconf, Limit, Sysconf};
 
 fn main() {
-    let mut openmax = 0;
-    unsafe {
-        println!("{:?}", open_max(&mut openmax));
+    match sysconf(Sysconf::OpenMax) {
+        Ok(Limit::Value(_)) => println!("sysconf succeeded.."),
+        Ok(limit) => println!("sysconf failed.. ({})", limit),
+        Err(e) => panic!("sysconf error for _SC_OPEN_MAX: {}", e),
     }
+    println!("{}", open_max());
 }
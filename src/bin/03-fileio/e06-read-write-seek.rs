@@ -10,19 +10,20 @@
 /// 123hansaplast!
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{SEEK_SET, fopen, fseek, fputs};
 use apue::{LibcResult, LibcPtrResult};
+use apue::cstr::to_cstring;
 
 fn main() {
     unsafe {
         let file = std::env::args()
             .next_back()
             .expect("specify path to file to be opened for read/write/seek");
-        let f = fopen(cstr!(file), cstr!("r+")).check_not_null().expect("fopen failed");
+        let file = to_cstring(file).expect("invalid path");
+        let f = fopen(file.as_ptr(), b"r+\0".as_ptr() as *const _).check_not_null().expect("fopen failed");
         fseek(f, 3, SEEK_SET).check_not_negative().expect("fseek failed");
-        fputs(cstr!("hansaplast!") as _, f);
+        fputs(b"hansaplast!\0".as_ptr() as _, f);
     }
 }
@@ -19,7 +19,7 @@
 // 40 file.nohole
 
 extern crate libc;
-#[macro_use(cstr, as_void)]
+#[macro_use(as_void)]
 extern crate apue;
 
 use libc::{mode_t, SEEK_SET, creat, write, lseek};
@@ -32,7 +32,7 @@ fn main() {
     unsafe {
         let s1 = CString::new("abcdefghij");
         let s2 = CString::new("ABCDEFGHIJ");
-        let fd = creat(cstr!("file.hole"), FILE_MODE).check_not_negative().expect("creat error");
+        let fd = creat(b"file.hole\0".as_ptr() as *const _, FILE_MODE).check_not_negative().expect("creat error");
         assert!(write(fd, as_void!(s1.unwrap().as_bytes()), 10) == 10,
                 "buffer write error");
         // offset is now 10
@@ -9,9 +9,14 @@
 ///   course is obvious. But the difference is big: with a buffer size of 1 writing
 ///   into /tmp/discard.txt took 23.5 minutes. Writing to /dev/null only 8.5 minutes.
 ///
+/// - a write to a pipe or socket may write less than asked for, `apue::io::writen`
+///   writes the rest
+///
 /// $ dd if=/dev/zero of=/tmp/file.dat bs=1024 count=1 2>/dev/null
 /// $ f05-copy-stdin-stdout 64 < /tmp/file.dat 2>&1 >/dev/null
 /// total loops: 16
+/// $ f05-copy-stdin-stdout 1000 < /tmp/file.dat | wc -c | tr -d ' '
+/// 1024
 
 /// ## Timing
 ///
@@ -94,8 +99,9 @@ extern crate libc;
 extern crate apue;
 extern crate errno;
 
-use libc::{STDIN_FILENO, STDOUT_FILENO, read, write};
+use libc::{STDIN_FILENO, STDOUT_FILENO, read};
 use apue::LibcResult;
+use apue::io::{writen, OnIntr};
 use errno::errno;
 use std::io::Write;
 
@@ -111,8 +117,7 @@ fn main() {
         let mut num_loops = 0;
         let buf = vec![0; buffsize];
         while let Ok(n) = read(STDIN_FILENO, as_void!(buf), buffsize).check_positive() {
-            assert!(write(STDOUT_FILENO, as_void!(buf), n as _) == n,
-                    "write error");
+            writen(&STDOUT_FILENO, &buf[..n as usize], OnIntr::Retry).expect("write error");
             num_loops += 1;
         }
         if errno().0 > 0 {
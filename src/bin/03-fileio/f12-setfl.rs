@@ -1,6 +1,8 @@
 /// Figure 3.12: Turn on one or more of the file status flags for a descriptor
 /// this example turns on the O_NONBLOCK flag
 ///
+/// `get_fl` and `set_fl` are in `apue::fd`, next to `clr_fl` and `set_nonblocking`
+///
 /// Takeaways:
 ///
 /// - Mac lets you set almost every flag (O_READ and O_WRITE), on Linux that's not possible
@@ -23,31 +25,19 @@
 
 extern crate libc;
 extern crate apue;
-#[macro_use(value_t)]
-extern crate clap;
-
-use libc::{F_GETFL, F_SETFL, O_NONBLOCK, fcntl};
-use clap::App;
-use apue::LibcResult;
 
-unsafe fn get_fl(fd: i32) -> std::io::Result<i32> {
-    return fcntl(fd, F_GETFL, 0).check_not_negative();
-}
-
-unsafe fn set_fl(fd: i32, flags: i32) {
-    let val = get_fl(fd).expect("fcntl F_GETFL error");
-    println!("current flags: {:b}", val);
-    let val = val | flags;
-    fcntl(fd, F_SETFL, val).check_not_negative().expect("fcntl F_SETFL error");
-}
+use libc::O_NONBLOCK;
+use apue::fd::{get_fl, set_fl};
 
 fn main() {
-    let matches = App::new("setfl")
-        .args_from_usage("<descr> 'id of the descriptor'")
-        .get_matches();
-    let fd = value_t!(matches.value_of("descr"), i32).unwrap_or_else(|e| e.exit());
-    unsafe {
-        set_fl(fd, O_NONBLOCK);
-        println!("new flags: {:b}", get_fl(fd).unwrap());
-    }
+    let fd: i32 = match std::env::args().nth(1).map(|s| s.parse()) {
+        Some(Ok(fd)) => fd,
+        _ => {
+            eprintln!("usage: f12-setfl <descr>");
+            std::process::exit(1);
+        }
+    };
+    println!("current flags: {:b}", get_fl(&fd).expect("fcntl F_GETFL error"));
+    set_fl(&fd, O_NONBLOCK).expect("fcntl F_SETFL error");
+    println!("new flags: {:b}", get_fl(&fd).unwrap());
 }
@@ -22,52 +22,43 @@
 /// kurt
 /// $ rm /tmp/sparse*.txt
 
-extern crate clap;
-#[macro_use(cstr)]
 extern crate apue;
 extern crate libc;
-extern crate errno;
 
-use clap::App;
 use std::vec::Vec;
 use std::io::{self, Write};
-use apue::LibcResult;
-use libc::{O_RDWR, O_RDONLY, O_CREAT, SEEK_SET, SEEK_CUR, c_void, open, read, write, lseek};
+use apue::fd::Fd;
+use libc::{O_RDWR, O_RDONLY, O_CREAT, SEEK_SET, SEEK_CUR};
 
 const BUFLEN: usize = 1;
 
 fn main() {
-    unsafe {
-        let matches = App::new("cp")
-            .args_from_usage("<from> source/file\n<to> destination/file")
-            .get_matches();
-        let from = matches.value_of("from").unwrap();
-        let to = matches.value_of("to").unwrap();
-        let fd1 = open(cstr!(from), O_RDONLY)
-            .check_not_negative()
-            .expect(&format!("can't open file {}", from));
-        let fd2 = open(cstr!(to), O_RDWR | O_CREAT, 0o600)
-            .check_not_negative()
-            .expect(&format!("can't open file {}", to));
-        let mut buf: Vec<u8> = vec![0; BUFLEN];
-        let mut sparse = false;
-        while read(fd1, buf.as_mut_ptr() as *mut c_void, BUFLEN) == BUFLEN as _ {
-            if buf[0] == 0 {
-                // better would be lseek with whence=SEEK_HOLE
-                // but this is non-bsd only, so we implement that ourselves
-                // of course this is not fast at all
-                sparse = true;
-                continue;
-            }
-            // end of a block of zeroes, seek the write file to the right pos
-            if sparse {
-                let pos = lseek(fd1, 0, SEEK_CUR);
-                println!("seeking to {}", pos);
-                lseek(fd2, pos, SEEK_SET);
-                sparse = false;
-            }
-            write(fd2, buf.as_ptr() as *mut c_void, BUFLEN);
-            io::stdout().write(&buf).unwrap();
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        println!("usage: {} <from> <to>", args[0]);
+        std::process::exit(1);
+    }
+    let (from, to) = (&args[1], &args[2]);
+    let fd1 = Fd::open(from, O_RDONLY, 0).expect(&format!("can't open file {}", from));
+    let fd2 = Fd::open(to, O_RDWR | O_CREAT, 0o600).expect(&format!("can't open file {}", to));
+    let mut buf: Vec<u8> = vec![0; BUFLEN];
+    let mut sparse = false;
+    while fd1.read(&mut buf).expect("read error") == BUFLEN {
+        if buf[0] == 0 {
+            // better would be lseek with whence=SEEK_HOLE
+            // but this is non-bsd only, so we implement that ourselves
+            // of course this is not fast at all
+            sparse = true;
+            continue;
+        }
+        // end of a block of zeroes, seek the write file to the right pos
+        if sparse {
+            let pos = fd1.lseek(0, SEEK_CUR).expect("lseek error");
+            println!("seeking to {}", pos);
+            fd2.lseek(pos, SEEK_SET).expect("lseek error");
+            sparse = false;
         }
+        fd2.write(&buf).expect("write error");
+        io::stdout().write(&buf).unwrap();
     }
 }
@@ -13,7 +13,6 @@
 /// ERROR: return code 101
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use std::ffi::{CStr, CString};
@@ -29,7 +28,7 @@ const GUESS_PATH_LENGH: usize = 4096;
 
 fn main() {
     unsafe {
-        let path_max = libc::pathconf(cstr!("."), _PC_PATH_MAX);
+        let path_max = libc::pathconf(b".\0".as_ptr() as *const _, _PC_PATH_MAX);
         let initial =
             CString::new("/tmp/someinitialpathwhichisquitelongalreadysowedontneedtoloopforsolong")
                 .unwrap();
@@ -37,8 +36,8 @@ fn main() {
         chdir(initial.as_ptr());
         println!("PATH_MAX={}", path_max);
         loop {
-            libc::mkdir(cstr!("a"), libc::S_IRWXU);
-            libc::chdir(cstr!("a"));
+            libc::mkdir(b"a\0".as_ptr() as *const _, libc::S_IRWXU);
+            libc::chdir(b"a\0".as_ptr() as *const _);
             let buf = {
                 let mut buf = Vec::with_capacity(BUF_BYTES);
                 let ptr = buf.as_mut_ptr() as *mut libc::c_char;
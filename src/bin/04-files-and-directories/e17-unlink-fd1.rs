@@ -21,7 +21,6 @@
 /// new fd: 3
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use apue::LibcResult;
@@ -31,14 +30,14 @@ const FILE_MODE: mode_t = S_IRUSR + S_IWUSR + S_IRGRP + S_IWGRP + S_IROTH + S_IW
 
 fn main() {
     let fd = unsafe {
-        unlink(cstr!("/dev/fd/1"))
+        unlink(b"/dev/fd/1\0".as_ptr() as *const _)
             .check_not_negative()
             .or_else(|e| {
                 println!("{}", e);
                 Err(e)
             })
             .ok();
-        creat(cstr!("/dev/fd/1"), FILE_MODE).check_not_negative().expect("creat error")
+        creat(b"/dev/fd/1\0".as_ptr() as *const _, FILE_MODE).check_not_negative().expect("creat error")
     };
     println!("new fd: {}", fd);
 }
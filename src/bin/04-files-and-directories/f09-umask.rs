@@ -19,7 +19,6 @@
 /// $ rm /tmp/{foo,bar}
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{mode_t, S_IRUSR, S_IWUSR, S_IRGRP, S_IWGRP, S_IROTH, S_IWOTH, umask, creat};
@@ -30,8 +29,8 @@ const RWRWRW: mode_t = (S_IRUSR | S_IWUSR | S_IRGRP | S_IWGRP | S_IROTH | S_IWOT
 fn main() {
     unsafe {
         umask(0);
-        creat(cstr!("/tmp/foo"), RWRWRW).check_not_negative().expect("creat error for /tmp/foo");
+        creat(b"/tmp/foo\0".as_ptr() as *const _, RWRWRW).check_not_negative().expect("creat error for /tmp/foo");
         umask(S_IRGRP | S_IWGRP | S_IROTH | S_IWOTH);
-        creat(cstr!("/tmp/bar"), RWRWRW).check_not_negative().expect("creat error for /tmp/bar");
+        creat(b"/tmp/bar\0".as_ptr() as *const _, RWRWRW).check_not_negative().expect("creat error for /tmp/bar");
     }
 }
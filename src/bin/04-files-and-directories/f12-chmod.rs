@@ -24,7 +24,6 @@
 /// -rw-r--r--
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{mode_t, S_IXUSR, S_ISUID, S_IROTH, S_IRGRP, S_IWUSR, S_IRUSR, stat, chmod};
@@ -33,17 +32,17 @@ use apue::LibcResult;
 fn main() {
     unsafe {
         let mut statbuf: stat = std::mem::uninitialized();
-        stat(cstr!("/tmp/foo"), &mut statbuf)
+        stat(b"/tmp/foo\0".as_ptr() as *const _, &mut statbuf)
             .check_not_negative()
             .expect("stat error for /tmp/foo");
         // turn on set-group-ID and turn off group-execute
-        chmod(cstr!("/tmp/foo"),
+        chmod(b"/tmp/foo\0".as_ptr() as *const _,
               (statbuf.st_mode & !S_IXUSR) | S_ISUID as mode_t)
             .check_not_negative()
             .expect("chmod error for foo");
 
         // set absolute mode to "rw-r--r--"
-        chmod(cstr!("/tmp/bar"), S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+        chmod(b"/tmp/bar\0".as_ptr() as *const _, S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
             .check_not_negative()
             .expect("chmod error for bar");
     }
@@ -42,7 +42,6 @@
 // /dev/disk1  931Gi  388Gi  542Gi    42% 3467369 4291499910    0%   /
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{open, sleep, unlink, c_int};
@@ -52,8 +51,8 @@ const O_RDWR: c_int = 2; // taken from /usr/include/sys/fcntl.h
 
 fn main() {
     unsafe {
-        open(cstr!("/tmp/f16-unlink.tmp"), O_RDWR).check_not_negative().expect("open error");
-        unlink(cstr!("/tmp/f16-unlink.tmp")).check_not_negative().expect("unlink error");
+        open(b"/tmp/f16-unlink.tmp\0".as_ptr() as *const _, O_RDWR).check_not_negative().expect("open error");
+        unlink(b"/tmp/f16-unlink.tmp\0".as_ptr() as *const _).check_not_negative().expect("unlink error");
         sleep(0);// change this to 15 to make the test as explained in the book
         println!("file unlinked");
     }
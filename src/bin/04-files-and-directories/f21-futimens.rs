@@ -21,7 +21,7 @@
 /// 0
 
 extern crate libc;
-#[macro_use(cstr, print_err)]
+#[macro_use(print_err)]
 extern crate apue;
 
 use apue::LibcResult;
@@ -34,7 +34,7 @@
 
 extern crate clap;
 extern crate libc;
-#[macro_use(print_err, cstr)]
+#[macro_use(print_err)]
 extern crate apue;
 extern crate errno;
 
@@ -42,6 +42,7 @@ use libc::{stat, S_IFMT, S_IFDIR, S_IFREG, S_IFBLK, S_IFCHR, S_IFIFO, S_IFLNK, S
            closedir, lstat, chdir};
 use apue::{LibcResult, LibcPtrResult};
 use apue::my_libc::readdir;
+use apue::cstr::{to_cstring, with_cstr};
 use clap::App;
 use std::ffi::{CString, CStr};
 
@@ -114,7 +115,7 @@ enum FileType {
 }
 
 unsafe fn myftw(cnt: &mut Counter) {
-    let dp = match opendir(cstr!(".")).check_not_null() {
+    let dp = match opendir(b".\0".as_ptr() as *const _).check_not_null() {
         Ok(dp) => dp,
         Err(_) => {
             print_err!("cannot open dir: {}", errno::errno());
@@ -138,9 +139,10 @@ unsafe fn myftw(cnt: &mut Counter) {
         match statbuf.st_mode & S_IFMT {
             S_IFDIR => {
                 cnt.count_other(&filename, FileType::Directory);
-                if chdir(cstr!(filename)).check_not_negative().is_ok() {
+                let dir = to_cstring(&filename).expect("invalid file name");
+                if chdir(dir.as_ptr()).check_not_negative().is_ok() {
                     myftw(cnt);
-                    chdir(cstr!(".."));
+                    chdir(b"..\0".as_ptr() as *const _);
                 }
             }
             _ => cnt.count_file(&filename, &statbuf),
@@ -165,7 +167,7 @@ fn main() {
             .args_from_usage("<path> beginning path for traversal")
             .get_matches();
         let path = matches.value_of("path").unwrap();
-        chdir(cstr!(path));
+        with_cstr(path, |path| chdir(path.as_ptr())).expect("invalid path");
         myftw(&mut c);
         println!("{:?}", c);
     }
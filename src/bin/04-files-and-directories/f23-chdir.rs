@@ -4,13 +4,12 @@
 /// chdir to /tmp succeeded
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::chdir;
 use apue::LibcResult;
 
 fn main() {
-    unsafe { chdir(cstr!("/tmp")) }.check_not_negative().expect("chdir failed");
+    unsafe { chdir(b"/tmp\0".as_ptr() as *const _) }.check_not_negative().expect("chdir failed");
     println!("chdir to /tmp succeeded");
 }
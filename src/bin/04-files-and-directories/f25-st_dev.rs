@@ -4,14 +4,16 @@
 /// from rust -> needed to reimplement them in `lib.rs`
 ///
 /// $ f25-st_dev > /dev/null # device numbers are different on ev. machine -> only test ret code
+/// $ f25-st_dev /nonexistent 2>&1
+/// /nonexistent: stat error: No such file or directory
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use std::env::args;
 use libc::{S_IFMT, S_IFCHR, S_IFBLK, stat};
-use apue::{err_sys, major, minor, LibcResult};
+use apue::{err_ret, major, minor, LibcResult};
+use apue::cstr::to_cstring;
 
 fn main() {
     let mut ar = args();
@@ -19,8 +21,9 @@ fn main() {
     let mut buf: stat = unsafe { std::mem::uninitialized() };
     while let Some(a) = ar.next() {
         print!("{}: ", a);
-        if unsafe { stat(cstr!(a), &mut buf) }.check_not_negative().is_err() {
-            err_sys("stat error");
+        let path = to_cstring(&a).expect("invalid path");
+        if unsafe { stat(path.as_ptr(), &mut buf) }.check_not_negative().is_err() {
+            err_ret!("stat error");
             continue;
         }
         print!("dev = {}/{}", major(buf.st_dev), minor(buf.st_dev));
@@ -3,7 +3,6 @@
 /// $ e01-setbuf-setvbuf
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{c_char, FILE, _IOFBF, _IONBF, setvbuf, fopen, strlen};
@@ -20,7 +19,7 @@ unsafe fn setbuf(stream: *mut libc::FILE, buf: *mut c_char) {
 
 fn main() {
     unsafe {
-        let stream = fopen(cstr!("/etc/passwd"), cstr!("r"));
+        let stream = fopen(b"/etc/passwd\0".as_ptr() as *const _, b"r\0".as_ptr() as *const _);
         setbuf(stream, std::ptr::null_mut());
     }
 }
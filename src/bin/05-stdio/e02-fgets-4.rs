@@ -13,10 +13,10 @@
 /// $ rm /tmp/e02.txt
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{fopen, fgets, fputs, printf};
+use apue::cstr::to_cstring;
 
 const BUFLEN: usize = 4;
 
@@ -31,12 +31,14 @@ fn main() {
         args.next(); // skip filename
         let f_in = args.next().unwrap();
         let f_out = args.next().unwrap();
-        let fd_in = fopen(cstr!(f_in), cstr!("r"));
-        let fd_out = fopen(cstr!(f_out), cstr!("w"));
+        let f_in = to_cstring(f_in).expect("invalid path");
+        let f_out = to_cstring(f_out).expect("invalid path");
+        let fd_in = fopen(f_in.as_ptr(), b"r\0".as_ptr() as *const _);
+        let fd_out = fopen(f_out.as_ptr(), b"w\0".as_ptr() as *const _);
 
         let buffer: [u8; BUFLEN] = std::mem::uninitialized();
         while !fgets(buffer.as_ptr() as *mut i8, BUFLEN as i32, fd_in).is_null() {
-            printf(cstr!("buffer = %s\n"), buffer.as_ptr());
+            printf(b"buffer = %s\n\0".as_ptr() as *const _, buffer.as_ptr());
             fputs(buffer.as_ptr() as *mut i8, fd_out);
         }
     }
@@ -31,7 +31,6 @@
 /// mem buffer = lorem ipsuhansaplast!aaaaaaa
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 #[cfg(any(target_os = "macos", target_os= "bsd"))]
@@ -127,7 +126,7 @@ mod fmemopen {
             memset(buf.as_ptr() as *mut c_void, 'a' as c_int, BUFLEN - 2);
             buf[BUFLEN - 2] = '\0' as u8;
             buf[BUFLEN - 1] = 'X' as u8;
-            printf(cstr!("buffer = %s\n"), buf.as_ptr());
+            printf(b"buffer = %s\n\0".as_ptr() as *const _, buf.as_ptr());
             let mut status = MemStream {
                 buffer: &mut buf,
                 pos: 0,
@@ -149,12 +148,12 @@ mod fmemopen {
             let tmpbuf: [u8; 5] = [55; 5];
             fputs("lorem ipsum dolor\0".as_ptr() as _, fd);
             fseek(fd, 10, SEEK_SET);
-            printf(cstr!("mem buffer = %s\n"), status.buffer.as_ptr());
-            fputs(cstr!("hansaplast!") as _, fd);
+            printf(b"mem buffer = %s\n\0".as_ptr() as *const _, status.buffer.as_ptr());
+            fputs(b"hansaplast!\0".as_ptr() as _, fd);
             fseek(fd, 0, SEEK_SET);
-            printf(cstr!("mem buffer = %s\n"), status.buffer.as_ptr());
+            printf(b"mem buffer = %s\n\0".as_ptr() as *const _, status.buffer.as_ptr());
             while !fgets(tmpbuf.as_ptr() as *mut i8, 5, fd).is_null() {
-                printf(cstr!("buffer = %s\n"), tmpbuf.as_ptr());
+                printf(b"buffer = %s\n\0".as_ptr() as *const _, tmpbuf.as_ptr());
             }
         }
     }
@@ -14,7 +14,6 @@
 /// stream = stderr, unbuffered, buffer size = 1, fp = 2
 /// stream = passwd, fully buffered, buffer size = 4096, fp = 3
 #[cfg(any(target_os = "macos"))]
-#[macro_use(cstr)]
 extern crate apue;
 
 
@@ -108,10 +107,10 @@ mod buffered {
             let stdin = __stdinp as *mut FILE;
             let stdout = __stdoutp as *mut FILE;
             let stderr = __stderrp as *mut FILE;
-            let passwd = fopen(cstr!("/etc/passwd"), cstr!("r"));
-            fputs(cstr!("enter any character\n"), stderr);
+            let passwd = fopen(b"/etc/passwd\0".as_ptr() as *const _, b"r\0".as_ptr() as *const _);
+            fputs(b"enter any character\n\0".as_ptr() as *const _, stderr);
             getchar();
-            fputs(cstr!("one line to stderr\n"), stderr);
+            fputs(b"one line to stderr\n\0".as_ptr() as *const _, stderr);
             fgetc(passwd);
             pr_stdio("stdin", stdin);
             pr_stdio("stdout", stdout);
@@ -8,7 +8,7 @@
 /// one line of output
 
 extern crate libc;
-#[macro_use(print_err, cstr)]
+#[macro_use(print_err)]
 extern crate apue;
 
 use std::ffi::{CStr, CString};
@@ -38,7 +38,7 @@ fn main() {
         if fp.is_null() {
             panic!("tmpfile error");
         }
-        fputs(cstr!("one line of output"), fp);
+        fputs(b"one line of output\0".as_ptr() as *const _, fp);
         rewind(fp);
         let line = CString::from_vec_unchecked(Vec::with_capacity(MAXLINE)).into_raw();
         if fgets(line, MAXLINE as i32, fp).is_null() {
@@ -18,7 +18,6 @@
 /// len of string in buf = 46
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{c_void, size_t, c_char, c_uchar, c_int, FILE, SEEK_SET, memset, fprintf, fseek, fflush,
@@ -50,37 +49,37 @@ fn main() {
         memset(buf.as_void(), 'a' as c_int, BSZ - 2);
         buf[BSZ - 2] = '\0' as u8;
         buf[BSZ - 1] = 'X' as u8;
-        let fp = fmemopen(buf.as_ptr() as *mut c_void, BSZ, cstr!("w+"));
+        let fp = fmemopen(buf.as_ptr() as *mut c_void, BSZ, b"w+\0".as_ptr() as *const _);
         if fp.is_null() {
             panic!("fmemopen failed");
         }
-        printf(cstr!("initial buffer contents: %s\n"), buf.as_muti8());
-        printf(cstr!("before flush: %s\n"), buf.as_muti8());
+        printf(b"initial buffer contents: %s\n\0".as_ptr() as *const _, buf.as_muti8());
+        printf(b"before flush: %s\n\0".as_ptr() as *const _, buf.as_muti8());
         fflush(fp);
         // fflush resets the position of the fp, that's a bug:
         // https://sourceware.org/bugzilla/show_bug.cgi?id=20005
         fseek(fp, ("hello world".len() + 1) as i64, SEEK_SET);
-        printf(cstr!("after fflush: %s\n"), buf.as_muti8());
-        printf(cstr!("len of string in buf = %ld\n"),
+        printf(b"after fflush: %s\n\0".as_ptr() as *const _, buf.as_muti8());
+        printf(b"len of string in buf = %ld\n\0".as_ptr() as *const _,
                strlen(buf.as_muti8()));
 
         memset(buf.as_void(), 'b' as c_int, BSZ - 2);
         buf[BSZ - 2] = '\0' as u8;
         buf[BSZ - 1] = 'X' as u8;
-        fprintf(fp, cstr!("hello, world"));
+        fprintf(fp, b"hello, world\0".as_ptr() as *const _);
         fseek(fp, 0, SEEK_SET);
 
-        printf(cstr!("after fseek: %s\n"), buf.as_muti8());
-        printf(cstr!("len of string in buf = %ld\n"),
+        printf(b"after fseek: %s\n\0".as_ptr() as *const _, buf.as_muti8());
+        printf(b"len of string in buf = %ld\n\0".as_ptr() as *const _,
                strlen(buf.as_muti8()));
 
         memset(buf.as_void(), 'c' as c_int, BSZ - 2);
         buf[BSZ - 2] = '\0' as u8;
         buf[BSZ - 1] = 'X' as u8;
-        fprintf(fp, cstr!("hello, world"));
+        fprintf(fp, b"hello, world\0".as_ptr() as *const _);
         fclose(fp);
-        printf(cstr!("after fclose: %s\n"), buf.as_muti8());
-        printf(cstr!("len of string in buf = %ld\n"),
+        printf(b"after fclose: %s\n\0".as_ptr() as *const _, buf.as_muti8());
+        printf(b"len of string in buf = %ld\n\0".as_ptr() as *const _,
                strlen(buf.as_muti8()));
     }
 }
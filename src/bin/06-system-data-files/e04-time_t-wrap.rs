@@ -2,7 +2,7 @@
 /// After it wraps around, what happens?
 
 extern crate libc;
-#[macro_use(cstr, as_char)]
+#[macro_use(as_char)]
 extern crate apue;
 
 use libc::{tm, time_t, c_char, size_t, printf};
@@ -25,9 +25,9 @@ fn main() {
             println!("{:?}", t - 1);
             strftime(as_char!(buf),
                      1024,
-                     cstr!("%a %b %d, %Y"),
+                     b"%a %b %d, %Y\0".as_ptr() as *const _,
                      localtime(&mut (t - 1)));
-            printf(cstr!("%s\n"), buf.as_ptr());
+            printf(b"%s\n\0".as_ptr() as *const _, buf.as_ptr());
             t *= 2;
         }
     }
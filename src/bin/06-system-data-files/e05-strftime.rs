@@ -3,7 +3,6 @@
 /// variable to different values and see what happens.
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{tm, time_t, c_char, size_t, printf};
@@ -26,9 +25,9 @@ fn main() {
         time(&mut t);
         strftime(buf.as_mut_ptr(),
                  256,
-                 cstr!("%a %b %e %H:%M:%S %Z %Y"),
+                 b"%a %b %e %H:%M:%S %Z %Y\0".as_ptr() as *const _,
                  localtime(&t));
-        printf(cstr!("%s\n"), buf.as_ptr());
+        printf(b"%s\n\0".as_ptr() as *const _, buf.as_ptr());
     }
 }
 
@@ -3,7 +3,7 @@
 /// the current date and time.
 
 extern crate libc;
-#[macro_use(cstr, as_char)]
+#[macro_use(as_char)]
 extern crate apue;
 
 use libc::{tm, time_t, c_char, size_t, printf, exit};
@@ -27,16 +27,16 @@ fn main() {
         let mut t: time_t = uninitialized();
         time(&mut t);
         let tmp = localtime(&mut t);
-        let fmt = "time and date: %r, %a %b %d, %Y";
-        if strftime(as_char!(buf1), 16, cstr!(fmt), tmp) == 0 {
-            printf(cstr!("buffer length 16 is too small\n"));
+        let fmt = b"time and date: %r, %a %b %d, %Y\0".as_ptr() as *const c_char;
+        if strftime(as_char!(buf1), 16, fmt, tmp) == 0 {
+            printf(b"buffer length 16 is too small\n\0".as_ptr() as *const _);
         } else {
-            printf(cstr!("%s\n"), buf1.as_ptr());
+            printf(b"%s\n\0".as_ptr() as *const _, buf1.as_ptr());
         }
-        if strftime(as_char!(buf2), 64, cstr!(fmt), tmp) == 0 {
-            printf(cstr!("buffer length 64 is too small\n"));
+        if strftime(as_char!(buf2), 64, fmt, tmp) == 0 {
+            printf(b"buffer length 64 is too small\n\0".as_ptr() as *const _);
         } else {
-            printf(cstr!("%s\n"), buf2.as_ptr());
+            printf(b"%s\n\0".as_ptr() as *const _, buf2.as_ptr());
         }
         exit(0);
     }
@@ -8,7 +8,6 @@
 /// second exit handler
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 extern crate errno;
 
@@ -22,11 +21,11 @@ fn my_atexit(f: Exitfunc) -> std::io::Result<i32> {
 }
 
 extern "C" fn my_exit1() {
-    unsafe { printf(cstr!("first exit handler\n")) };
+    unsafe { printf(b"first exit handler\n\0".as_ptr() as *const _) };
 }
 
 extern "C" fn my_exit2() {
-    unsafe { printf(cstr!("second exit handler\n")) };
+    unsafe { printf(b"second exit handler\n\0".as_ptr() as *const _) };
 }
 
 fn main() {
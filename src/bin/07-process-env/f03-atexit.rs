@@ -11,7 +11,6 @@
 /// second exit handler
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 extern crate errno;
 
@@ -19,11 +18,11 @@ use apue::LibcResult;
 use libc::{atexit, printf};
 
 extern "C" fn my_exit1() {
-    unsafe { printf(cstr!("first exit handler\n")) };
+    unsafe { printf(b"first exit handler\n\0".as_ptr() as *const _) };
 }
 
 extern "C" fn my_exit2() {
-    unsafe { printf(cstr!("second exit handler\n")) };
+    unsafe { printf(b"second exit handler\n\0".as_ptr() as *const _) };
 }
 
 fn main() {
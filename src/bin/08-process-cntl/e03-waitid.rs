@@ -9,18 +9,26 @@
 /// - the same program in C (e.g. http://bit.ly/2iSLMid) yields the same results. On Linux
 ///   it works though, but on MacOs it prints signal number = 0 also for the 2nd and 3rd line
 /// - on Linux the field `si_status` is missing in libc, since the headers on Linux are a mess..
+///   Newer libc versions offer `si_status()` on both platforms, so the decoding moved into
+///   `apue::wait::WaitStatus::from_siginfo`
 
 extern crate libc;
 extern crate apue;
 
 use libc::{exit, fork, abort, raise, siginfo_t, SIGFPE};
-use apue::my_libc::{waitid, idtype_t, WEXITED, CLD_DUMPED, CLD_EXITED, CLD_STOPPED};
+use apue::my_libc::{waitid, idtype_t, WEXITED};
+use apue::wait::WaitStatus;
 use apue::LibcResult;
 use std::panic;
 
 fn handle_panic(e: &panic::PanicInfo) {
-    match e.payload().downcast_ref::<String>() {
-        Some(as_string) if as_string == "attempt to divide by zero" => {
+    // depending on the compiler version the payload is a String or a &str
+    let msg = match e.payload().downcast_ref::<String>() {
+        Some(as_string) => Some(as_string.as_str()),
+        None => e.payload().downcast_ref::<&str>().map(|s| *s),
+    };
+    match msg {
+        Some("attempt to divide by zero") => {
             unsafe { raise(SIGFPE) };
         }
         _ => {
@@ -29,35 +37,10 @@ fn handle_panic(e: &panic::PanicInfo) {
     }
 }
 
-#[cfg(target_os = "macos")]
 fn pr_exit(i: siginfo_t) {
-    let sigcode = i.si_code;
-    let status = i.si_status;
-    match sigcode {
-        CLD_EXITED => println!("normal termination, exit status = {}", status),
-        CLD_STOPPED => println!("child stoped, signal number = {}", status),
-        _ => {
-            println!("abnormal termination, signal number = {}", status);
-            if sigcode == CLD_DUMPED {
-                println!("(core file generated)");
-            }
-        }
-    }
-}
-
-/// si_status is missing in Linux in libc, no wonder: header files is a mess there
-#[cfg(target_os = "linux")]
-fn pr_exit(i: siginfo_t) {
-    let sigcode = i.si_code;
-    match sigcode {
-        CLD_EXITED => println!("normal termination"),
-        CLD_STOPPED => println!("child stoped"),
-        _ => {
-            println!("abnormal termination");
-            if sigcode == CLD_DUMPED {
-                println!("(core file generated)");
-            }
-        }
+    match WaitStatus::from_siginfo(&i) {
+        Some(status) => println!("{}", status),
+        None => println!("no child status"),
     }
 }
 
@@ -85,8 +68,9 @@ fn main() {
         pid = fork().check_not_negative().expect("fork error");
         if pid == 0 {
             // child
-            let mut z = 1;
-            z -= 1;
+            // newer compilers reject a constant division by zero, so hide the zero
+            // behind the argument count (no arguments are passed)
+            let z = std::env::args().count() as i32 - 1;
             pid = 1 / z; // divide by 0 generates SIGFPE
         }
 
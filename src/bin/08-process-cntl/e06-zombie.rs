@@ -6,7 +6,6 @@
 /// More details here: http://stackoverflow.com/questions/41427982
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{fork, sleep, exit, system};
@@ -19,6 +18,6 @@ fn main() {
             exit(0);
         }
         sleep(99);
-        system(cstr!("ps -fo pid,ppid,state,tty,command"));
+        system(b"ps -fo pid,ppid,state,tty,command\0".as_ptr() as *const _);
     }
 }
@@ -13,7 +13,6 @@
 /// close-on-exec is not set
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{opendir, fcntl, open, close, closedir, F_GETFD, FD_CLOEXEC, O_RDONLY};
@@ -32,13 +31,13 @@ unsafe fn pr_flags(fd: i32) {
 
 fn main() {
     unsafe {
-        let dp = opendir(cstr!("/"));
+        let dp = opendir(b"/\0".as_ptr() as *const _);
         let dfd = dirfd(dp);
         assert!(!dp.is_null(), "can't open root dir");
         readdir(dp).check_not_null().ok(); // just read one entry and discard it
         pr_flags(dfd);
         let fd =
-            open(cstr!("/"), O_RDONLY).check_not_negative().expect("cannot open root for reading");
+            open(b"/\0".as_ptr() as *const _, O_RDONLY).check_not_negative().expect("cannot open root for reading");
         pr_flags(fd);
         close(fd);
         closedir(dp);
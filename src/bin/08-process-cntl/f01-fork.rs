@@ -8,7 +8,6 @@
 /// glob = 7, var = 89
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{c_int, STDOUT_FILENO, write, fork, usleep, printf, getpid};
@@ -26,12 +25,12 @@ fn main() {
         // automatic variable on the stack
         let mut var: i8 = 88;
         if write(STDOUT_FILENO, buf.as_ptr() as _, buf.len() as _) != buf.len() as _ {
-            err_sys("write error");
+            err_sys!("write error");
         }
         // this is line buffered when running in shell (writing to stdout)
         // if stdout is going into a file (e.g. starting from intellij) it is fully buffered
         // and hence output is delayed after fork -> printed twice
-        printf(cstr!("before fork\n"));
+        printf(b"before fork\n\0".as_ptr() as *const _);
         if let Ok(pid) = fork().check_not_negative() {
             match pid {
                 0 => {
@@ -42,12 +41,12 @@ fn main() {
                     usleep(10);
                 }
             }
-            printf(cstr!("pid = %ld, glob = %d, var = %d\n"),
+            printf(b"pid = %ld, glob = %d, var = %d\n\0".as_ptr() as *const _,
                    getpid(),
                    GLOBVAR,
                    var as c_int);
         } else {
-            err_sys("fork error");
+            err_sys!("fork error");
         }
     }
 }
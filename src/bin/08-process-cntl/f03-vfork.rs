@@ -17,7 +17,6 @@
 /// glob = 7, var = 89
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{printf, getpid, c_int, _exit};
@@ -30,7 +29,7 @@ static mut GLOBVAR: i64 = 6;
 fn main() {
     unsafe {
         let mut var = Box::new(88);
-        printf(cstr!("before vfork\n"));
+        printf(b"before vfork\n\0".as_ptr() as *const _);
         match vfork().check_not_negative().expect("vfork error") {
             0 => {
                 // child
@@ -39,7 +38,7 @@ fn main() {
                 _exit(0);
             }
             _ => {
-                printf(cstr!("pid = %ld, glob = %d, var = %d\n"),
+                printf(b"pid = %ld, glob = %d, var = %d\n\0".as_ptr() as *const _,
                        getpid(),
                        GLOBVAR,
                        *var as c_int);
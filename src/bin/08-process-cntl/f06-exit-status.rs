@@ -9,14 +9,14 @@
 /// mac only:
 /// $ f06-exit-status
 /// normal termination, exit status = 7
-/// abnormal termination, signal number = 6
-/// abnormal termination, signal number = 8
+/// abnormal termination, signal number = 6 (SIGABRT)
+/// abnormal termination, signal number = 8 (SIGFPE)
 ///
 /// linux only:
 /// $ f06-exit-status
 /// normal termination, exit status = 7
-/// abnormal termination, signal number = 6  (core file generated)
-/// abnormal termination, signal number = 8  (core file generated)
+/// abnormal termination, signal number = 6 (SIGABRT) (core file generated)
+/// abnormal termination, signal number = 8 (SIGFPE) (core file generated)
 
 extern crate libc;
 extern crate apue;
@@ -28,8 +28,13 @@ use std::panic;
 
 
 fn handle_panic(e: &panic::PanicInfo) {
-    match e.payload().downcast_ref::<String>() {
-        Some(as_string) if as_string == "attempt to divide by zero" => {
+    // depending on the compiler version the payload is a String or a &str
+    let msg = match e.payload().downcast_ref::<String>() {
+        Some(as_string) => Some(as_string.as_str()),
+        None => e.payload().downcast_ref::<&str>().map(|s| *s),
+    };
+    match msg {
+        Some("attempt to divide by zero") => {
             unsafe { raise(SIGFPE) };
         }
         _ => {
@@ -49,7 +54,7 @@ fn main() {
         }
         if wait(&mut status) != pid {
             // wait for child
-            err_sys("wait error");
+            err_sys!("wait error");
         }
         pr_exit(status);
 
@@ -60,20 +65,21 @@ fn main() {
         }
         if wait(&mut status) != pid {
             // wait for child
-            err_sys("wait error");
+            err_sys!("wait error");
         }
         pr_exit(status);
 
         pid = fork().check_not_negative().expect("fork error");
         if pid == 0 {
             // child
-            let mut z = 1;
-            z -= 1;
+            // newer compilers reject a constant division by zero, so hide the zero
+            // behind the argument count (no arguments are passed)
+            let z = std::env::args().count() as i32 - 1;
             status /= z; // divide by 0 generates SIGFPE
         }
 
         if wait(&mut status) != pid {
-            err_sys("wait error");
+            err_sys!("wait error");
         }
         pr_exit(status);
     }
@@ -32,7 +32,7 @@ unsafe fn doit() {
         _ => {
             // wait for first child
             if waitpid(pid, std::ptr::null_mut(), 0) != pid {
-                err_sys("waitpid error");
+                err_sys!("waitpid error");
             }
         }
     }
@@ -1,10 +1,20 @@
-/// Figure 8.12 Program with a race condition
+/// Figure 8.12 Program with a race condition, and
+/// Figure 8.13 Modification of Figure 8.12 to avoid race condition
 ///
 /// Takeaway: on OSX it needed at least usleep(20) in order
 /// to experience the race condition
 ///
+/// Pass `signal` or `pipe` to let the parent go first using TELL_WAIT from
+/// `apue::sync_parent_child` (Figure 10.24 or Figure 15.7).
+///
 /// $ f12-race-condition | awk 'END{print NR}'
 /// 2
+/// $ f12-race-condition signal
+/// output from parent
+/// output from child
+/// $ f12-race-condition pipe
+/// output from parent
+/// output from child
 
 extern crate libc;
 extern crate apue;
@@ -12,6 +22,7 @@ extern crate apue;
 use libc::{c_char, FILE, STDOUT_FILENO, fork, setbuf, fdopen, usleep};
 use apue::LibcResult;
 use apue::my_libc::putc;
+use apue::sync_parent_child;
 
 
 unsafe fn charatatime(out: *mut FILE, s: &str) {
@@ -22,14 +33,25 @@ unsafe fn charatatime(out: *mut FILE, s: &str) {
 }
 
 fn main() {
+    let tell_wait = std::env::args().nth(1).map(|name| {
+        sync_parent_child::by_name(&name).expect("TELL_WAIT error")
+    });
     unsafe {
         // set unbuffered
         let stdout = fdopen(STDOUT_FILENO, &('w' as c_char));
         setbuf(stdout, std::ptr::null_mut());
         let pid = fork().check_not_negative().expect("fork error");
-        match pid {
-            0 => charatatime(stdout, "output from child \n"),
-            _ => charatatime(stdout, "output from parent \n"),
+        match (pid, tell_wait) {
+            (0, None) => charatatime(stdout, "output from child \n"),
+            (_, None) => charatatime(stdout, "output from parent \n"),
+            (0, Some(tw)) => {
+                tw.wait_parent().expect("WAIT_PARENT error"); // parent goes first
+                charatatime(stdout, "output from child \n");
+            }
+            (_, Some(tw)) => {
+                charatatime(stdout, "output from parent \n");
+                tw.tell_child(pid).expect("TELL_CHILD error");
+            }
         }
     }
 }
@@ -1,9 +1,13 @@
 /// Figure 8.16 Example of exec functions
 ///
-/// This probably went a bit overboard with cstr! and check_not_negative :) still,
+/// This probably went a bit overboard with check_not_negative :) still,
 /// worked pretty out of the box (except: first I accidentally called f16-exec and then wondered
 /// why fork suddenly dies with "Resource temporarily unavailable"..)
 ///
+/// Instead of the variadic execle and execlp this uses `apue::exec::execve` and
+/// `execvp`, which take the arguments and the environment as slices and only return
+/// the error.
+///
 /// $ f16-exec | cat | head -5
 /// argv[0] = echoall
 /// argv[1] = myarg1
@@ -12,31 +16,25 @@
 /// PATH=/tmp
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
-extern crate errno;
 
-use libc::{fork, waitpid, c_char};
+use libc::{fork, waitpid};
 use apue::LibcResult;
-use apue::my_libc::{execle, execlp};
+use apue::exec::{execve, execvp};
 
 fn main() {
-    unsafe {
-        let mut curpath = std::env::current_exe().unwrap();
-        curpath.pop();
-        curpath.push("f17-echo-all");
+    let mut curpath = std::env::current_exe().unwrap();
+    curpath.pop();
+    curpath.push("f17-echo-all");
 
+    unsafe {
         let pid = fork().check_not_negative().expect("fork error");
         match pid {
             0 => {
-                execle(cstr!(curpath.to_str().unwrap()),
-                       cstr!("echoall"),
-                       cstr!("myarg1"),
-                       cstr!("MY ARG2"),
-                       0 as *const c_char,
-                       [cstr!("USER=unkown"), cstr!("PATH=/tmp"), 0 as *const c_char].as_ptr())
-                    .check_not_negative()
-                    .expect(&format!("execle error: {}", errno::errno()));
+                let err = execve(&curpath,
+                                 &["echoall", "myarg1", "MY ARG2"],
+                                 &["USER=unkown", "PATH=/tmp"]);
+                panic!("execve error: {}", err);
             }
             _ => {
                 waitpid(pid, std::ptr::null_mut(), 0).check_not_negative().expect("wait error");
@@ -45,12 +43,8 @@ fn main() {
 
         let pid = fork().check_not_negative().expect("fork error");
         if pid == 0 {
-            execlp(cstr!("f17-echo-all"),
-                   cstr!("echoall"),
-                   cstr!("only 1 arg"),
-                   0 as *const c_char)
-                .check_not_negative()
-                .expect(&format!("execlp error: {}", errno::errno()));
+            let err = execvp("f17-echo-all", &["echoall", "only 1 arg"]);
+            panic!("execvp error: {}", err);
         }
     }
 }
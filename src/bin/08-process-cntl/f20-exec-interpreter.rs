@@ -9,21 +9,102 @@
 /// argv[2] =
 /// argv[3] = myarg1
 /// argv[4] = MY ARG2
+///
+/// `--show file [arg...]` prints the argument vector the interpreter of `file` would get
+/// (`apue::exec::interpreter`). Linux passes everything after the interpreter's pathname
+/// as one argument:
+///
+/// $ printf '#!  /bin/sh -e -x  \necho hi\n' > /tmp/f20-script; f20-exec-interpreter --show /tmp/f20-script a b
+/// /bin/sh
+/// -e -x
+/// /tmp/f20-script
+/// a
+/// b
+///
+/// `--execvp file [arg...]` runs `file` with `apue::exec::execvp`. A file without `#!`
+/// line is run by /bin/sh, a file that isn't executable is skipped, but reported if
+/// nothing else is found in PATH:
+///
+/// $ printf 'echo $0 $1\n' > /tmp/f20-noshebang; chmod +x /tmp/f20-noshebang; PATH=/nonexistent:/tmp $(command -v f20-exec-interpreter) --execvp f20-noshebang x
+/// /tmp/f20-noshebang x
+/// $ touch /tmp/f20-noexec; chmod -x /tmp/f20-noexec; PATH=/tmp $(command -v f20-exec-interpreter) --execvp f20-noexec 2>&1
+/// execvp f20-noexec: Permission denied (os error 13)
+/// ERROR: return code 1
+/// $ PATH=/tmp $(command -v f20-exec-interpreter) --execvp f20-nonexistent 2>&1
+/// execvp f20-nonexistent: No such file or directory (os error 2)
+/// ERROR: return code 1
+///
+/// `--fexecve file [arg...]` opens `file` and executes the descriptor with
+/// `apue::exec::fexecve`, the path is only used by open:
+///
+/// $ f20-exec-interpreter --fexecve /bin/echo echo via fexecve; rm -f /tmp/f20-script /tmp/f20-noshebang /tmp/f20-noexec
+/// via fexecve
 
-extern crate libc;
-#[macro_use(cstr)]
+#[macro_use(err_quit)]
 extern crate apue;
-extern crate errno;
+extern crate libc;
 
 use std::io::prelude::*;
 use std::fs::OpenOptions;
+use std::ffi::OsString;
 use std::os::unix::fs::OpenOptionsExt;
+use std::process::exit;
 
-use libc::{fork, waitpid, c_char};
+use libc::{fork, waitpid, O_RDONLY};
 use apue::LibcResult;
-use apue::my_libc::execl;
+use apue::exec::{execv, execvp, fexecve, interpreter};
+use apue::fd::Fd;
+
+fn usage() -> ! {
+    eprintln!("usage: f20-exec-interpreter [--show|--execvp|--fexecve file [arg...]]");
+    exit(1);
+}
+
+fn show(file: &str, args: &[String]) {
+    match interpreter(file) {
+        Ok(Some(interp)) => {
+            let mut argv = vec![file.to_string()];
+            argv.extend_from_slice(args);
+            for arg in interp.argv(file, &argv) {
+                println!("{}", arg.to_string_lossy());
+            }
+        }
+        Ok(None) => println!("{} is no interpreter file", file),
+        Err(e) => err_quit!("{}: {}", file, e),
+    }
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 {
+        if args.len() < 3 {
+            usage();
+        }
+        match args[1].as_str() {
+            "--show" => show(&args[2], &args[3..]),
+            "--execvp" => {
+                let err = execvp(&args[2], &args[2..]);
+                err_quit!("execvp {}: {}", args[2], err);
+            }
+            "--fexecve" => {
+                let fd = Fd::open(&args[2], O_RDONLY, 0)
+                    .unwrap_or_else(|e| err_quit!("can't open {}: {}", args[2], e));
+                let envp: Vec<OsString> = std::env::vars_os()
+                    .map(|(name, value)| {
+                        let mut var = name;
+                        var.push("=");
+                        var.push(value);
+                        var
+                    })
+                    .collect();
+                let err = fexecve(&fd, &args[3..], &envp);
+                err_quit!("fexecve {}: {}", args[2], err);
+            }
+            _ => usage(),
+        }
+        return;
+    }
+
     let curexe = std::env::current_exe().unwrap();
     let testinterp = curexe.with_file_name("testinterp");
     {
@@ -44,13 +125,8 @@ fn main() {
         let pid = fork().check_not_negative().expect("cannot fork");
         match pid {
             0 => {
-                execl(cstr!(testinterp.to_str().unwrap()),
-                      cstr!("testinterp"),
-                      cstr!("myarg1"),
-                      cstr!("MY ARG2"),
-                      0 as *const c_char)
-                    .check_not_negative()
-                    .expect(&format!("execl error: {}", errno::errno()));
+                let err = execv(&testinterp, &["testinterp", "myarg1", "MY ARG2"]);
+                panic!("execv error: {}", err);
             }
             _ => {
                 waitpid(pid, std::ptr::null_mut(), 0).check_not_negative().expect("waitpid error");
@@ -0,0 +1,50 @@
+/// Figure 8.23 Calling the system function, with `apue::process::Command`
+///
+/// The commands of f23-system, but started with `Command`: the program is executed
+/// directly instead of by `/bin/sh -c`, so a missing program is an error of `spawn`
+/// instead of exit status 127. The environment and the working directory of the child
+/// are set up before exec. The child gets SIGPIPE back with its default action, so
+/// `yes` is killed quietly when `head` exits instead of complaining about EPIPE.
+///
+/// $ f23-command 2>&1
+/// normal termination, exit status = 0
+/// spawn nosuchcommand: No such file or directory (os error 2)
+/// normal termination, exit status = 44
+/// hello from the environment
+/// normal termination, exit status = 0
+/// /tmp
+/// normal termination, exit status = 0
+/// y
+/// normal termination, exit status = 0
+
+extern crate apue;
+
+use apue::process::Command;
+
+fn main() {
+    let status = Command::new("true").status().expect("status error");
+    println!("{}", status);
+
+    if let Err(e) = Command::new("nosuchcommand").spawn() {
+        println!("spawn nosuchcommand: {}", e);
+    }
+
+    let status = Command::new("sh").args(&["-c", "exit 44"]).status().expect("status error");
+    println!("{}", status);
+
+    let status = Command::new("sh")
+        .args(&["-c", "echo $GREETING"])
+        .env("GREETING", "hello from the environment")
+        .status()
+        .expect("status error");
+    println!("{}", status);
+
+    let status = Command::new("pwd").current_dir("/tmp").status().expect("status error");
+    println!("{}", status);
+
+    let mut child = Command::new("sh")
+        .args(&["-c", "yes | head -1"])
+        .spawn()
+        .expect("spawn error");
+    println!("{}", child.wait().expect("wait error"));
+}
@@ -9,12 +9,12 @@
 /// normal termination, exit status = 0
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{system, exit};
 use std::env;
 use apue::{LibcResult, pr_exit};
+use apue::cstr::to_cstring;
 
 fn main() {
     let mut args = env::args();
@@ -23,7 +23,7 @@ fn main() {
         unsafe { exit(1) };
     }
     args.next(); // skip exe-name
-    let status =
-        unsafe { system(cstr!(args.next().unwrap())).check_not_negative().expect("system() error") };
+    let cmd = to_cstring(args.next().unwrap()).expect("invalid command");
+    let status = unsafe { system(cmd.as_ptr()) }.check_not_negative().expect("system() error");
     pr_exit(status);
 }
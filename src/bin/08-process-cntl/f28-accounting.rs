@@ -1,10 +1,10 @@
 /// Figure 2.28: Program to generate accounting data
-#[macro_use(cstr)]
 extern crate apue;
 extern crate libc;
 
-use libc::{sleep, fork, exit, abort, c_char, kill, getpid, SIGKILL};
+use libc::{sleep, fork, exit, abort, kill, getpid, SIGKILL};
 use apue::LibcResult;
+use apue::cstr::{to_cstring, CStrArgs};
 use apue::my_libc::execl;
 
 unsafe fn _fork() -> i32 {
@@ -26,11 +26,9 @@ fn main() {
         }
         // 2nd child
         if _fork() > 0 {
-            execl(cstr!("/bin/dd"),
-                  cstr!("dd"),
-                  cstr!("if=/etc/passwd"),
-                  cstr!("of=/dev/stdout"),
-                  0 as *const c_char)
+            let path = to_cstring("/bin/dd").unwrap();
+            let argv = CStrArgs::argv(["dd", "if=/etc/passwd", "of=/dev/stdout"]).unwrap();
+            execl(path.as_ptr(), argv.ptr(0), argv.ptr(1), argv.ptr(2), argv.ptr(3))
                 .check_not_negative()
                 .expect("execl error");
             exit(7);
@@ -27,7 +27,6 @@
 // .. do a few commands
 // $ f29-acdata /var/account/acct
 // $ sudo accton # disable
-#[macro_use(cstr)]
 extern crate apue;
 extern crate libc;
 
@@ -36,6 +35,7 @@ use libc::{fopen, fread, c_char, c_ushort, c_uint, c_void, ferror};
 use libc::{c_uchar, c_int};
 
 use apue::{LibcPtrResult, err_sys, array_to_string};
+use apue::cstr::to_cstring;
 
 const AFORK: u8 = 0x01; // fork'd but not exec'd
 const ASU: u8 = 0x02; // used super-user permissions
@@ -110,7 +110,8 @@ fn main() {
             return;
         }
         let fname = args.next_back().unwrap();
-        let fp = fopen(cstr!(fname.clone()), cstr!("r"))
+        let path = to_cstring(&fname).expect("invalid path");
+        let fp = fopen(path.as_ptr(), b"r\0".as_ptr() as *const _)
             .check_not_null()
             .expect(&format!("can't open {}", fname));
         let mut acdata: acct = std::mem::zeroed();
@@ -134,7 +135,7 @@ fn main() {
                      flag2str(acdata, ASU, "S"))
         }
         if ferror(fp) != 0 {
-            err_sys("read error");
+            err_sys!("read error");
         }
     }
 }
@@ -58,7 +58,7 @@ fn main() {
                      adj);
             set_errno(Errno(0));
             if nice(adj) == -1 && errno().0 != 0 {
-                err_sys("child set scheduling priority");
+                err_sys!("child set scheduling priority");
             }
             println!("now child nice value is {}", nice(0) + nzero);
             "child"
@@ -14,11 +14,11 @@
 /// normal termination, exit status = 0
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{setbuf, sysconf, system, clock_t, _SC_CLK_TCK};
 use apue::{pr_exit, LibcResult};
+use apue::cstr::to_cstring;
 use apue::my_libc::{stdout, times, tms};
 use std::mem::uninitialized;
 
@@ -29,7 +29,8 @@ unsafe fn do_cmd(cmd: &str) {
     // clock_t (return type of times) is unsigned on macos (and probably
     // bsd in general) -> don't check for -1
     let start = times(&mut tmsstart);
-    let status = system(cstr!(cmd)).check_not_negative().expect("system() error");
+    let cmd = to_cstring(cmd).expect("invalid command");
+    let status = system(cmd.as_ptr()).check_not_negative().expect("system() error");
     let end = times(&mut tmsend);
     pr_times(end - start, &tmsstart, &tmsend);
     pr_exit(status);
@@ -23,7 +23,6 @@
 // $ rm /tmp/f12-orph.txt
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 extern crate errno;
 
@@ -34,7 +33,7 @@ use apue::my_libc::stdout;
 
 extern "C" fn sig_hup(_: c_int) {
     unsafe {
-        printf(cstr!("SIGHUP received, pid=%ld\n"), getpid());
+        printf(b"SIGHUP received, pid=%ld\n\0".as_ptr() as *const _, getpid());
         fflush(stdout);
     }
 }
@@ -1,23 +1,44 @@
 /// Exercise 10.2: Implement the sig2str function described in Section 10.22.
 ///
+/// The first version read the descriptions out of `sys_siglist` which newer glibc versions
+/// don't export anymore. The implementation now lives in `apue::signals` and works with
+/// a table of its own.
+///
 /// $ e02-sig2str
 /// Signal: Interrupt
+/// Name: SIGINT
+/// Number of INT: 2
+///
+/// With arguments the numbers of the given names are printed instead. Offsets that
+/// leave the realtime range are unknown:
+///
+/// linux only:
+/// $ e02-sig2str SIGTERM RTMIN+1 RTMAX-1 RTMIN+2147483647 RTMAX-2147483647 FOO
+/// SIGTERM: 15
+/// RTMIN+1: 35
+/// RTMAX-1: 63
+/// RTMIN+2147483647: unknown
+/// RTMAX-2147483647: unknown
+/// FOO: unknown
 
 extern crate apue;
 extern crate libc;
 
-use libc::{SIGINT, c_char};
-
-extern "C" {
-    static sys_siglist: [*const c_char; 65usize];
-}
-
-fn sig2str(signo: i32) -> &'static str {
-    unsafe {
-        std::ffi::CStr::from_ptr(sys_siglist[signo as usize]).to_str().expect("invalid utf8 string")
-    }
-}
+use libc::SIGINT;
+use apue::signals::{sig2str, str2sig, description};
 
 fn main() {
-    println!("Signal: {}", sig2str(SIGINT));
+    let names: Vec<String> = std::env::args().skip(1).collect();
+    if !names.is_empty() {
+        for name in names {
+            match str2sig(&name) {
+                Some(signo) => println!("{}: {}", name, signo),
+                None => println!("{}: unknown", name),
+            }
+        }
+        return;
+    }
+    println!("Signal: {}", description(SIGINT).unwrap());
+    println!("Name: {}", sig2str(SIGINT).unwrap());
+    println!("Number of INT: {}", str2sig("INT").unwrap());
 }
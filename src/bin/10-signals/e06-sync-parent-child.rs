@@ -9,18 +9,23 @@
 /// right. I initially tried with fetch_xor until I learned that it *always*
 /// returns the previous value.
 ///
+/// The synchronization functions are now behind the `TellWait` trait, pass `pipe`
+/// to use the pipe based version of Figure 15.7 instead of signals.
+///
 /// $ rm -f /tmp/e06-sync.txt && e06-sync-parent-child && cat /tmp/e06-sync.txt
 /// 200
+/// $ rm -f /tmp/e06-sync.txt && e06-sync-parent-child pipe && cat /tmp/e06-sync.txt
+/// 200
 
 extern crate apue;
 extern crate libc;
 
 use std::io::prelude::*;
-use libc::{fork, getppid};
+use libc::{fork, getppid, waitpid};
 use std::fs::{File, OpenOptions};
 use std::io::{Error, SeekFrom};
 use apue::LibcResult;
-use apue::sync_parent_child::*;
+use apue::sync_parent_child;
 
 fn increase_file_counter() -> Result<(), Error> {
     let mut f = OpenOptions::new().read(true)
@@ -35,29 +40,29 @@ fn increase_file_counter() -> Result<(), Error> {
 }
 
 fn main() {
-    unsafe {
-        {
-            let mut f = File::create("/tmp/e06-sync.txt").unwrap();
-            f.write_all(b"0").unwrap();
+    let variant = std::env::args().nth(1).unwrap_or("signal".to_string());
+    {
+        let mut f = File::create("/tmp/e06-sync.txt").unwrap();
+        f.write_all(b"0").unwrap();
+    }
+    let tw = sync_parent_child::by_name(&variant).expect("TELL_WAIT error");
+    let pid = unsafe { fork() }.check_not_negative().expect("fork error");
+    if pid == 0 {
+        // child
+        let ppid = unsafe { getppid() };
+        for _ in 1..101 {
+            // child goes first
+            increase_file_counter().expect("file read/write error");
+            tw.tell_parent(ppid).expect("TELL_PARENT error");
+            tw.wait_parent().expect("WAIT_PARENT error");
         }
-        let oldmask = tell_wait();
-        let pid = fork().check_not_negative().expect("fork error");
-        if pid == 0 {
-            // child
-            let ppid = getppid();
-            for _ in 1..101 {
-                // child goes first
-                increase_file_counter().expect("file read/write error");
-                tell_parent(ppid);
-                wait_parent(oldmask);
-            }
-        } else {
-            // parent
-            for _ in 1..101 {
-                wait_child(oldmask);
-                increase_file_counter().expect("file read/write error");
-                tell_child(pid);
-            }
+    } else {
+        // parent
+        for _ in 1..101 {
+            tw.wait_child().expect("WAIT_CHILD error");
+            increase_file_counter().expect("file read/write error");
+            tw.tell_child(pid).expect("TELL_CHILD error");
         }
+        unsafe { waitpid(pid, std::ptr::null_mut(), 0) }.check_not_negative().expect("waitpid error");
     }
 }
@@ -14,7 +14,6 @@
 /// - if woken up too late (check with time()) sleep for less then 60 seconds
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{sleep, time, localtime, c_char};
@@ -30,7 +29,7 @@ fn main() {
             sleep(60);
             time(&mut t);
             let tm = localtime(&t);
-            strftime(buf.as_mut_ptr(), 256, cstr!("%a %b %e %H:%M:%S %Z %Y"), tm);
+            strftime(buf.as_mut_ptr(), 256, b"%a %b %e %H:%M:%S %Z %Y\0".as_ptr() as *const _, tm);
             println!("{:?}, tm_sec={}",
                      CStr::from_ptr(buf.as_ptr()),
                      (*tm).tm_sec);
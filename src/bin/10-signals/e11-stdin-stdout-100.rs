@@ -22,16 +22,17 @@
 ///   thread 'main' panicked at 'failed printing to stdout: File too large
 ///   (os error 27)', .../src/libstd/io/stdio.rs:693 note: Run with `RUST_BACKTRACE=1` for
 ///   a backtrace. fatal runtime error: failed to initiate panic, error 5
+/// - `apue::io::writen` with `OnIntr::Stop` returns after the signal and tells how
+///   many bytes of the buffer were written before the error
 
 extern crate libc;
 #[macro_use(as_void)]
 extern crate apue;
-extern crate errno;
 
 use libc::{SIGXFSZ, STDIN_FILENO, STDOUT_FILENO, c_int, rlimit, RLIMIT_FSIZE};
-use libc::{read, write, setrlimit};
+use libc::{read, setrlimit};
 use apue::{LibcResult, signal_intr};
-use errno::errno;
+use apue::io::{writen, OnIntr};
 use std::io::Write;
 
 fn exceed_filesize_limit(_: c_int) {
@@ -50,8 +51,8 @@ fn main() {
         setrlimit(RLIMIT_FSIZE, &limit);
         let buf = vec![0; buffsize];
         while let Ok(n) = read(STDIN_FILENO, as_void!(buf), buffsize).check_positive() {
-            if write(STDOUT_FILENO, as_void!(buf), n as _) != n {
-                println!("write error: {}", errno());
+            if let Err(e) = writen(&STDOUT_FILENO, &buf[..n as usize], OnIntr::Stop) {
+                println!("write error: {}", e);
                 break;
             }
             num_loops += 1;
@@ -12,7 +12,7 @@
 ///   the memory is allocated in the stack and `getrlimit(RLIMIT_STACK)` returned
 ///   a max stack size of 2^23 bytes -> the max size of a buffer in the stack
 ///   is 2^22 because some bytes are needed for the code and the other vars.
-#[macro_use(as_void, cstr)]
+#[macro_use(as_void)]
 extern crate apue;
 extern crate libc;
 extern crate errno;
@@ -30,7 +30,7 @@ fn main() {
         signal(SIGALRM, sig_alrm).check_not_negative().expect("couldn't set alarm");
         alarm(1);
         let buffer: Vec<u8> = Vec::with_capacity(SIZE);
-        let file = fopen(cstr!("/tmp/10_fwrite_1gb.txt"), cstr!("w"))
+        let file = fopen(b"/tmp/10_fwrite_1gb.txt\0".as_ptr() as *const _, b"w\0".as_ptr() as *const _)
             .check_not_null()
             .expect("can't open file");
         fwrite(as_void!(buffer), 1, SIZE, file).check_not_negative().expect("can't write to file");
@@ -12,7 +12,6 @@
 /// The lock is most likely due to a malloc who is threadsafe through
 /// locking which results in a deadlock. Explanation here:
 /// http://stackoverflow.com/a/3941563/119861
-#[macro_use(cstr)]
 extern crate apue;
 extern crate libc;
 
@@ -23,8 +22,8 @@ use std::ffi::CStr;
 
 extern "C" fn my_alarm(_: c_int) {
     unsafe {
-        printf(cstr!("in signal handler\n"));
-        getpwnam(cstr!("root")).check_not_null().expect("getpwnam(root) error");
+        printf(b"in signal handler\n\0".as_ptr() as *const _);
+        getpwnam(b"root\0".as_ptr() as *const _).check_not_null().expect("getpwnam(root) error");
         alarm(1);
     }
 }
@@ -34,7 +33,7 @@ fn main() {
         signal(SIGALRM, my_alarm as usize);
         alarm(1);
         loop {
-            let pwd = getpwnam(cstr!("nobody")).check_not_null().expect("getpwnam error");
+            let pwd = getpwnam(b"nobody\0".as_ptr() as *const _).check_not_null().expect("getpwnam error");
             let pw_name = CStr::from_ptr((*pwd).pw_name).to_str().unwrap();
             if pw_name != "nobody" {
                 println!("return value corrupted! pw_name = {}", pw_name);
@@ -8,6 +8,7 @@ use libc::{c_int, SIGINT, SIG_ERR, SIGUSR1, SIG_BLOCK, SIG_SETMASK};
 use libc::{signal, sigemptyset, sigaddset};
 use apue::my_libc::{sigprocmask, sigsuspend};
 use apue::{pr_mask, LibcResult};
+use std::io::ErrorKind;
 use std::mem::uninitialized as uninit;
 
 fn sig_int(_: c_int) {
@@ -33,7 +34,11 @@ fn main() {
         // Critical region of code
         pr_mask("in critical region: ");
         // Pause, allowing all signals except SIGUSR1
-        sigsuspend(&waitmask).check_minus_one().expect("sigsuspend error");
+        // sigsuspend always returns -1, with EINTR once a signal handler returned
+        let err = sigsuspend(&waitmask).check_minus_one().unwrap_err();
+        if err.kind() != ErrorKind::Interrupted {
+            panic!("sigsuspend error: {}", err);
+        }
         pr_mask("after return from sigsuspend: ");
         // reset signal mask which unblocks SIGINT
         sigprocmask(SIG_SETMASK, &oldmask, std::ptr::null_mut())
@@ -33,7 +33,6 @@
 // caught SIGINT
 // q
 // caught SIGCHLD
-#[macro_use(cstr)]
 extern crate apue;
 extern crate libc;
 
@@ -43,13 +42,13 @@ use libc::{signal, printf};
 
 fn sig_int(_: c_int) {
     unsafe {
-        printf(cstr!("caught SIGINT\n"));
+        printf(b"caught SIGINT\n\0".as_ptr() as *const _);
     }
 }
 
 fn sig_chld(_: c_int) {
     unsafe {
-        printf(cstr!("caught SIGCHLD\n"));
+        printf(b"caught SIGCHLD\n\0".as_ptr() as *const _);
     }
 }
 
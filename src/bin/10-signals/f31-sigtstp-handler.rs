@@ -39,7 +39,7 @@ fn main() {
         let buf = vec![0; BUFFSIZE];
         while let Ok(n) = read(STDIN_FILENO, as_void!(buf), BUFFSIZE).check_positive() {
             if write(STDOUT_FILENO, as_void!(buf), n as _) != n {
-                err_sys("write error");
+                err_sys!("write error");
             }
         }
     }
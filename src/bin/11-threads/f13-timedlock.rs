@@ -8,7 +8,7 @@
 /// avoid Rusts array bound checking.
 
 extern crate libc;
-#[macro_use(as_char, cstr)]
+#[macro_use(as_char)]
 extern crate apue;
 
 #[cfg(target_os = "linux")]
@@ -26,7 +26,7 @@ mod timedlock {
     unsafe fn print_time(s: &str, tout: &timespec) {
         let tmp = localtime(&tout.tv_sec);
         let buf: [c_char; BUFLEN] = [0; BUFLEN];
-        strftime(as_char!(buf), BUFLEN, cstr!("%r"), tmp);
+        strftime(as_char!(buf), BUFLEN, b"%r\0".as_ptr() as *const _, tmp);
         println!("{} {}", s, array_to_string(&buf));
     }
 
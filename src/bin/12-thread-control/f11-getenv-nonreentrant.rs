@@ -3,7 +3,6 @@
 /// Finding: some fun with pointers and 0 terminated strings :-)
 
 extern crate libc;
-#[macro_use(cstr)]
 extern crate apue;
 
 use libc::{c_char, printf};
@@ -41,6 +40,6 @@ fn getenv(name: &str) {
 fn main() {
     getenv("PATH");
     unsafe {
-        printf(cstr!("%s\n"), BUF.as_ptr());
+        printf(b"%s\n\0".as_ptr() as *const _, BUF.as_ptr());
     }
 }
\ No newline at end of file
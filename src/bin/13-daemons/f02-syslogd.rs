@@ -0,0 +1,43 @@
+/// Figure 13.2 The UNIX System syslog facility
+///
+/// A stand-in for syslogd: binds a UNIX datagram socket to `socket` (like /dev/log)
+/// and prints every frame it receives on one line. It stops after `count` messages
+/// (default: never) and removes the socket. Together with `apue::syslog::Client` it
+/// lets tests check what a program logs without a real syslogd, see
+/// `f07-reread --syslog`.
+///
+/// With logger(1) of util-linux, daemon.err is priority 3 * 8 + 3:
+///
+/// linux only:
+/// $ f02-syslogd /tmp/f02-syslogd.sock 1 | sed -E 's/^(<[0-9]+>).* (test:)/\1 \2/' & sleep 0.2; logger -u /tmp/f02-syslogd.sock --rfc3164 -p daemon.err -t test hello; wait
+/// <27> test: hello
+
+use std::fs;
+use std::os::unix::net::UnixDatagram;
+use std::process::exit;
+
+fn usage() -> ! {
+    eprintln!("usage: f02-syslogd <socket> [count]");
+    exit(1);
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        usage();
+    }
+    let path = &args[1];
+    let count = args.get(2).map(|s| s.parse::<usize>().unwrap_or_else(|_| usage()));
+
+    // a socket left behind by an earlier run makes bind fail
+    let _ = fs::remove_file(path);
+    let socket = UnixDatagram::bind(path).unwrap_or_else(|e| panic!("can't bind {}: {}", path, e));
+    let mut buf = [0; 8192];
+    let mut received = 0;
+    while count.map_or(true, |count| received < count) {
+        let n = socket.recv(&mut buf).expect("recv error");
+        println!("{}", String::from_utf8_lossy(&buf[..n]).trim_end_matches('\n'));
+        received += 1;
+    }
+    let _ = fs::remove_file(path);
+}
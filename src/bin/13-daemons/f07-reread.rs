@@ -0,0 +1,121 @@
+/// Figure 13.7 Daemon rereading configuration files
+///
+/// Becomes a daemon (Figure 13.1), makes sure it runs only once by locking `pidfile`
+/// (Figure 13.6) and then waits for signals in a separate thread: SIGHUP rereads
+/// `--config` (default /etc/f07-reread.conf) and logs its first line, SIGTERM stops the
+/// daemon. All messages go to syslog with the identifier f07-reread. With `--syslog
+/// socket` they are sent by `apue::syslog::Client` to `socket` instead, in RFC 3164
+/// format or with `--rfc5424` in RFC 5424 format.
+///
+/// The second instance notices the lock before it daemonizes, so it can still
+/// complain on the terminal. After two forks the daemon is neither a session leader
+/// nor has it a controlling terminal:
+///
+/// $ f07-reread /tmp/f07-reread.pid
+/// $ sleep 0.2; pid=$(cat /tmp/f07-reread.pid); ps -o sid=,tty= -p $pid | awk -v pid=$pid '{print ($1 == pid ? "leader" : "no leader"), $2}'
+/// no leader ?
+/// $ f07-reread /tmp/f07-reread.pid 2>&1 | sed 's/[0-9]*$/N/'
+/// daemon already running, pid N
+/// $ kill -HUP $(cat /tmp/f07-reread.pid); sleep 0.2; ps -o stat= -p $(cat /tmp/f07-reread.pid) | grep -qv Z && echo running
+/// running
+/// $ kill $(cat /tmp/f07-reread.pid); sleep 0.2; ps -o stat= -p $(cat /tmp/f07-reread.pid) | grep -qv Z || echo stopped
+/// stopped
+///
+/// With the stand-in syslogd `f02-syslogd` (the timestamps and pids are replaced):
+///
+/// $ echo first line > /tmp/f07-reread.conf
+/// $ f02-syslogd /tmp/f07-syslogd.sock 4 > /tmp/f07-syslogd.out & sleep 0.2; f07-reread --syslog /tmp/f07-syslogd.sock --config /tmp/f07-reread.conf /tmp/f07-reread.pid; sleep 0.2; kill -HUP $(cat /tmp/f07-reread.pid); sleep 0.2; kill $(cat /tmp/f07-reread.pid); wait
+/// $ sed -E 's/^(<[0-9]+>)[A-Z][a-z]{2} [ 0-9]{2} [0-9:]{8} (.*)\[[0-9]+\]/\1TIME \2[PID]/' /tmp/f07-syslogd.out
+/// <30>TIME f07-reread[PID]: configuration: first line
+/// <30>TIME f07-reread[PID]: Re-reading configuration file
+/// <30>TIME f07-reread[PID]: configuration: first line
+/// <30>TIME f07-reread[PID]: got SIGTERM; exiting
+/// $ f02-syslogd /tmp/f07-syslogd.sock 2 > /tmp/f07-syslogd.out & sleep 0.2; f07-reread --syslog /tmp/f07-syslogd.sock --rfc5424 --config /tmp/f07-reread.conf /tmp/f07-reread.pid; sleep 0.2; kill $(cat /tmp/f07-reread.pid); wait
+/// $ sed -E 's/^(<[0-9]+>1) [-0-9T:.+]+ [^ ]+ (.*) [0-9]+ /\1 TIME HOST \2 PID /' /tmp/f07-syslogd.out
+/// <30>1 TIME HOST f07-reread PID - - configuration: first line
+/// <30>1 TIME HOST f07-reread PID - - got SIGTERM; exiting
+///
+/// Errors are reported with the `log_*` macros, which log to syslog in a daemon:
+///
+/// $ f02-syslogd /tmp/f07-syslogd.sock 2 > /tmp/f07-syslogd.out & sleep 0.2; f07-reread --syslog /tmp/f07-syslogd.sock --config /tmp/f07-nonexistent.conf /tmp/f07-reread.pid; sleep 0.2; kill $(cat /tmp/f07-reread.pid); wait
+/// $ sed -E 's/^(<[0-9]+>)[A-Z][a-z]{2} [ 0-9]{2} [0-9:]{8} (.*)\[[0-9]+\]/\1TIME \2[PID]/' /tmp/f07-syslogd.out; rm -f /tmp/f07-reread.pid /tmp/f07-reread.conf /tmp/f07-syslogd.out
+/// <27>TIME f07-reread[PID]: can't open /tmp/f07-nonexistent.conf: No such file or directory (os error 2)
+/// <30>TIME f07-reread[PID]: got SIGTERM; exiting
+
+#[macro_use(log_msg, log_quit)]
+extern crate apue;
+
+use apue::daemon::{daemonize, handle_signals, running_pid, PidFile};
+use apue::syslog::{self, syslog, Client, Facility, Format, Priority};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+fn usage() -> ! {
+    eprintln!("usage: f07-reread [--config file] [--syslog socket [--rfc5424]] <pidfile>");
+    exit(1);
+}
+
+fn reread(config: &Path) {
+    match File::open(config) {
+        Ok(f) => {
+            let mut line = String::new();
+            let _ = BufReader::new(f).read_line(&mut line);
+            syslog(Priority::Info, &format!("configuration: {}", line.trim_end()));
+        }
+        Err(e) => log_msg!("can't open {}: {}", config.display(), e),
+    }
+}
+
+fn main() {
+    let mut config = PathBuf::from("/etc/f07-reread.conf");
+    let mut socket = None;
+    let mut format = Format::Rfc3164;
+    let mut pidfile = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config = PathBuf::from(args.next().unwrap_or_else(|| usage())),
+            "--syslog" => socket = Some(PathBuf::from(args.next().unwrap_or_else(|| usage()))),
+            "--rfc5424" => format = Format::Rfc5424,
+            _ if pidfile.is_none() => pidfile = Some(PathBuf::from(arg)),
+            _ => usage(),
+        }
+    }
+    let pidfile = pidfile.unwrap_or_else(|| usage());
+
+    // the daemon runs in /
+    let cwd = std::env::current_dir().expect("getcwd error");
+    let pidfile = cwd.join(pidfile);
+    let config = cwd.join(config);
+    let socket = socket.map(|socket| cwd.join(socket));
+
+    if let Some(pid) = running_pid(&pidfile).expect("can't check the pid file") {
+        eprintln!("daemon already running, pid {}", pid);
+        exit(1);
+    }
+    if daemonize("f07-reread").is_err() {
+        exit(1);
+    }
+    // daemonize closed all descriptors, so the client's socket is created afterwards
+    if let Some(socket) = socket {
+        match Client::new("f07-reread") {
+            Ok(mut client) => {
+                client.path(socket).facility(Facility::Daemon).format(format).pid(true);
+                syslog::use_client(client);
+            }
+            Err(e) => log_quit!("can't create the syslog client: {}", e),
+        }
+    }
+    // another instance could have started in the meantime
+    let _pidfile = match PidFile::create(&pidfile) {
+        Ok(pidfile) => pidfile,
+        Err(e) => log_quit!("can't lock {}: {}", pidfile.display(), e),
+    };
+    reread(&config);
+    let thread = handle_signals(move || reread(&config))
+        .unwrap_or_else(|e| log_quit!("can't handle signals: {}", e));
+    // the rest of the daemon would go here
+    let _ = thread.join();
+}
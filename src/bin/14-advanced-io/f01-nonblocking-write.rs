@@ -0,0 +1,51 @@
+/// Figure 14.1 Large nonblocking write
+///
+/// Reads up to 500,000 bytes from stdin, makes stdout nonblocking and writes the data
+/// to it, reporting every write on stderr. To a file the first write takes everything,
+/// to a pipe whose reader is slow most writes fail with EAGAIN and the program spins:
+/// this polling wastes CPU time, the multiplexers in `apue::poll` wait instead.
+///
+/// $ head -c 500000 /dev/zero > /tmp/f01-in.dat
+/// $ f01-nonblocking-write < /tmp/f01-in.dat > /tmp/f01-out.dat 2> /tmp/f01-err.txt; cat /tmp/f01-err.txt
+/// read 500000 bytes
+/// nwrite = 500000, errno = 0
+/// $ cmp /tmp/f01-in.dat /tmp/f01-out.dat && echo same
+/// same
+///
+/// linux only:
+/// $ f01-nonblocking-write < /tmp/f01-in.dat 2> /tmp/f01-err.txt | (sleep 0.2; wc -c)
+/// 500000
+/// $ grep -q "errno = 11" /tmp/f01-err.txt && echo "got EAGAIN"
+/// got EAGAIN
+
+extern crate apue;
+extern crate libc;
+
+use apue::fd::{clr_fl, set_fl};
+use libc::{O_NONBLOCK, STDIN_FILENO, STDOUT_FILENO};
+use std::io::Error;
+
+fn main() {
+    let mut buf = vec![0u8; 500000];
+    let ntowrite = unsafe { libc::read(STDIN_FILENO, buf.as_mut_ptr() as *mut _, buf.len()) };
+    if ntowrite < 0 {
+        panic!("read error: {}", Error::last_os_error());
+    }
+    eprintln!("read {} bytes", ntowrite);
+
+    set_fl(&STDOUT_FILENO, O_NONBLOCK).expect("set_fl error");
+
+    let mut ptr = 0;
+    let mut ntowrite = ntowrite as usize;
+    while ntowrite > 0 {
+        let nwrite = unsafe { libc::write(STDOUT_FILENO, buf[ptr..].as_ptr() as *const _, ntowrite) };
+        let errno = if nwrite < 0 { Error::last_os_error().raw_os_error().unwrap_or(0) } else { 0 };
+        eprintln!("nwrite = {}, errno = {}", nwrite, errno);
+        if nwrite > 0 {
+            ptr += nwrite as usize;
+            ntowrite -= nwrite as usize;
+        }
+    }
+
+    clr_fl(&STDOUT_FILENO, O_NONBLOCK).expect("clr_fl error");
+}
@@ -0,0 +1,64 @@
+/// Figure 14.7 Example of deadlock detection
+///
+/// Parent and child each lock one byte of a two byte file, wait for each other with
+/// TELL_WAIT and then try to lock the other byte with F_SETLKW. The kernel notices the
+/// deadlock and one of them fails with EDEADLK. It exits, which releases its lock, so
+/// the other one gets the byte. Which of the two fails is up to the kernel:
+///
+/// ```text
+/// parent: got the lock, byte 1
+/// child: got the lock, byte 0
+/// child: writew_lock error: Resource deadlock avoided (os error 35)
+/// parent: got the lock, byte 0
+/// ```
+///
+/// $ f07-deadlock 2>&1 | awk '/got the lock/ {n++} /deadlock avoided/ {d++} END {print n, d}'
+/// 3 1
+
+extern crate apue;
+extern crate libc;
+
+use apue::LibcResult;
+use apue::fd::Fd;
+use apue::lock::Lock;
+use apue::sync_parent_child;
+use libc::{fork, getppid, O_CREAT, O_RDWR, O_TRUNC, SEEK_SET, S_IRGRP, S_IROTH, S_IRUSR, S_IWUSR};
+use std::process::exit;
+
+fn lockabyte(name: &str, fd: &Fd, offset: libc::off_t) {
+    match Lock::write(offset, SEEK_SET, 1).lock(fd) {
+        Ok(guard) => guard.leak(), // held until the process exits
+        Err(e) => {
+            eprintln!("{}: writew_lock error: {}", name, e);
+            exit(1);
+        }
+    }
+    println!("{}: got the lock, byte {}", name, offset);
+}
+
+fn main() {
+    // create a file and write two bytes to it
+    let fd = Fd::open("/tmp/templock",
+                      O_RDWR | O_CREAT | O_TRUNC,
+                      S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+        .expect("creat error");
+    fd.write(b"ab").expect("write error");
+    // the locks are on the open file, it doesn't need a name any more
+    std::fs::remove_file("/tmp/templock").expect("unlink error");
+
+    let tw = sync_parent_child::by_name("signal").expect("TELL_WAIT error");
+    let pid = unsafe { fork() }.check_not_negative().expect("fork error");
+    if pid == 0 {
+        // child
+        lockabyte("child", &fd, 0);
+        tw.tell_parent(unsafe { getppid() }).expect("TELL_PARENT error");
+        tw.wait_parent().expect("WAIT_PARENT error");
+        lockabyte("child", &fd, 1);
+    } else {
+        // parent
+        lockabyte("parent", &fd, 1);
+        tw.tell_child(pid).expect("TELL_CHILD error");
+        tw.wait_child().expect("WAIT_CHILD error");
+        lockabyte("parent", &fd, 0);
+    }
+}
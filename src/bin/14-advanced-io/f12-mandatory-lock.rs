@@ -0,0 +1,92 @@
+/// Figure 14.12 Determine whether mandatory locking is supported
+///
+/// The file gets the set-group-ID bit without group-execute, which marks it for
+/// mandatory locking. The parent write locks the whole file, the child first checks
+/// that it can't lock it and then reads it with a nonblocking descriptor. With
+/// mandatory locking the read fails with EAGAIN, with advisory locking it succeeds.
+///
+/// Linux dropped mandatory locking in 5.15 (before it needed the `mand` mount option),
+/// macOS never had it, so record locks are advisory on both: they only keep out
+/// processes which check for locks themselves.
+///
+/// linux only:
+/// $ f12-mandatory-lock /tmp/f12-mandatory-lock.txt; rm -f /tmp/f12-mandatory-lock.txt
+/// read_lock of already-locked region returns 11
+/// read OK (no mandatory locking), buf = ab
+///
+/// mac only:
+/// $ f12-mandatory-lock /tmp/f12-mandatory-lock.txt; rm -f /tmp/f12-mandatory-lock.txt
+/// read_lock of already-locked region returns 35
+/// read OK (no mandatory locking), buf = ab
+
+extern crate apue;
+extern crate libc;
+
+use apue::LibcResult;
+use apue::fd::Fd;
+use apue::lock::{lock_reg, Lock, LockType};
+use apue::sync_parent_child;
+use libc::{F_GETFL, F_SETFL, F_SETLK, O_CREAT, O_NONBLOCK, O_RDWR, O_TRUNC, SEEK_SET, S_IRGRP,
+           S_IROTH, S_ISGID, S_IRUSR, S_IWUSR, S_IXGRP};
+use libc::{fchmod, fork, fstat, waitpid};
+use std::os::unix::io::AsRawFd;
+use std::process::exit;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: f12-mandatory-lock filename");
+            exit(1);
+        }
+    };
+    let fd = Fd::open(&path,
+                      O_RDWR | O_CREAT | O_TRUNC,
+                      S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+        .expect("open error");
+    fd.write(b"abcdef").expect("write error");
+
+    // turn on set-group-ID and turn off group-execute
+    let mut statbuf: libc::stat = unsafe { std::mem::zeroed() };
+    unsafe { fstat(fd.as_raw_fd(), &mut statbuf) }.check_not_negative().expect("fstat error");
+    unsafe { fchmod(fd.as_raw_fd(), (statbuf.st_mode & !S_IXGRP) | S_ISGID) }
+        .check_not_negative()
+        .expect("fchmod error");
+
+    let tw = sync_parent_child::by_name("signal").expect("TELL_WAIT error");
+    let pid = unsafe { fork() }.check_not_negative().expect("fork error");
+    if pid > 0 {
+        // parent: write lock entire file
+        let _guard = Lock::write(0, SEEK_SET, 0).try_lock(&fd).expect("write_lock error");
+        tw.tell_child(pid).expect("TELL_CHILD error");
+        unsafe { waitpid(pid, std::ptr::null_mut(), 0) }.check_not_negative().expect("waitpid error");
+    } else {
+        // child: wait for parent to set lock
+        tw.wait_parent().expect("WAIT_PARENT error");
+        let flags = fd.fcntl(F_GETFL, 0).expect("fcntl F_GETFL error");
+        fd.fcntl(F_SETFL, flags | O_NONBLOCK).expect("fcntl F_SETFL error");
+
+        // first let's see what error we get if region is locked
+        match lock_reg(&fd, F_SETLK, LockType::Read, 0, SEEK_SET, 0) {
+            Ok(()) => {
+                eprintln!("child: read_lock succeeded");
+                exit(1);
+            }
+            Err(e) => {
+                println!("read_lock of already-locked region returns {}",
+                         e.raw_os_error().unwrap_or(0))
+            }
+        }
+
+        // now try to read the mandatory locked file
+        fd.lseek(0, SEEK_SET).expect("lseek error");
+        let mut buf = [0; 2];
+        match fd.read(&mut buf) {
+            Ok(n) => {
+                println!("read OK (no mandatory locking), buf = {}",
+                         String::from_utf8_lossy(&buf[..n]))
+            }
+            Err(e) => eprintln!("read failed (mandatory locking works): {}", e),
+        }
+    }
+}
@@ -0,0 +1,151 @@
+/// Figure 14.14 Overview of telnet program, with I/O multiplexing instead of two processes
+///
+/// Runs `command` with its stdin and stdout connected to pipes and relays stdin to the
+/// command and the command's output to stdout, like telnet between terminal and
+/// network. A single process handles both directions with a `Poller` from
+/// `apue::poll` (`--poller select|poll|epoll`, default poll).
+///
+/// Blocking writes would deadlock as soon as both pipes are full: the relay waits for
+/// the command to read while the command waits for the relay to read. So the pipe ends
+/// of the relay are nonblocking and only written when the poller reports them
+/// writable. When stdin is at end of file the relay closes the command's stdin, and it
+/// stops once the command's stdout is at end of file. A command that exits without
+/// reading all of its input (EPIPE) gets no more of stdin, its output is still relayed
+/// to the end.
+///
+/// $ printf 'hello\nworld\n' | f14-relay tr a-z A-Z
+/// HELLO
+/// WORLD
+/// $ yes | f14-relay head -2
+/// y
+/// y
+/// $ head -c 1000000 /dev/urandom > /tmp/f14-in.dat
+/// $ f14-relay --poller select cat < /tmp/f14-in.dat | cmp - /tmp/f14-in.dat && echo same
+/// same
+/// $ f14-relay --poller poll cat < /tmp/f14-in.dat | cmp - /tmp/f14-in.dat && echo same
+/// same
+///
+/// epoll doesn't take regular files (EPERM), so stdin has to be a pipe:
+///
+/// linux only:
+/// $ cat /tmp/f14-in.dat | f14-relay --poller epoll cat | cmp - /tmp/f14-in.dat && echo same
+/// same
+
+extern crate apue;
+extern crate libc;
+
+use apue::LibcResult;
+use apue::fd::{self, Fd};
+use apue::poll::{self, Interest};
+use apue::process::Command;
+use libc::{c_void, STDIN_FILENO, STDOUT_FILENO};
+use std::io::{ErrorKind, Write};
+use std::os::unix::io::AsRawFd;
+use std::process::exit;
+
+const BUFFSIZE: usize = 8192;
+
+fn usage() -> ! {
+    eprintln!("usage: f14-relay [--poller select|poll|epoll] command [arg ...]");
+    exit(1);
+}
+
+// stdin isn't an `Fd` (it must not be closed) and io::Stdin would buffer data the
+// poller doesn't know about
+fn read_stdin(buf: &mut [u8]) -> std::io::Result<usize> {
+    let n = unsafe { libc::read(STDIN_FILENO, buf.as_mut_ptr() as *mut c_void, buf.len()) }
+        .check_not_negative()?;
+    Ok(n as usize)
+}
+
+fn main() {
+    let mut poller_name = "poll".to_string();
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(|s| s.as_str()) == Some("--poller") {
+        args.next();
+        poller_name = args.next().unwrap_or_else(|| usage());
+    }
+    let program = args.next().unwrap_or_else(|| usage());
+
+    let (child_in_rd, child_in) = fd::pipe().expect("pipe error");
+    let (child_out, child_out_wr) = fd::pipe().expect("pipe error");
+    let mut child = Command::new(&program)
+        .args(args)
+        .redirect(child_in_rd.as_raw_fd(), STDIN_FILENO)
+        .redirect(child_out_wr.as_raw_fd(), STDOUT_FILENO)
+        .spawn()
+        .unwrap_or_else(|e| panic!("can't run {}: {}", program, e));
+    drop(child_in_rd);
+    drop(child_out_wr);
+    fd::set_nonblocking(&child_in, true).expect("set_nonblocking error");
+    fd::set_nonblocking(&child_out, true).expect("set_nonblocking error");
+
+    let mut poller = poll::by_name(&poller_name).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        usage()
+    });
+    poller.add(STDIN_FILENO, Interest::Read).expect("poller add error");
+    poller.add(child_out.as_raw_fd(), Interest::Read).expect("poller add error");
+
+    // data read from stdin which the command didn't take yet
+    let mut pending: Vec<u8> = Vec::new();
+    let mut child_in: Option<Fd> = Some(child_in);
+    let mut buf = [0; BUFFSIZE];
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+
+    loop {
+        let events = match poller.wait(None) {
+            Ok(events) => events,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => panic!("wait error: {}", e),
+        };
+        for ev in events {
+            if ev.fd == STDIN_FILENO {
+                let n = read_stdin(&mut buf).expect("read error");
+                poller.remove(STDIN_FILENO).expect("poller remove error");
+                if n == 0 {
+                    // end of file, closing the pipe passes it on to the command
+                    child_in = None;
+                } else {
+                    // stop reading until the command took it
+                    pending.extend_from_slice(&buf[..n]);
+                    let fd = child_in.as_ref().unwrap().as_raw_fd();
+                    poller.add(fd, Interest::Write).expect("poller add error");
+                }
+            } else if ev.fd == child_out.as_raw_fd() {
+                match child_out.read(&mut buf) {
+                    Ok(0) => {
+                        stdout.flush().expect("write error");
+                        child.wait().expect("waitpid error");
+                        return;
+                    }
+                    Ok(n) => stdout.write_all(&buf[..n]).expect("write error"),
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(e) => panic!("read error: {}", e),
+                }
+                stdout.flush().expect("write error");
+            } else if let Some(fd) = child_in.take() {
+                match fd.write(&pending) {
+                    Ok(n) => {
+                        pending.drain(..n);
+                    }
+                    Err(ref e) if e.kind() == ErrorKind::WouldBlock => {}
+                    Err(ref e) if e.kind() == ErrorKind::BrokenPipe => {
+                        // the command stopped reading: stdin stays out of the poller and
+                        // the pipe is closed, its output is still read until end of file
+                        poller.remove(fd.as_raw_fd()).expect("poller remove error");
+                        pending.clear();
+                        continue;
+                    }
+                    Err(e) => panic!("write error: {}", e),
+                }
+                if pending.is_empty() {
+                    poller.remove(fd.as_raw_fd()).expect("poller remove error");
+                    poller.add(STDIN_FILENO, Interest::Read).expect("poller add error");
+                }
+                child_in = Some(fd);
+            }
+        }
+    }
+}
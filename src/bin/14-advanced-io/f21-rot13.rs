@@ -0,0 +1,311 @@
+/// Figure 14.21 Translate a file using ROT-13 and asynchronous I/O
+///
+/// `--method` selects the implementation:
+///
+/// - `aio`: Figure 14.21, `-n` buffers of 4096 bytes (default 8) with a request each
+///   in flight, using `apue::aio`. A buffer is read, translated and written, then it
+///   reads the next block that nobody read yet. This is the default
+/// - `sync`: Figure 14.20, read, translate and write one block after the other
+///
+/// `--notify` selects how `aio` learns about completed requests:
+///
+/// - `suspend`: aio_suspend on all requests in flight, as in the book (default)
+/// - `signal`: every request sends SIGUSR1, which is blocked and taken with sigwait
+/// - `thread`: every request runs a closure in a new thread which sends a message over
+///   a channel, Linux with glibc only
+///
+/// The timing is printed in the same table format as Figure 3.5.
+///
+/// $ printf 'Hello, World!\n' > /tmp/f21-in.txt
+/// $ f21-rot13 /tmp/f21-in.txt /tmp/f21-out.txt > /dev/null && cat /tmp/f21-out.txt
+/// Uryyb, Jbeyq!
+/// $ head -c 100000 /dev/urandom | base64 > /tmp/f21-in.txt; tr 'A-Za-z' 'N-ZA-Mn-za-m' < /tmp/f21-in.txt > /tmp/f21-expected.txt
+/// $ f21-rot13 -n 3 /tmp/f21-in.txt /tmp/f21-out.txt > /dev/null && cmp /tmp/f21-out.txt /tmp/f21-expected.txt && echo same
+/// same
+/// $ f21-rot13 --method sync /tmp/f21-in.txt /tmp/f21-out.txt > /dev/null && cmp /tmp/f21-out.txt /tmp/f21-expected.txt && echo same
+/// same
+/// $ f21-rot13 --notify signal /tmp/f21-in.txt /tmp/f21-out.txt > /dev/null && cmp /tmp/f21-out.txt /tmp/f21-expected.txt && echo same
+/// same
+/// $ f21-rot13 /dev/null /tmp/f21-out.txt | awk 'NR == 3 {print $2, $8}'
+/// aio 0
+///
+/// linux only:
+/// $ f21-rot13 --notify thread /tmp/f21-in.txt /tmp/f21-out.txt > /dev/null && cmp /tmp/f21-out.txt /tmp/f21-expected.txt && echo same; rm -f /tmp/f21-*.txt
+/// same
+
+/// ## Timing
+///
+/// > dd if=/dev/urandom bs=1048576 count=192 | base64 > /tmp/bigfile.txt
+///
+/// Every variant translates the file three times, the table shows the fastest run.
+/// The input is in the page cache, the output file is on ext4 and the machine has a
+/// single CPU. Like in the book the aio variants end with aio_fsync.
+///
+/// ### Linux
+///
+/// > uname -rsv
+/// Linux 6.18.44 #1 SMP PREEMPT_DYNAMIC
+///
+/// | method |  n | notify  |      size | real time | user time | sys time |
+/// |--------|----|---------|-----------|-----------|-----------|----------|
+/// | sync   |  - | -       | 271967502 |      0.35 |      0.13 |     0.22 |
+/// | aio    |  1 | suspend | 271967502 |      1.36 |      0.21 |     0.96 |
+/// | aio    |  8 | suspend | 271967502 |      0.97 |      0.21 |     0.55 |
+/// | aio    | 64 | suspend | 271967502 |      0.66 |      0.18 |     0.32 |
+/// | aio    |  8 | signal  | 271967502 |      1.01 |      0.25 |     0.54 |
+/// | aio    |  8 | thread  | 271967502 |      4.21 |      0.63 |     2.71 |
+///
+/// Takeaways: with cached data glibc's AIO is slower than plain read and write, four
+/// times with one request in flight and still twice with 64. glibc implements it in user
+/// space: every request is handed to a helper thread doing an ordinary pread or pwrite,
+/// so each 4 KiB block costs thread wake-ups and locking on top of the system call. More
+/// requests in flight amortize that, notification by thread makes it much worse because
+/// glibc starts a new thread for every request. AIO only pays off when the I/O itself is
+/// slow (uncached files on a disk) and the program has something else to do meanwhile.
+
+extern crate apue;
+extern crate libc;
+
+use apue::aio::{self, AioCb, Notify};
+use apue::fd::Fd;
+use apue::signals::SigSet;
+use apue::timing::{Timer, Times};
+use libc::{c_int, off_t, O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, SIGUSR1, S_IRGRP, S_IROTH, S_IRUSR,
+           S_IWUSR};
+use libc::sigwait;
+use std::process::exit;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+const BSZ: usize = 4096;
+
+fn translate(c: u8) -> u8 {
+    match c {
+        b'a'..=b'z' => (c - b'a' + 13) % 26 + b'a',
+        b'A'..=b'Z' => (c - b'A' + 13) % 26 + b'A',
+        _ => c,
+    }
+}
+
+// Figure 14.20
+fn rot13_sync(ifd: &Fd, ofd: &Fd) -> u64 {
+    let mut buf = [0; BSZ];
+    let mut total = 0;
+    loop {
+        let n = ifd.read(&mut buf).expect("read failed");
+        if n == 0 {
+            return total;
+        }
+        for c in buf[..n].iter_mut() {
+            *c = translate(*c);
+        }
+        let nw = ofd.write(&buf[..n]).expect("write failed");
+        if nw != n {
+            eprintln!("short write ({}/{})", nw, n);
+            exit(1);
+        }
+        total += n as u64;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Unused,
+    ReadPending,
+    WritePending,
+}
+
+struct Buf {
+    op: Op,
+    last: bool,
+    offset: off_t,
+    cb: AioCb,
+}
+
+// how the main loop waits when all buffers are busy
+enum Waiter {
+    Suspend,
+    Signal(SigSet),
+    Thread(Sender<()>, Receiver<()>),
+}
+
+impl Waiter {
+    fn notify(&self) -> Notify {
+        match *self {
+            Waiter::Suspend => Notify::None,
+            Waiter::Signal(_) => Notify::Signal(SIGUSR1),
+            Waiter::Thread(ref tx, _) => thread_notify(tx.clone()),
+        }
+    }
+
+    fn wait(&self, bufs: &[Buf]) {
+        match *self {
+            Waiter::Suspend => {
+                let list: Vec<&AioCb> = bufs.iter().map(|b| &b.cb).collect();
+                aio::suspend(&list, None).expect("aio_suspend failed");
+            }
+            Waiter::Signal(ref mask) => {
+                let mut signo: c_int = 0;
+                unsafe { sigwait(mask.as_ptr(), &mut signo) };
+            }
+            Waiter::Thread(_, ref rx) => rx.recv().expect("recv failed"),
+        }
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn thread_notify(tx: Sender<()>) -> Notify {
+    Notify::Thread(Box::new(move || {
+        let _ = tx.send(());
+    }))
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+fn thread_notify(_: Sender<()>) -> Notify {
+    eprintln!("notification by thread is Linux with glibc only");
+    exit(1);
+}
+
+// Figure 14.21
+fn rot13_aio(ifd: &Fd, ofd: &Fd, nbuf: usize, waiter: &Waiter) -> u64 {
+    let size = ifd.file_size().expect("fstat error");
+    let mut bufs: Vec<Buf> = (0..nbuf)
+        .map(|_| {
+            Buf {
+                op: Op::Unused,
+                last: false,
+                offset: 0,
+                cb: AioCb::new(vec![0; BSZ]),
+            }
+        })
+        .collect();
+    let mut off: off_t = 0;
+    let mut numop = 0;
+    let mut total = 0;
+    loop {
+        for buf in bufs.iter_mut() {
+            match buf.op {
+                Op::Unused => {
+                    // read from the input file if more data remains unread
+                    if off < size {
+                        buf.op = Op::ReadPending;
+                        buf.offset = off;
+                        off += BSZ as off_t;
+                        buf.last = off >= size;
+                        buf.cb.buffer_mut().unwrap().resize(BSZ, 0);
+                        buf.cb.read(ifd, buf.offset, waiter.notify()).expect("aio_read failed");
+                        numop += 1;
+                    }
+                }
+                Op::ReadPending => {
+                    let n = match buf.cb.poll() {
+                        None => continue,
+                        Some(res) => res.expect("read failed"),
+                    };
+                    if n != BSZ && !buf.last {
+                        eprintln!("short read ({}/{})", n, BSZ);
+                        exit(1);
+                    }
+                    // a read is complete; translate the buffer and write it
+                    {
+                        let data = buf.cb.buffer_mut().unwrap();
+                        data.truncate(n);
+                        for c in data.iter_mut() {
+                            *c = translate(*c);
+                        }
+                    }
+                    buf.op = Op::WritePending;
+                    buf.cb.write(ofd, buf.offset, waiter.notify()).expect("aio_write failed");
+                }
+                Op::WritePending => {
+                    let n = match buf.cb.poll() {
+                        None => continue,
+                        Some(res) => res.expect("write failed"),
+                    };
+                    let len = buf.cb.buffer().unwrap().len();
+                    if n != len {
+                        eprintln!("short write ({}/{})", n, len);
+                        exit(1);
+                    }
+                    // a write is complete; mark the buffer as unused
+                    total += n as u64;
+                    buf.op = Op::Unused;
+                    numop -= 1;
+                }
+            }
+        }
+        if numop == 0 {
+            if off >= size {
+                break;
+            }
+        } else {
+            waiter.wait(&bufs);
+        }
+    }
+    let mut cb = AioCb::new(Vec::new());
+    cb.fsync(ofd, Notify::None).expect("aio_fsync failed");
+    cb.wait().expect("aio_fsync failed");
+    total
+}
+
+fn usage() -> ! {
+    eprintln!("usage: f21-rot13 [--method aio|sync] [-n nbuf] [--notify suspend|signal|thread] \
+               <infile> <outfile>");
+    exit(1);
+}
+
+fn main() {
+    let mut method = "aio".to_string();
+    let mut notify = "suspend".to_string();
+    let mut nbuf = 8;
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--method" => method = args.next().unwrap_or_else(|| usage()),
+            "--notify" => notify = args.next().unwrap_or_else(|| usage()),
+            "-n" => nbuf = args.next().and_then(|s| s.parse().ok()).unwrap_or_else(|| usage()),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 || nbuf == 0 {
+        usage();
+    }
+
+    let ifd = Fd::open(&files[0], O_RDONLY, 0)
+        .unwrap_or_else(|e| panic!("can't open {}: {}", files[0], e));
+    let ofd = Fd::open(&files[1],
+                       O_RDWR | O_CREAT | O_TRUNC,
+                       S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+        .unwrap_or_else(|e| panic!("can't create {}: {}", files[1], e));
+    let waiter = match notify.as_str() {
+        "suspend" => Waiter::Suspend,
+        "signal" => {
+            let mut mask = SigSet::empty();
+            mask.insert(SIGUSR1);
+            mask.block().expect("SIG_BLOCK error");
+            Waiter::Signal(mask)
+        }
+        "thread" => {
+            let (tx, rx) = channel();
+            Waiter::Thread(tx, rx)
+        }
+        _ => usage(),
+    };
+
+    let timer = Timer::start();
+    let size = match method.as_str() {
+        "aio" => rot13_aio(&ifd, &ofd, nbuf, &waiter),
+        "sync" => rot13_sync(&ifd, &ofd),
+        _ => usage(),
+    };
+    let times = timer.elapsed();
+
+    let (n, notify) = if method == "sync" {
+        ("-".to_string(), "-".to_string())
+    } else {
+        (nbuf.to_string(), notify)
+    };
+    println!("| method |  n | notify  |      size | {}", Times::HEADER);
+    println!("|--------|----|---------|-----------|{}", Times::SEPARATOR);
+    println!("| {:<6} | {:>2} | {:<7} | {:>9} | {}", method, n, notify, size, times);
+}
@@ -0,0 +1,150 @@
+/// Figure 14.23 Timing results comparing writev and other techniques
+///
+/// Writes a header followed by data `-n` times to `outfile` (default: 100 bytes of
+/// header, 200 bytes of data, 1048576 times as in the book), with one of three methods:
+///
+/// - `two-writes`: write the header, then write the data
+/// - `copy`: copy both into one buffer, then one write
+/// - `writev`: one writev with both buffers (`apue::io::writev_all`)
+///
+/// `--method all` (the default) runs the three one after the other and prints one table
+/// row each, like the buffer size table of Figure 3.5 (`f05-copy-stdin-stdout`).
+///
+/// $ f23-writev -n 1000 /tmp/f23-out.dat | awk 'NR > 2 {print $2, $4}'
+/// two-writes 300000
+/// copy 300000
+/// writev 300000
+/// $ f23-writev --method writev --header 3 --data 4 -n 2 /tmp/f23-out.dat > /dev/null && cat /tmp/f23-out.dat; echo; rm -f /tmp/f23-out.dat
+/// HHHDDDDHHHDDDD
+
+/// ## Timing
+///
+/// > f23-writev /tmp/f23-out.dat
+/// > f23-writev /dev/null
+///
+/// Every method writes 314572800 bytes, the tables show the fastest of three runs. The
+/// header is 100, the data 200 bytes, as in the book.
+///
+/// ### Linux
+///
+/// > uname -rsv
+/// Linux 6.18.44 #1 SMP PREEMPT_DYNAMIC
+///
+/// To a file on ext4:
+///
+/// | method     |      size | real time | user time | sys time |
+/// |------------|-----------|-----------|-----------|----------|
+/// | two-writes | 314572800 |      0.85 |      0.18 |     0.66 |
+/// | copy       | 314572800 |      0.69 |      0.10 |     0.58 |
+/// | writev     | 314572800 |      0.64 |      0.07 |     0.57 |
+///
+/// To /dev/null:
+///
+/// | method     |      size | real time | user time | sys time |
+/// |------------|-----------|-----------|-----------|----------|
+/// | two-writes | 314572800 |      0.33 |      0.16 |     0.17 |
+/// | copy       | 314572800 |      0.17 |      0.07 |     0.10 |
+/// | writev     | 314572800 |      0.23 |      0.08 |     0.15 |
+///
+/// Takeaways: as in the book two writes cost clearly more than one, the second system
+/// call per record doubles the fixed cost. Between copying and writev the difference is
+/// small: copying 300 bytes in user space is cheap, while the kernel has to check and
+/// walk the iovec array. Without real I/O (/dev/null) the copy even wins, with a file
+/// writev is slightly ahead. For header+payload records writev saves the system call
+/// without needing a buffer sized for the largest record.
+
+extern crate apue;
+extern crate libc;
+
+use apue::fd::Fd;
+use apue::io::writev_all;
+use apue::timing::{Timer, Times};
+use libc::{O_CREAT, O_TRUNC, O_WRONLY, S_IRGRP, S_IROTH, S_IRUSR, S_IWUSR};
+use std::io::IoSlice;
+use std::process::exit;
+
+fn write_all(fd: &Fd, mut buf: &[u8]) {
+    while !buf.is_empty() {
+        let n = fd.write(buf).expect("write error");
+        buf = &buf[n..];
+    }
+}
+
+fn two_writes(fd: &Fd, header: &[u8], data: &[u8], n: usize) {
+    for _ in 0..n {
+        write_all(fd, header);
+        write_all(fd, data);
+    }
+}
+
+fn copy(fd: &Fd, header: &[u8], data: &[u8], n: usize) {
+    let mut buf = vec![0; header.len() + data.len()];
+    for _ in 0..n {
+        buf[..header.len()].copy_from_slice(header);
+        buf[header.len()..].copy_from_slice(data);
+        write_all(fd, &buf);
+    }
+}
+
+fn writev(fd: &Fd, header: &[u8], data: &[u8], n: usize) {
+    for _ in 0..n {
+        writev_all(fd, &mut [IoSlice::new(header), IoSlice::new(data)]).expect("writev error");
+    }
+}
+
+fn number<I: Iterator<Item = String>>(args: &mut I) -> usize {
+    args.next().and_then(|s| s.parse().ok()).unwrap_or_else(|| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("usage: f23-writev [--method two-writes|copy|writev|all] [--header n] [--data n] \
+               [-n loops] <outfile>");
+    exit(1);
+}
+
+fn main() {
+    let mut method = "all".to_string();
+    let mut header_len = 100;
+    let mut data_len = 200;
+    let mut loops = 1048576;
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--method" => method = args.next().unwrap_or_else(|| usage()),
+            "--header" => header_len = number(&mut args),
+            "--data" => data_len = number(&mut args),
+            "-n" => loops = number(&mut args),
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        usage();
+    }
+    let methods = match method.as_str() {
+        "all" => vec!["two-writes", "copy", "writev"],
+        "two-writes" | "copy" | "writev" => vec![method.as_str()],
+        _ => usage(),
+    };
+    let header = vec![b'H'; header_len];
+    let data = vec![b'D'; data_len];
+
+    println!("| method     |      size | {}", Times::HEADER);
+    println!("|------------|-----------|{}", Times::SEPARATOR);
+    for method in methods {
+        let fd = Fd::open(&files[0],
+                          O_WRONLY | O_CREAT | O_TRUNC,
+                          S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+            .unwrap_or_else(|e| panic!("can't creat {}: {}", files[0], e));
+        let timer = Timer::start();
+        match method {
+            "two-writes" => two_writes(&fd, &header, &data, loops),
+            "copy" => copy(&fd, &header, &data, loops),
+            _ => writev(&fd, &header, &data, loops),
+        }
+        println!("| {:<10} | {:>9} | {}",
+                 method,
+                 (header_len + data_len) * loops,
+                 timer.elapsed());
+    }
+}
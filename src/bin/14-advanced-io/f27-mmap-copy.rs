@@ -0,0 +1,203 @@
+/// Figure 14.27 Copy a file using memory-mapped I/O
+///
+/// `--method` selects how the data gets from `fromfile` to `tofile`:
+///
+/// - `mmap`: as in the book, both files are mapped in chunks of 1 GiB (`apue::mmap`) and
+///   the chunks are copied with memcpy. This is the default
+/// - `rw`: read and write with a buffer of `--bufsize` bytes (default 131072), like
+///   Figure 3.5
+/// - `sendfile`: sendfile(2) from file to file, Linux only
+/// - `copy_file_range`: copy_file_range(2), the kernel copies (or shares the blocks if
+///   the filesystem supports it), Linux only
+///
+/// The timing is printed in the same table format as Figure 3.5.
+///
+/// $ dd if=/dev/urandom of=/tmp/f27-in.dat bs=1000 count=3000 2>/dev/null
+/// $ f27-mmap-copy /tmp/f27-in.dat /tmp/f27-out.dat > /dev/null && cmp /tmp/f27-in.dat /tmp/f27-out.dat && echo same
+/// same
+/// $ f27-mmap-copy --method rw --bufsize 100 /tmp/f27-in.dat /tmp/f27-out.dat > /dev/null && cmp /tmp/f27-in.dat /tmp/f27-out.dat && echo same
+/// same
+/// $ f27-mmap-copy --method mmap /dev/null /tmp/f27-out.dat | awk 'NR == 3 {print $2, $4}'
+/// mmap 0
+///
+/// Failed calls are reported with their arguments and the errno:
+///
+/// $ f27-mmap-copy /tmp/f27-nonexistent.dat /tmp/f27-out.dat 2>&1
+/// open /tmp/f27-nonexistent.dat: ENOENT: No such file or directory
+/// ERROR: return code 1
+/// $ f27-mmap-copy /tmp/f27-in.dat /tmp/f27-nonexistent/out.dat 2>&1
+/// open /tmp/f27-nonexistent/out.dat for writing: ENOENT: No such file or directory
+/// ERROR: return code 1
+///
+/// linux only:
+/// $ f27-mmap-copy --method sendfile /tmp/f27-in.dat /tmp/f27-out.dat > /dev/null && cmp /tmp/f27-in.dat /tmp/f27-out.dat && echo same
+/// same
+/// $ f27-mmap-copy --method copy_file_range /tmp/f27-in.dat /tmp/f27-out.dat > /dev/null && cmp /tmp/f27-in.dat /tmp/f27-out.dat && echo same; rm -f /tmp/f27-in.dat /tmp/f27-out.dat
+/// same
+
+/// ## Timing
+///
+/// > dd if=/dev/urandom of=/tmp/bigfile.dat bs=1048576 count=1024
+///
+/// Every method copies the file five times, the table shows the fastest run.
+/// The file is in the page cache, so this measures copying between memory and not
+/// the disk.
+///
+/// ### Linux
+///
+/// > uname -rsv
+/// Linux 6.18.44 #1 SMP PREEMPT_DYNAMIC
+///
+/// | method          |       size | real time | user time | sys time |
+/// |-----------------|------------|-----------|-----------|----------|
+/// | mmap            | 1073741824 |      0.38 |      0.10 |     0.22 |
+/// | rw              | 1073741824 |      0.38 |      0.00 |     0.33 |
+/// | sendfile        | 1073741824 |      0.33 |      0.00 |     0.26 |
+/// | copy_file_range | 1073741824 |      0.29 |      0.00 |     0.25 |
+///
+/// Takeaways: the differences are small once the file is cached. mmap saves the copy
+/// into the buffer but pays with page faults (the user time is the memcpy), the two
+/// in-kernel copies don't need a user space buffer at all. copy_file_range is the
+/// fastest and on filesystems with reflinks (btrfs, xfs) it doesn't copy the data at all.
+
+#[macro_use(err_quit)]
+extern crate apue;
+extern crate libc;
+
+use apue::{Context, LibcResult};
+use apue::fd::Fd;
+use apue::mmap::{Advice, Mmap, MmapMut, Sharing};
+use apue::timing::{Timer, Times};
+use libc::{off_t, O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, S_IRGRP, S_IROTH, S_IRUSR, S_IWUSR};
+use libc::ftruncate;
+use std::os::unix::io::AsRawFd;
+use std::process::exit;
+
+const COPYINCR: usize = 1024 * 1024 * 1024; // 1 GiB
+
+fn copy_mmap(fdin: &Fd, fdout: &Fd, size: usize) {
+    // set output file size
+    unsafe { ftruncate(fdout.as_raw_fd(), size as off_t) }
+        .check_not_negative()
+        .context(format_args!("ftruncate to {} bytes", size))
+        .unwrap_or_else(|e| err_quit!("{}", e));
+    let mut fsz = 0;
+    while fsz < size {
+        let copysz = std::cmp::min(size - fsz, COPYINCR);
+        let src = Mmap::map(fdin, fsz as off_t, copysz).expect("mmap error for input");
+        src.advise(Advice::Sequential).expect("madvise error");
+        let mut dst = MmapMut::map(fdout, fsz as off_t, copysz, Sharing::Shared)
+            .expect("mmap error for output");
+        dst.copy_from_slice(&src);
+        fsz += copysz;
+    }
+}
+
+fn copy_rw(fdin: &Fd, fdout: &Fd, bufsize: usize) {
+    let mut buf = vec![0; bufsize];
+    loop {
+        let n = fdin.read(&mut buf).expect("read error");
+        if n == 0 {
+            break;
+        }
+        let mut done = 0;
+        while done < n {
+            done += fdout.write(&buf[done..n]).expect("write error");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn copy_sendfile(fdin: &Fd, fdout: &Fd, size: usize) {
+    let mut left = size;
+    while left > 0 {
+        let n = unsafe { libc::sendfile(fdout.as_raw_fd(), fdin.as_raw_fd(), std::ptr::null_mut(), left) }
+            .check_not_negative()
+            .expect("sendfile error");
+        if n == 0 {
+            break; // the file shrunk
+        }
+        left -= n as usize;
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn copy_file_range(fdin: &Fd, fdout: &Fd, size: usize) {
+    let mut left = size;
+    while left > 0 {
+        let n = unsafe {
+            libc::copy_file_range(fdin.as_raw_fd(),
+                                  std::ptr::null_mut(),
+                                  fdout.as_raw_fd(),
+                                  std::ptr::null_mut(),
+                                  left,
+                                  0)
+        }.check_not_negative()
+            .expect("copy_file_range error");
+        if n == 0 {
+            break;
+        }
+        left -= n as usize;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_sendfile(_: &Fd, _: &Fd, _: usize) {
+    eprintln!("sendfile can only copy to sockets on this platform");
+    exit(1);
+}
+
+#[cfg(not(target_os = "linux"))]
+fn copy_file_range(_: &Fd, _: &Fd, _: usize) {
+    eprintln!("copy_file_range is Linux only");
+    exit(1);
+}
+
+fn usage() -> ! {
+    eprintln!("usage: f27-mmap-copy [--method mmap|rw|sendfile|copy_file_range] [--bufsize n] \
+               <fromfile> <tofile>");
+    exit(1);
+}
+
+fn main() {
+    let mut method = "mmap".to_string();
+    let mut bufsize = 131072;
+    let mut files = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--method" => method = args.next().unwrap_or_else(|| usage()),
+            "--bufsize" => {
+                bufsize = args.next().and_then(|s| s.parse().ok()).unwrap_or_else(|| usage())
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 2 || bufsize == 0 {
+        usage();
+    }
+
+    let fdin = Fd::open(&files[0], O_RDONLY, 0)
+        .context(format_args!("open {}", files[0]))
+        .unwrap_or_else(|e| err_quit!("{}", e));
+    let fdout = Fd::open(&files[1],
+                         O_RDWR | O_CREAT | O_TRUNC,
+                         S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+        .context(format_args!("open {} for writing", files[1]))
+        .unwrap_or_else(|e| err_quit!("{}", e));
+    let size = fdin.file_size().expect("fstat error") as usize;
+
+    let timer = Timer::start();
+    match method.as_str() {
+        "mmap" => copy_mmap(&fdin, &fdout, size),
+        "rw" => copy_rw(&fdin, &fdout, bufsize),
+        "sendfile" => copy_sendfile(&fdin, &fdout, size),
+        "copy_file_range" => copy_file_range(&fdin, &fdout, size),
+        _ => usage(),
+    }
+    let times = timer.elapsed();
+
+    println!("| method          |       size | {}", Times::HEADER);
+    println!("|-----------------|------------|{}", Times::SEPARATOR);
+    println!("| {:<15} | {:>10} | {}", method, size, times);
+}
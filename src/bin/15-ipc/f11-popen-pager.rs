@@ -0,0 +1,46 @@
+/// Figure 15.11 Copy file to pager program using popen
+///
+/// The popen implementation (Figure 15.12) lives in `apue::popen`.
+///
+/// $ echo "hello pager" > /tmp/f11-popen.txt
+/// $ PAGER=cat f11-popen-pager /tmp/f11-popen.txt
+/// hello pager
+///
+/// The command runs with the default action for SIGPIPE, so `yes` is killed silently
+/// once `head` exits:
+///
+/// $ echo 'yes | head -1' > /tmp/f11-yes.sh; PAGER='sh /tmp/f11-yes.sh' f11-popen-pager /tmp/f11-popen.txt 2>&1
+/// y
+/// $ rm /tmp/f11-popen.txt /tmp/f11-yes.sh
+
+extern crate apue;
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use apue::popen::Mode;
+use apue::{popen, pclose};
+
+// environment variable, or default
+const PAGER: &'static str = "${PAGER:-more}";
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 2 {
+        println!("usage: {} <pathname>", args[0]);
+        std::process::exit(1);
+    }
+    let fpin = File::open(&args[1]).expect(&format!("can't open {}", args[1]));
+    let mut fpout = popen(PAGER, Mode::Write).expect("popen error");
+
+    // copy argv[1] to pager
+    for line in BufReader::new(fpin).lines() {
+        let line = line.expect("read error");
+        if let Err(e) = writeln!(fpout, "{}", line) {
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                break; // pager quit early
+            }
+            panic!("write error to pipe: {}", e);
+        }
+    }
+    pclose(fpout).expect("pclose error");
+}
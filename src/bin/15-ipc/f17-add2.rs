@@ -0,0 +1,26 @@
+/// Figure 15.17 Simple filter to add two numbers
+///
+/// Used as coprocess by f18-coprocess. Rust's stdout is line buffered even when
+/// connected to a pipe, so we don't run into the deadlock of Figure 15.19.
+///
+/// $ printf "1 2\n40 2\nfoo\n" | f17-add2
+/// 3
+/// 42
+/// invalid args
+
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = line.expect("read error");
+        let nums: Vec<Option<i64>> = line.split_whitespace().map(|n| n.parse().ok()).collect();
+        let res = match nums.as_slice() {
+            &[Some(int1), Some(int2)] => format!("{}", int1 + int2),
+            _ => "invalid args".to_string(),
+        };
+        writeln!(out, "{}", res).expect("write error");
+    }
+}
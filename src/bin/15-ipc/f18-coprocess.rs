@@ -0,0 +1,51 @@
+/// Figure 15.18 Program to drive the add2 filter
+///
+/// The pipe plumbing and the SIGPIPE handling moved into `apue::coprocess::Coprocess`,
+/// when the coprocess dies `send` returns a BrokenPipe error instead of a signal
+/// killing this program.
+///
+/// $ printf "1 2\n3 4\n" | f18-coprocess
+/// 3
+/// 7
+/// add2: normal termination, exit status = 0
+///
+/// `f18-coprocess filter [arg...]` drives `filter` instead of add2 and leaves it to the
+/// drop of `Coprocess` to reap it. The output of `yes` isn't read to the end, dropping
+/// closes the pipe so `yes` dies of SIGPIPE instead of blocking forever:
+///
+/// $ echo x | f18-coprocess yes
+/// y
+/// $ f18-coprocess nosuchfilter 2>&1
+/// can't start nosuchfilter: No such file or directory (os error 2)
+/// ERROR: return code 1
+
+#[macro_use(err_quit)]
+extern crate apue;
+
+use std::ffi::OsString;
+use std::io::{self, BufRead};
+use std::time::Duration;
+use apue::coprocess::Coprocess;
+
+fn main() {
+    let args: Vec<OsString> = std::env::args_os().skip(1).collect();
+    let add2 = std::env::current_exe().unwrap().with_file_name("f17-add2").into_os_string();
+    let (filter, filter_args) = args.split_first().unwrap_or((&add2, &[]));
+    let mut coproc = Coprocess::spawn(filter, &filter_args.iter().collect::<Vec<_>>())
+        .unwrap_or_else(|e| err_quit!("can't start {}: {}", filter.to_string_lossy(), e));
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = line.expect("read error");
+        coproc.send(&line).expect("write error to pipe");
+        match coproc.recv_timeout(Duration::from_secs(5)).expect("read error from pipe") {
+            Some(answer) => println!("{}", answer),
+            None => {
+                println!("child closed pipe");
+                break;
+            }
+        }
+    }
+    if args.is_empty() {
+        println!("add2: {}", coproc.wait().expect("waitpid error"));
+    }
+}
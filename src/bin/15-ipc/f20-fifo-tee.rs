@@ -0,0 +1,133 @@
+/// Figure 15.20 Using a FIFO and tee to send a stream to two different processes
+///
+/// The book combines mkfifo(1) and tee(1), this program does both: standard input
+/// is copied to standard output and to every FIFO given as argument, missing FIFOs
+/// are created. Consumers can come and go while the stream runs:
+///
+/// - a FIFO without reader (ENXIO) is skipped and opened again for the next block
+/// - a consumer that closed its end (EPIPE) is dropped until it reopens the FIFO
+/// - a consumer that doesn't keep up (EAGAIN) is waited for, with `-d` the block is
+///   dropped for this consumer instead
+///
+/// The input is copied in blocks of at most PIPE_BUF bytes, so every write into a
+/// FIFO is atomic and a dropped block never leaves half a block behind.
+/// With `-w` it waits until every FIFO has a reader before copying anything.
+///
+/// $ rm -f /tmp/f20-fifo1 /tmp/f20-fifo2
+/// $ (sleep 0.2; tr a-z A-Z < /tmp/f20-fifo1 > /tmp/f20-out1.txt) & (sleep 0.2; rev < /tmp/f20-fifo2 > /tmp/f20-out2.txt) & echo hello | f20-fifo-tee -w /tmp/f20-fifo1 /tmp/f20-fifo2; wait; cat /tmp/f20-out1.txt /tmp/f20-out2.txt
+/// hello
+/// HELLO
+/// olleh
+/// $ echo nobody listens | f20-fifo-tee /tmp/f20-fifo1
+/// nobody listens
+/// $ rm -f /tmp/f20-fifo1 /tmp/f20-fifo2 /tmp/f20-out1.txt /tmp/f20-out2.txt
+
+extern crate apue;
+extern crate libc;
+
+use apue::fd::Fd;
+use apue::fifo;
+use apue::signals::{Handler, SigAction};
+use libc::{pollfd, poll, EINTR, EPIPE, POLLOUT, SIGPIPE, S_IRUSR, S_IWUSR, S_IRGRP, S_IROTH};
+use std::io::{self, Error, ErrorKind, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+struct Consumer {
+    path: String,
+    fd: Option<Fd>,
+}
+
+/// block until `fd` has room for more data
+fn wait_writable(fd: &Fd) -> io::Result<()> {
+    let mut pfd = pollfd {
+        fd: fd.as_raw_fd(),
+        events: POLLOUT,
+        revents: 0,
+    };
+    while unsafe { poll(&mut pfd, 1, -1) } < 0 {
+        let err = Error::last_os_error();
+        if err.raw_os_error() != Some(EINTR) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// write the whole block, Ok(false) if the reader is gone
+fn write_block(fd: &Fd, mut block: &[u8], drop_when_full: bool) -> io::Result<bool> {
+    while !block.is_empty() {
+        match fd.write(block) {
+            Ok(n) => block = &block[n..],
+            Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
+                if drop_when_full {
+                    return Ok(true);
+                }
+                wait_writable(fd)?;
+            }
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(ref e) if e.raw_os_error() == Some(EPIPE) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+fn main() {
+    let mut drop_when_full = false;
+    let mut wait_for_readers = false;
+    let mut consumers = Vec::new();
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "-d" => drop_when_full = true,
+            "-w" => wait_for_readers = true,
+            _ => consumers.push(Consumer { path: arg, fd: None }),
+        }
+    }
+    if consumers.is_empty() {
+        eprintln!("usage: f20-fifo-tee [-d] [-w] fifo...");
+        std::process::exit(1);
+    }
+
+    // a vanished consumer shows up as EPIPE
+    SigAction::new(Handler::Ignore).install(SIGPIPE).expect("signal error").keep();
+    for c in consumers.iter() {
+        fifo::create(&c.path, S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)
+            .unwrap_or_else(|e| panic!("can't create {}: {}", c.path, e));
+    }
+    for c in consumers.iter_mut() {
+        while wait_for_readers && c.fd.is_none() {
+            c.fd = fifo::open_write(&c.path).expect("open error");
+            if c.fd.is_none() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    let stdin = io::stdin();
+    let mut stdin = stdin.lock();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut buf = [0; fifo::PIPE_BUF];
+    loop {
+        let n = match stdin.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => panic!("read error: {}", e),
+        };
+        stdout.write_all(&buf[..n]).expect("write error");
+        for c in consumers.iter_mut() {
+            if c.fd.is_none() {
+                c.fd = fifo::open_write(&c.path).expect("open error");
+            }
+            let connected = match c.fd {
+                Some(ref fd) => write_block(fd, &buf[..n], drop_when_full).expect("write error"),
+                None => continue,
+            };
+            if !connected {
+                c.fd = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+/// Figure 15.27 Timing comparison of IPC alternatives
+///
+/// The book sends 100,000 messages of 100 bytes through an XSI message queue, a
+/// full-duplex pipe and a UNIX domain socket. This version sends `count` messages
+/// (default 100000) of several sizes from a parent to its child through
+///
+/// - `msgqueue`: XSI message queue (`apue::msgqueue::MsgQueue`)
+/// - `mqueue`: POSIX message queue (`apue::msgqueue::Mq`), Linux only
+/// - `pipe`: pipe, the reader reads exactly one message per loop
+/// - `socketpair`: UNIX domain stream socket
+///
+/// The times are in seconds, user and sys time are the sums of parent and child.
+///
+/// linux only:
+/// $ f27-ipc-timing 100 | awk 'NR > 2 {print $2}' | sort | uniq -c
+///       4 mqueue
+///       4 msgqueue
+///       4 pipe
+///       4 socketpair
+
+/// ## Timing
+///
+/// > cargo build --release --bin f27-ipc-timing && target/release/f27-ipc-timing
+///
+/// ### Linux
+///
+/// > uname -rsv
+/// Linux 6.18.44 #1 SMP PREEMPT_DYNAMIC
+///
+/// | method     | size | messages | real time | user time | sys time |
+/// |------------|------|----------|-----------|-----------|----------|
+/// | msgqueue   |    8 |   100000 |      0.11 |      0.03 |     0.08 |
+/// | mqueue     |    8 |   100000 |      0.10 |      0.02 |     0.08 |
+/// | pipe       |    8 |   100000 |      0.05 |      0.02 |     0.03 |
+/// | socketpair |    8 |   100000 |      0.13 |      0.03 |     0.09 |
+/// | msgqueue   |  128 |   100000 |      0.17 |      0.02 |     0.15 |
+/// | mqueue     |  128 |   100000 |      0.15 |      0.02 |     0.13 |
+/// | pipe       |  128 |   100000 |      0.07 |      0.02 |     0.05 |
+/// | socketpair |  128 |   100000 |      0.15 |      0.01 |     0.13 |
+/// | msgqueue   | 1024 |   100000 |      0.26 |      0.06 |     0.19 |
+/// | mqueue     | 1024 |   100000 |      0.13 |      0.03 |     0.10 |
+/// | pipe       | 1024 |   100000 |      0.09 |      0.01 |     0.08 |
+/// | socketpair | 1024 |   100000 |      0.21 |      0.03 |     0.17 |
+/// | msgqueue   | 8192 |   100000 |      0.52 |      0.09 |     0.42 |
+/// | mqueue     | 8192 |   100000 |      0.27 |      0.08 |     0.19 |
+/// | pipe       | 8192 |   100000 |      0.31 |      0.07 |     0.24 |
+/// | socketpair | 8192 |   100000 |      0.22 |      0.04 |     0.18 |
+///
+/// Takeaways: up to 1024 bytes the pipe is fastest, with 8192 bytes the socket and the
+/// POSIX message queue win. The XSI message queue isn't faster than the POSIX one any
+/// more (the book measured the opposite on Solaris), from 1024 bytes on it's the
+/// slowest. Numbers vary by about 20% between runs.
+
+extern crate apue;
+extern crate libc;
+
+use apue::LibcResult;
+use apue::fd::{self, Fd};
+use apue::msgqueue::MsgQueue;
+#[cfg(target_os = "linux")]
+use apue::msgqueue::Mq;
+use apue::timing::{Timer, Times};
+use libc::{pid_t, AF_UNIX, SOCK_STREAM, S_IRUSR, S_IWUSR};
+use libc::{_exit, fork, getpid, socketpair, waitpid};
+use std::os::unix::io::FromRawFd;
+
+#[derive(Clone, Copy)]
+enum Method {
+    MsgQueue,
+    #[cfg(target_os = "linux")]
+    Mqueue,
+    Pipe,
+    SocketPair,
+}
+
+impl Method {
+    fn name(&self) -> &'static str {
+        match *self {
+            Method::MsgQueue => "msgqueue",
+            #[cfg(target_os = "linux")]
+            Method::Mqueue => "mqueue",
+            Method::Pipe => "pipe",
+            Method::SocketPair => "socketpair",
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const METHODS: &[Method] = &[Method::MsgQueue, Method::Mqueue, Method::Pipe, Method::SocketPair];
+#[cfg(target_os = "macos")]
+const METHODS: &[Method] = &[Method::MsgQueue, Method::Pipe, Method::SocketPair];
+
+// one end of the connection, the child receives and the parent sends
+enum Channel {
+    MsgQueue(MsgQueue),
+    #[cfg(target_os = "linux")]
+    Mqueue(Mq),
+    Stream(Fd, Fd),
+}
+
+impl Channel {
+    fn new(method: Method, size: usize) -> Channel {
+        match method {
+            Method::MsgQueue => {
+                Channel::MsgQueue(MsgQueue::private(S_IRUSR | S_IWUSR).expect("msgget error"))
+            }
+            #[cfg(target_os = "linux")]
+            Method::Mqueue => {
+                // unlinked right away, parent and child share the open descriptor
+                let name = format!("/apue-f27-{}", unsafe { getpid() });
+                let mq = Mq::create(&name, S_IRUSR | S_IWUSR, 10, size).expect("mq_open error");
+                Mq::unlink(&name).expect("mq_unlink error");
+                Channel::Mqueue(mq)
+            }
+            Method::Pipe => {
+                let (r, w) = fd::pipe().expect("pipe error");
+                Channel::Stream(r, w)
+            }
+            Method::SocketPair => {
+                let mut fds = [0; 2];
+                unsafe { socketpair(AF_UNIX, SOCK_STREAM, 0, fds.as_mut_ptr()) }
+                    .check_not_negative()
+                    .expect("socketpair error");
+                unsafe { Channel::Stream(Fd::from_raw_fd(fds[0]), Fd::from_raw_fd(fds[1])) }
+            }
+        }
+    }
+
+    fn send<const N: usize>(&self, msg: &[u8; N]) {
+        match *self {
+            Channel::MsgQueue(ref q) => q.send(1, msg).expect("msgsnd error"),
+            #[cfg(target_os = "linux")]
+            Channel::Mqueue(ref mq) => mq.send(msg, 0).expect("mq_send error"),
+            Channel::Stream(_, ref w) => {
+                let mut done = 0;
+                while done < N {
+                    done += w.write(&msg[done..]).expect("write error");
+                }
+            }
+        }
+    }
+
+    fn recv<const N: usize>(&self) -> [u8; N] {
+        match *self {
+            Channel::MsgQueue(ref q) => q.recv::<[u8; N]>(0).expect("msgrcv error").1,
+            #[cfg(target_os = "linux")]
+            Channel::Mqueue(ref mq) => mq.recv::<[u8; N]>().expect("mq_receive error").0,
+            Channel::Stream(ref r, _) => {
+                // a stream doesn't keep message boundaries
+                let mut buf = [0; N];
+                let mut done = 0;
+                while done < N {
+                    match r.read(&mut buf[done..]).expect("read error") {
+                        0 => panic!("unexpected end of file"),
+                        n => done += n,
+                    }
+                }
+                buf
+            }
+        }
+    }
+
+    fn remove(self) {
+        if let Channel::MsgQueue(q) = self {
+            q.remove().expect("msgctl error");
+        }
+    }
+}
+
+fn run<const N: usize>(method: Method, count: usize) -> Times {
+    let channel = Channel::new(method, N);
+    let msg = [0x42u8; N];
+    let timer = Timer::start();
+
+    let pid: pid_t = unsafe { fork() }.check_not_negative().expect("fork error");
+    if pid == 0 {
+        for _ in 0..count {
+            let buf = channel.recv::<N>();
+            assert_eq!(buf[N - 1], 0x42);
+        }
+        unsafe { _exit(0) };
+    }
+    for _ in 0..count {
+        channel.send(&msg);
+    }
+    unsafe { waitpid(pid, std::ptr::null_mut(), 0) }.check_not_negative().expect("waitpid error");
+
+    // the child was waited for, so its CPU time is included
+    let times = timer.elapsed();
+    channel.remove();
+    times
+}
+
+fn main() {
+    let count = std::env::args()
+        .nth(1)
+        .map(|s| s.parse().expect("usage: f27-ipc-timing [count]"))
+        .unwrap_or(100000);
+    println!("| method     | size | messages | {}", Times::HEADER);
+    println!("|------------|------|----------|{}", Times::SEPARATOR);
+    for &size in [8, 128, 1024, 8192].iter() {
+        for &method in METHODS {
+            let times = match size {
+                8 => run::<8>(method, count),
+                128 => run::<128>(method, count),
+                1024 => run::<1024>(method, count),
+                _ => run::<8192>(method, count),
+            };
+            println!("| {:<10} | {:>4} | {:>8} | {}", method.name(), size, count, times);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+/// Figure 15.33 IPC between parent and child using memory mapped I/O of /dev/zero
+///
+/// Parent and child take turns incrementing a long integer in shared memory, synchronized
+/// with TELL_WAIT from `apue::sync_parent_child`. Other than in the book the parent waits
+/// for the child and prints the final counter.
+///
+/// The optional argument selects the shared memory (all from `apue::ipc::shm`):
+///
+/// - `dev-zero`: mapping of /dev/zero (default, as in the book)
+/// - `anon`: anonymous mapping with MAP_ANON (Figure 15.33 with the change of 15.9)
+/// - `posix`: POSIX shared memory object
+/// - `xsi`: XSI shared memory segment
+///
+/// $ f33-dev-zero-counter
+/// counter = 1000
+/// $ f33-dev-zero-counter anon
+/// counter = 1000
+/// $ f33-dev-zero-counter posix
+/// counter = 1000
+/// $ f33-dev-zero-counter xsi
+/// counter = 1000
+
+extern crate apue;
+extern crate libc;
+
+use apue::LibcResult;
+use apue::ipc::shm::{PosixShm, Region, SysvShm};
+use apue::sync_parent_child::{self, TellWait};
+use libc::{c_long, fork, getpid, getppid, waitpid, S_IRUSR, S_IWUSR};
+use std::mem::size_of;
+use std::process::exit;
+
+const NLOOPS: c_long = 1000;
+const SIZE: usize = size_of::<c_long>();
+
+fn update(ptr: *mut c_long) -> c_long {
+    unsafe {
+        let old = *ptr;
+        *ptr += 1;
+        old
+    }
+}
+
+fn run(area: *mut c_long, tw: &dyn TellWait) {
+    let pid = unsafe { fork() }.check_not_negative().expect("fork error");
+    if pid > 0 {
+        // parent
+        for i in (0..NLOOPS).filter(|i| i % 2 == 0) {
+            let counter = update(area);
+            if counter != i {
+                eprintln!("parent: expected {}, got {}", i, counter);
+                exit(1);
+            }
+            tw.tell_child(pid).expect("TELL_CHILD error");
+            tw.wait_child().expect("WAIT_CHILD error");
+        }
+        unsafe { waitpid(pid, std::ptr::null_mut(), 0) }.check_not_negative().expect("waitpid error");
+        println!("counter = {}", unsafe { *area });
+    } else {
+        // child
+        for i in (1..NLOOPS + 1).filter(|i| i % 2 == 1) {
+            tw.wait_parent().expect("WAIT_PARENT error");
+            let counter = update(area);
+            if counter != i {
+                eprintln!("child: expected {}, got {}", i, counter);
+                exit(1);
+            }
+            tw.tell_parent(unsafe { getppid() }).expect("TELL_PARENT error");
+        }
+    }
+}
+
+fn main() {
+    let tw = sync_parent_child::by_name("signal").expect("TELL_WAIT error");
+    match std::env::args().nth(1).as_ref().map(|s| s.as_str()).unwrap_or("dev-zero") {
+        "dev-zero" => {
+            let mut area = Region::dev_zero(SIZE).expect("mmap error");
+            run(area.as_mut_ptr() as *mut c_long, &*tw);
+        }
+        "anon" => {
+            let mut area = Region::anonymous(SIZE).expect("mmap error");
+            run(area.as_mut_ptr() as *mut c_long, &*tw);
+        }
+        "posix" => {
+            // new objects are zero filled, the name isn't needed after mapping
+            let name = format!("/apue-f33-{}", unsafe { getpid() });
+            let shm = PosixShm::create(&name, SIZE, S_IRUSR | S_IWUSR).expect("shm_open error");
+            PosixShm::unlink(&name).expect("shm_unlink error");
+            let mut area = shm.map().expect("mmap error");
+            run(area.as_mut_ptr() as *mut c_long, &*tw);
+        }
+        "xsi" => {
+            // marked for removal right away, it's destroyed after the last shmdt
+            let shm = SysvShm::private(SIZE, S_IRUSR | S_IWUSR).expect("shmget error");
+            let mut area = shm.attach().expect("shmat error");
+            shm.remove().expect("shmctl error");
+            run(area.as_mut_ptr() as *mut c_long, &*tw);
+        }
+        other => {
+            eprintln!("usage: f33-dev-zero-counter [dev-zero|anon|posix|xsi], not {}", other);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Figure 15.18: Driving a coprocess
+//!
+//! A coprocess is a filter whose standard input and standard output are both connected
+//! to pipes of the parent. `Coprocess` sends it lines and reads lines back, optionally
+//! with a timeout (using poll(2), the filter might never answer).
+//!
+//! The book installs a SIGPIPE handler that exits the program if the coprocess dies.
+//! A library can't do that, so while writing SIGPIPE is blocked for the calling thread: a
+//! dead coprocess results in an `ErrorKind::BrokenPipe` error and the pending SIGPIPE is
+//! consumed before the mask is restored. The coprocess itself gets SIGPIPE back with
+//! its default action.
+
+use cstr::CStrArgs;
+use fd::{self, Fd};
+use libc::{c_int, pid_t, pollfd};
+use libc::{EINTR, EPIPE, POLLIN, POLLOUT, SIGPIPE, SIG_BLOCK, SIG_DFL, SIG_SETMASK,
+           STDIN_FILENO, STDOUT_FILENO, WNOHANG};
+use libc::{dup2, execvp, poll, pthread_sigmask, sigwait, signal, waitpid};
+use process;
+use signals::SigSet;
+use std::ffi::OsStr;
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+use wait::WaitStatus;
+
+pub struct Coprocess {
+    pid: pid_t,
+    // stdin of the coprocess, None after close_stdin
+    to: Option<Fd>,
+    // stdout of the coprocess, None once dropped
+    from: Option<Fd>,
+    // bytes read after the last newline
+    buf: Vec<u8>,
+    status: Option<WaitStatus>,
+}
+
+/// wait until `fd` is ready for `events`, false on timeout
+fn wait_ready(fd: &Fd, events: i16, deadline: Option<Instant>) -> Result<bool> {
+    loop {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    return Ok(false);
+                }
+                let left = deadline - now;
+                // round up, otherwise we'd spin for the last millisecond
+                (left.as_secs() * 1000 + u64::from(left.subsec_nanos()).div_ceil(1_000_000)) as c_int
+            }
+            None => -1,
+        };
+        let mut pfd = pollfd {
+            fd: fd.as_raw_fd(),
+            events: events,
+            revents: 0,
+        };
+        match unsafe { poll(&mut pfd, 1, timeout) } {
+            n if n > 0 => return Ok(true),
+            0 => {}
+            _ => {
+                let err = Error::last_os_error();
+                if err.raw_os_error() != Some(EINTR) {
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+impl Coprocess {
+    /// start `program` (searched in PATH) with `args`, argv[0] is `program`. Fails with
+    /// the error of execvp if the program can't be executed
+    pub fn spawn<S: AsRef<OsStr>>(program: S, args: &[S]) -> Result<Coprocess> {
+        let argv = CStrArgs::argv(Some(&program).into_iter().chain(args))?;
+
+        let (child_stdin, to) = fd::pipe()?;
+        let (from, child_stdout) = fd::pipe()?;
+        let exec = || unsafe {
+            // the pipes are close-on-exec, only the dup2'ed copies survive
+            dup2(child_stdin.as_raw_fd(), STDIN_FILENO);
+            dup2(child_stdout.as_raw_fd(), STDOUT_FILENO);
+            // Rust ignores SIGPIPE, the filter would inherit that
+            signal(SIGPIPE, SIG_DFL);
+            execvp(argv.ptr(0), argv.as_ptr());
+            Error::last_os_error().raw_os_error().unwrap_or(0)
+        };
+        let (pid, errno) = unsafe { process::fork_exec(exec, |_| {})? };
+
+        let mut coprocess = Coprocess {
+            pid: pid,
+            to: Some(to),
+            from: Some(from),
+            buf: Vec::new(),
+            status: None,
+        };
+        if let Some(errno) = errno {
+            coprocess.wait()?;
+            return Err(Error::from_raw_os_error(errno));
+        }
+        Ok(coprocess)
+    }
+
+    pub fn id(&self) -> pid_t {
+        self.pid
+    }
+
+    /// send `line` followed by a newline
+    pub fn send(&mut self, line: &str) -> Result<()> {
+        self.send_deadline(line, None)
+    }
+
+    /// like `send`, but fails with `ErrorKind::TimedOut` if the coprocess doesn't
+    /// read its input in time
+    pub fn send_timeout(&mut self, line: &str, timeout: Duration) -> Result<()> {
+        self.send_deadline(line, Some(Instant::now() + timeout))
+    }
+
+    fn send_deadline(&mut self, line: &str, deadline: Option<Instant>) -> Result<()> {
+        let mut data = Vec::with_capacity(line.len() + 1);
+        data.extend_from_slice(line.as_bytes());
+        data.push(b'\n');
+        let to = match self.to {
+            Some(ref to) => to,
+            None => return Err(Error::from_raw_os_error(EPIPE)),
+        };
+
+        let mut written = 0;
+        while written < data.len() {
+            if deadline.is_some() && !wait_ready(to, POLLOUT, deadline)? {
+                return Err(Error::new(ErrorKind::TimedOut, "coprocess doesn't read its input"));
+            }
+            match write_nosigpipe(to, &data[written..]) {
+                Ok(n) => written += n,
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// next line without the newline, None when the coprocess closed its output
+    pub fn recv(&mut self) -> Result<Option<String>> {
+        self.recv_deadline(None)
+    }
+
+    /// like `recv`, but fails with `ErrorKind::TimedOut` if no full line arrives in time.
+    /// Already received parts of the line are kept for the next call
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<Option<String>> {
+        self.recv_deadline(Some(Instant::now() + timeout))
+    }
+
+    fn recv_deadline(&mut self, deadline: Option<Instant>) -> Result<Option<String>> {
+        let mut chunk = [0; 4096];
+        loop {
+            if let Some(pos) = self.buf.iter().position(|&b| b == b'\n') {
+                let rest = self.buf.split_off(pos + 1);
+                let mut line = ::std::mem::replace(&mut self.buf, rest);
+                line.pop();
+                return String::from_utf8(line)
+                    .map(Some)
+                    .map_err(|e| Error::new(ErrorKind::InvalidData, e));
+            }
+            let from = match self.from {
+                Some(ref mut from) => from,
+                None => return Ok(None),
+            };
+            if deadline.is_some() && !wait_ready(from, POLLIN, deadline)? {
+                return Err(Error::new(ErrorKind::TimedOut, "no answer from coprocess"));
+            }
+            match from.read(&mut chunk) {
+                Ok(0) => {
+                    // child closed pipe, a last line without newline is still returned
+                    if self.buf.is_empty() {
+                        return Ok(None);
+                    }
+                    let line = ::std::mem::take(&mut self.buf);
+                    return String::from_utf8(line)
+                        .map(Some)
+                        .map_err(|e| Error::new(ErrorKind::InvalidData, e));
+                }
+                Ok(n) => self.buf.extend_from_slice(&chunk[..n]),
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// close the input of the coprocess so it sees end of file, its output can still be read
+    pub fn close_stdin(&mut self) {
+        self.to = None;
+    }
+
+    /// the exit status if the coprocess already terminated
+    pub fn try_status(&mut self) -> Result<Option<WaitStatus>> {
+        self.waitpid(WNOHANG)
+    }
+
+    /// close its input and wait for the coprocess to terminate
+    pub fn wait(&mut self) -> Result<WaitStatus> {
+        self.close_stdin();
+        self.waitpid(0).map(|status| status.unwrap())
+    }
+
+    fn waitpid(&mut self, options: c_int) -> Result<Option<WaitStatus>> {
+        if self.status.is_none() {
+            let mut status = 0;
+            loop {
+                match unsafe { waitpid(self.pid, &mut status, options) } {
+                    0 => return Ok(None),
+                    pid if pid > 0 => break,
+                    _ => {
+                        let err = Error::last_os_error();
+                        if err.raw_os_error() != Some(EINTR) {
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+            self.status = Some(WaitStatus::from_raw(status));
+        }
+        Ok(self.status)
+    }
+}
+
+impl Drop for Coprocess {
+    /// closes the pipes and reaps the coprocess, filters terminate on end of file. A
+    /// coprocess that still writes gets SIGPIPE instead of blocking on the full pipe
+    fn drop(&mut self) {
+        self.from = None;
+        let _ = self.wait();
+    }
+}
+
+/// write with SIGPIPE blocked for this thread, a SIGPIPE caused by the write is consumed
+fn write_nosigpipe(fd: &Fd, buf: &[u8]) -> Result<usize> {
+    let sigpipe: SigSet = [SIGPIPE].iter().cloned().collect();
+    let mut oldmask = SigSet::empty();
+    unsafe {
+        pthread_sigmask(SIG_BLOCK, sigpipe.as_ptr(), oldmask.as_mut_ptr());
+    }
+    // a SIGPIPE that was pending before belongs to somebody else
+    let was_pending = SigSet::pending().map(|p| p.contains(SIGPIPE)).unwrap_or(true);
+    let res = fd.write(buf);
+    if let Err(ref e) = res {
+        let caused_sigpipe = !was_pending && !oldmask.contains(SIGPIPE) &&
+                             SigSet::pending().map(|p| p.contains(SIGPIPE)).unwrap_or(false);
+        if e.raw_os_error() == Some(EPIPE) && caused_sigpipe {
+            let mut signo = 0;
+            unsafe {
+                sigwait(sigpipe.as_ptr(), &mut signo);
+            }
+        }
+    }
+    unsafe {
+        pthread_sigmask(SIG_SETMASK, oldmask.as_ptr(), ::std::ptr::null_mut());
+    }
+    res
+}
@@ -0,0 +1,137 @@
+//! C strings for libc calls
+//!
+//! The `cstr!` macro returns the pointer of a temporary `CString`, which dangles as soon
+//! as the statement is over. The functions here own the strings for as long as the
+//! pointers are used: `with_cstr` for a single argument, `CStrArgs` for the argument
+//! and environment vectors of execv/execve and the arguments of variadic calls like
+//! execl.
+//!
+//! Paths and arguments are taken as `OsStr`, their bytes are passed on as they are,
+//! they don't have to be UTF-8. A NUL byte inside fails with `ErrorKind::InvalidInput`.
+
+use libc::c_char;
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::ffi::OsStrExt;
+use std::ptr::null;
+use std::slice;
+
+/// the bytes of `s` as C string, e.g. of a `Path`
+pub fn to_cstring<S: AsRef<OsStr>>(s: S) -> Result<CString> {
+    CString::new(s.as_ref().as_bytes()).map_err(|e| Error::new(ErrorKind::InvalidInput, e))
+}
+
+/// call `f` with `s` as C string, e.g.
+/// `with_cstr(path, |path| unsafe { libc::unlink(path.as_ptr()) })`
+pub fn with_cstr<S, T, F>(s: S, f: F) -> Result<T>
+    where S: AsRef<OsStr>,
+          F: FnOnce(&CStr) -> T
+{
+    let s = to_cstring(s)?;
+    Ok(f(&s))
+}
+
+/// Owned C strings and a null terminated array of pointers to them, the `argv` and
+/// `envp` of execv(3) and execve(2). The pointers stay valid until `CStrArgs` is
+/// dropped, also when it's moved.
+#[derive(Debug)]
+pub struct CStrArgs {
+    strings: Vec<CString>,
+    // a pointer into every string of `strings` and the terminating null
+    ptrs: Vec<*const c_char>,
+}
+
+// the pointers only point into the strings owned by the same value
+unsafe impl Send for CStrArgs {}
+unsafe impl Sync for CStrArgs {}
+
+impl CStrArgs {
+    pub fn new() -> CStrArgs {
+        CStrArgs {
+            strings: Vec::new(),
+            ptrs: vec![null()],
+        }
+    }
+
+    /// `args` as argument vector, the first one is argv[0]
+    pub fn argv<I, S>(args: I) -> Result<CStrArgs>
+        where I: IntoIterator<Item = S>,
+              S: AsRef<OsStr>
+    {
+        let mut argv = CStrArgs::new();
+        for arg in args {
+            argv.push(arg)?;
+        }
+        Ok(argv)
+    }
+
+    /// `vars` as environment in "name=value" form. A name containing '=' fails with
+    /// `ErrorKind::InvalidInput`
+    pub fn envp<I, K, V>(vars: I) -> Result<CStrArgs>
+        where I: IntoIterator<Item = (K, V)>,
+              K: AsRef<OsStr>,
+              V: AsRef<OsStr>
+    {
+        let mut envp = CStrArgs::new();
+        for (name, value) in vars {
+            let name = name.as_ref();
+            if name.is_empty() || name.as_bytes().contains(&b'=') {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                      format!("invalid variable name {:?}", name)));
+            }
+            let mut var = OsString::from(name);
+            var.push("=");
+            var.push(value);
+            envp.push(var)?;
+        }
+        Ok(envp)
+    }
+
+    /// the environment of the calling process
+    pub fn from_env() -> Result<CStrArgs> {
+        CStrArgs::envp(::std::env::vars_os())
+    }
+
+    pub fn push<S: AsRef<OsStr>>(&mut self, s: S) -> Result<&mut CStrArgs> {
+        let s = to_cstring(s)?;
+        // the heap buffer of the CString doesn't move when `strings` grows
+        let last = self.ptrs.len() - 1;
+        self.ptrs[last] = s.as_ptr();
+        self.ptrs.push(null());
+        self.strings.push(s);
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&CStr> {
+        self.strings.get(i).map(|s| s.as_c_str())
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, CString> {
+        self.strings.iter()
+    }
+
+    /// the pointer to string `i` for variadic calls like execl, `ptr(len())` is the
+    /// terminating null. Panics if `i > len()`
+    pub fn ptr(&self, i: usize) -> *const c_char {
+        self.ptrs[i]
+    }
+
+    /// the null terminated array of pointers, for execv and execve
+    pub fn as_ptr(&self) -> *const *const c_char {
+        self.ptrs.as_ptr()
+    }
+}
+
+impl Default for CStrArgs {
+    fn default() -> CStrArgs {
+        CStrArgs::new()
+    }
+}
@@ -0,0 +1,154 @@
+//! Daemon processes, chapter 13
+//!
+//! `daemonize` turns the calling process into a daemon (Figure 13.1). A daemon that must
+//! only run once holds a write lock on its `PidFile` (Figure 13.6), and
+//! `handle_signals` is the SIGHUP/SIGTERM thread of Figure 13.7.
+//!
+//! A daemon has no terminal, so after `daemonize` diagnostics go to syslog, also those
+//! of the `log_*` macros.
+
+use libc::{c_int, pid_t, O_CREAT, O_RDONLY, O_RDWR, SEEK_SET, SIGHUP, SIGTERM, S_IRGRP, S_IROTH,
+           S_IRUSR, S_IWUSR, LOG_CONS};
+use libc::{chdir, close, dup, fork, ftruncate, getpid, open, setsid, umask};
+use error::set_log_to_stderr;
+use fd::Fd;
+use limits::open_max;
+use lock::{lockfile, Lock};
+use signals::{Handler, SigAction, SigSet};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::thread::{self, JoinHandle};
+use syslog::{openlog, syslog, Facility, Priority};
+use LibcResult;
+
+/// Figure 13.1: become a daemon and open the syslog with `name` as identifier.
+///
+/// - clear the file mode creation mask
+/// - fork, the parent exits, so the child isn't a process group leader
+/// - setsid: new session without a controlling terminal
+/// - ignore SIGHUP and fork again, the parent (the session leader) exits. The
+///   daemon isn't a session leader and can't acquire a controlling terminal
+/// - change the working directory to / so no file system is kept busy
+/// - close all descriptors up to `limits::open_max()` and open /dev/null as
+///   stdin, stdout and stderr
+///
+/// Only the daemon returns. Errors after the first fork are returned in the child,
+/// the caller's parent has already seen it exit successfully.
+pub fn daemonize(name: &str) -> Result<()> {
+    unsafe {
+        umask(0);
+        let maxfd = open_max() as c_int;
+
+        // become a session leader to lose the controlling terminal
+        if fork().check_not_negative()? != 0 {
+            exit(0);
+        }
+        setsid().check_not_negative()?;
+
+        // ensure future opens won't allocate controlling terminals
+        SigAction::new(Handler::Ignore).install(SIGHUP)?.keep();
+        if fork().check_not_negative()? != 0 {
+            exit(0);
+        }
+
+        chdir(b"/\0".as_ptr() as *const _).check_not_negative()?;
+
+        for fd in 0..maxfd {
+            close(fd);
+        }
+        let fd0 = open(b"/dev/null\0".as_ptr() as *const _, O_RDWR);
+        let fd1 = dup(0);
+        let fd2 = dup(0);
+
+        openlog(name, LOG_CONS, Facility::Daemon);
+        set_log_to_stderr(false);
+        if fd0 != 0 || fd1 != 1 || fd2 != 2 {
+            let msg = format!("unexpected file descriptors {} {} {}", fd0, fd1, fd2);
+            syslog(Priority::Error, &msg);
+            return Err(Error::other(msg));
+        }
+    }
+    Ok(())
+}
+
+/// Figure 13.6: file with the pid of a daemon, write locked as long as the daemon
+/// runs. Record locks aren't inherited by fork, so create it after `daemonize`
+#[derive(Debug)]
+pub struct PidFile {
+    // closing it releases the lock
+    _fd: Fd,
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// open or create `path`, lock it and write the pid into it. Fails with EAGAIN
+    /// (`ErrorKind::WouldBlock`) while another process holds the lock. The file isn't
+    /// removed at exit, only the lock tells if the daemon is running
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<PidFile> {
+        let fd = Fd::open(&path, O_RDWR | O_CREAT, S_IRUSR | S_IWUSR | S_IRGRP | S_IROTH)?;
+        lockfile(&fd)?.leak();
+        unsafe { ftruncate(fd.as_raw_fd(), 0) }.check_not_negative()?;
+        let pid = format!("{}\n", unsafe { getpid() });
+        if fd.write(pid.as_bytes())? != pid.len() {
+            return Err(Error::new(ErrorKind::WriteZero, "short write to the pid file"));
+        }
+        Ok(PidFile {
+            _fd: fd,
+            path: path.as_ref().to_path_buf(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// the pid of the process holding the lock on the pid file `path`, None if the file
+/// doesn't exist or isn't locked. F_GETLK doesn't need write access, so anybody who
+/// can read the file can ask
+pub fn running_pid<P: AsRef<Path>>(path: P) -> Result<Option<pid_t>> {
+    let fd = match Fd::open(path, O_RDONLY, 0) {
+        Ok(fd) => fd,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    Lock::write(0, SEEK_SET, 0).test(&fd)
+}
+
+/// Figure 13.7: block SIGHUP and SIGTERM and take them in a new thread with sigwait.
+/// SIGHUP calls `reread`, e.g. to read the configuration file again, SIGTERM logs and
+/// exits the process.
+///
+/// Call it before starting other threads, they inherit the blocked signals. SIGHUP is
+/// set back to the default action, ignored signals (as left by `daemonize`) are
+/// discarded instead of waiting for sigwait.
+pub fn handle_signals<F>(mut reread: F) -> Result<JoinHandle<()>>
+    where F: FnMut() + Send + 'static
+{
+    SigAction::new(Handler::Default).install(SIGHUP)?.keep();
+    let mut mask = SigSet::empty();
+    mask.insert(SIGHUP);
+    mask.insert(SIGTERM);
+    mask.block()?;
+    Ok(thread::spawn(move || loop {
+        let mut signo = 0;
+        let err = unsafe { libc::sigwait(mask.as_ptr(), &mut signo) };
+        if err != 0 {
+            syslog(Priority::Error, &format!("sigwait failed: {}", Error::from_raw_os_error(err)));
+            exit(1);
+        }
+        match signo {
+            SIGHUP => {
+                syslog(Priority::Info, "Re-reading configuration file");
+                reread();
+            }
+            SIGTERM => {
+                syslog(Priority::Info, "got SIGTERM; exiting");
+                exit(0);
+            }
+            _ => syslog(Priority::Info, &format!("unexpected signal {}", signo)),
+        }
+    }))
+}
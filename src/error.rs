@@ -0,0 +1,557 @@
+//! Error routines, Appendix B
+//!
+//! The `err_*` macros print to stderr, the `log_*` macros are for programs that may
+//! run as daemons: they print to stderr as well, until the process becomes a daemon
+//! (`daemon::daemonize`) or calls `log_open` with `to_stderr` false. From then on they
+//! log to syslog with `LOG_ERR`. All macros take the arguments of `format!`.
+//!
+//! | macro       | strerror(errno) | afterwards     |
+//! |-------------|-----------------|----------------|
+//! | `err_ret`   | yes             | return         |
+//! | `err_sys`   | yes             | exit(1)        |
+//! | `err_dump`  | yes             | abort()        |
+//! | `err_msg`   | no              | return         |
+//! | `err_quit`  | no              | exit(1)        |
+//! | `err_cont`  | given error     | return         |
+//! | `err_exit`  | given error     | exit(1)        |
+//! | `log_ret`   | yes             | return         |
+//! | `log_sys`   | yes             | exit(2)        |
+//! | `log_msg`   | no              | return         |
+//! | `log_quit`  | no              | exit(2)        |
+//! | `log_exit`  | given error     | exit(2)        |
+//!
+//! errno is saved before the message is formatted, as formatting can change it.
+//!
+//! `strerror` is based on strerror_r(3), the old `sys_errlist` array isn't exported by
+//! newer C libraries. `Errno` adds the symbolic names from a table of every errno libc
+//! defines for the system.
+//!
+//! `Context` adds the call that failed to an `io::Error`, so it reads "open /x: ENOENT:
+//! No such file or directory" instead of a bare errno.
+
+use libc::{c_char, c_int, ERANGE};
+use std::error;
+use std::ffi::CStr;
+use std::fmt;
+use std::io::{self, ErrorKind, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use syslog::{openlog, syslog, Facility, Priority};
+
+static LOG_TO_STDERR: AtomicBool = AtomicBool::new(true);
+
+/// the current errno
+pub fn errno() -> c_int {
+    ::errno::errno().0
+}
+
+/// the message for the errno `error`, e.g. "Permission denied" for EACCES. Other than
+/// strerror(3) it's thread-safe
+pub fn strerror(error: c_int) -> String {
+    let mut buf = vec![0 as c_char; 256];
+    loop {
+        match unsafe { libc::strerror_r(error, buf.as_mut_ptr(), buf.len()) } {
+            // older glibc return -1 and set errno
+            ERANGE => {}
+            -1 if errno() == ERANGE => {}
+            0 => break,
+            _ => return format!("Unknown error {}", error),
+        }
+        let len = buf.len();
+        buf.resize(len * 2, 0);
+    }
+    unsafe { CStr::from_ptr(buf.as_ptr()) }.to_string_lossy().into_owned()
+}
+
+/// print `msg` to stderr, followed by the text of `error`
+pub fn err_doit(error: Option<c_int>, msg: &str) {
+    // stdout first, so the error comes after what was printed before
+    let _ = ::std::io::stdout().flush();
+    let text = match error {
+        Some(error) => format!("{}: {}\n", msg, strerror(error)),
+        None => format!("{}\n", msg),
+    };
+    let _ = ::std::io::stderr().write_all(text.as_bytes());
+}
+
+/// `err_doit` or syslog with `LOG_ERR`, depending on `log_open`
+pub fn log_doit(error: Option<c_int>, msg: &str) {
+    if LOG_TO_STDERR.load(Ordering::SeqCst) {
+        err_doit(error, msg);
+    } else {
+        match error {
+            Some(error) => syslog(Priority::Error, &format!("{}: {}", msg, strerror(error))),
+            None => syslog(Priority::Error, msg),
+        }
+    }
+}
+
+/// Figure B.5 log_open: the `log_*` macros print to stderr if `to_stderr`, else the
+/// syslog is opened with `ident`, `option` and `facility` and they log there
+pub fn log_open(ident: &str, option: c_int, facility: Facility, to_stderr: bool) {
+    LOG_TO_STDERR.store(to_stderr, Ordering::SeqCst);
+    if !to_stderr {
+        openlog(ident, option, facility);
+    }
+}
+
+/// let the `log_*` macros log to syslog (false) or print to stderr (true), the syslog
+/// has to be opened already
+pub fn set_log_to_stderr(to_stderr: bool) {
+    LOG_TO_STDERR.store(to_stderr, Ordering::SeqCst);
+}
+
+/// an errno value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Errno(pub c_int);
+
+// every errno of the system, aliases (EWOULDBLOCK, ...) after the name they stand for
+static ERRNOS: &[(&str, c_int)] = &[
+    ("EPERM", libc::EPERM),
+    ("ENOENT", libc::ENOENT),
+    ("ESRCH", libc::ESRCH),
+    ("EINTR", libc::EINTR),
+    ("EIO", libc::EIO),
+    ("ENXIO", libc::ENXIO),
+    ("E2BIG", libc::E2BIG),
+    ("ENOEXEC", libc::ENOEXEC),
+    ("EBADF", libc::EBADF),
+    ("ECHILD", libc::ECHILD),
+    ("EAGAIN", libc::EAGAIN),
+    ("EWOULDBLOCK", libc::EWOULDBLOCK),
+    ("ENOMEM", libc::ENOMEM),
+    ("EACCES", libc::EACCES),
+    ("EFAULT", libc::EFAULT),
+    ("ENOTBLK", libc::ENOTBLK),
+    ("EBUSY", libc::EBUSY),
+    ("EEXIST", libc::EEXIST),
+    ("EXDEV", libc::EXDEV),
+    ("ENODEV", libc::ENODEV),
+    ("ENOTDIR", libc::ENOTDIR),
+    ("EISDIR", libc::EISDIR),
+    ("EINVAL", libc::EINVAL),
+    ("ENFILE", libc::ENFILE),
+    ("EMFILE", libc::EMFILE),
+    ("ENOTTY", libc::ENOTTY),
+    ("ETXTBSY", libc::ETXTBSY),
+    ("EFBIG", libc::EFBIG),
+    ("ENOSPC", libc::ENOSPC),
+    ("ESPIPE", libc::ESPIPE),
+    ("EROFS", libc::EROFS),
+    ("EMLINK", libc::EMLINK),
+    ("EPIPE", libc::EPIPE),
+    ("EDOM", libc::EDOM),
+    ("ERANGE", libc::ERANGE),
+    ("EDEADLK", libc::EDEADLK),
+    #[cfg(target_os = "linux")]
+    ("EDEADLOCK", libc::EDEADLOCK),
+    ("ENAMETOOLONG", libc::ENAMETOOLONG),
+    ("ENOLCK", libc::ENOLCK),
+    ("ENOSYS", libc::ENOSYS),
+    ("ENOTEMPTY", libc::ENOTEMPTY),
+    ("ELOOP", libc::ELOOP),
+    ("ENOMSG", libc::ENOMSG),
+    ("EIDRM", libc::EIDRM),
+    #[cfg(target_os = "linux")]
+    ("ECHRNG", libc::ECHRNG),
+    #[cfg(target_os = "linux")]
+    ("EL2NSYNC", libc::EL2NSYNC),
+    #[cfg(target_os = "linux")]
+    ("EL3HLT", libc::EL3HLT),
+    #[cfg(target_os = "linux")]
+    ("EL3RST", libc::EL3RST),
+    #[cfg(target_os = "linux")]
+    ("ELNRNG", libc::ELNRNG),
+    #[cfg(target_os = "linux")]
+    ("EUNATCH", libc::EUNATCH),
+    #[cfg(target_os = "linux")]
+    ("ENOCSI", libc::ENOCSI),
+    #[cfg(target_os = "linux")]
+    ("EL2HLT", libc::EL2HLT),
+    #[cfg(target_os = "linux")]
+    ("EBADE", libc::EBADE),
+    #[cfg(target_os = "linux")]
+    ("EBADR", libc::EBADR),
+    #[cfg(target_os = "linux")]
+    ("EXFULL", libc::EXFULL),
+    #[cfg(target_os = "linux")]
+    ("ENOANO", libc::ENOANO),
+    #[cfg(target_os = "linux")]
+    ("EBADRQC", libc::EBADRQC),
+    #[cfg(target_os = "linux")]
+    ("EBADSLT", libc::EBADSLT),
+    #[cfg(target_os = "linux")]
+    ("EBFONT", libc::EBFONT),
+    ("ENOSTR", libc::ENOSTR),
+    ("ENODATA", libc::ENODATA),
+    ("ETIME", libc::ETIME),
+    ("ENOSR", libc::ENOSR),
+    #[cfg(target_os = "linux")]
+    ("ENONET", libc::ENONET),
+    #[cfg(target_os = "linux")]
+    ("ENOPKG", libc::ENOPKG),
+    ("EREMOTE", libc::EREMOTE),
+    ("ENOLINK", libc::ENOLINK),
+    #[cfg(target_os = "linux")]
+    ("EADV", libc::EADV),
+    #[cfg(target_os = "linux")]
+    ("ESRMNT", libc::ESRMNT),
+    #[cfg(target_os = "linux")]
+    ("ECOMM", libc::ECOMM),
+    ("EPROTO", libc::EPROTO),
+    ("EMULTIHOP", libc::EMULTIHOP),
+    #[cfg(target_os = "linux")]
+    ("EDOTDOT", libc::EDOTDOT),
+    ("EBADMSG", libc::EBADMSG),
+    ("EOVERFLOW", libc::EOVERFLOW),
+    #[cfg(target_os = "linux")]
+    ("ENOTUNIQ", libc::ENOTUNIQ),
+    #[cfg(target_os = "linux")]
+    ("EBADFD", libc::EBADFD),
+    #[cfg(target_os = "linux")]
+    ("EREMCHG", libc::EREMCHG),
+    #[cfg(target_os = "linux")]
+    ("ELIBACC", libc::ELIBACC),
+    #[cfg(target_os = "linux")]
+    ("ELIBBAD", libc::ELIBBAD),
+    #[cfg(target_os = "linux")]
+    ("ELIBSCN", libc::ELIBSCN),
+    #[cfg(target_os = "linux")]
+    ("ELIBMAX", libc::ELIBMAX),
+    #[cfg(target_os = "linux")]
+    ("ELIBEXEC", libc::ELIBEXEC),
+    ("EILSEQ", libc::EILSEQ),
+    #[cfg(target_os = "linux")]
+    ("ERESTART", libc::ERESTART),
+    #[cfg(target_os = "linux")]
+    ("ESTRPIPE", libc::ESTRPIPE),
+    ("EUSERS", libc::EUSERS),
+    ("ENOTSOCK", libc::ENOTSOCK),
+    ("EDESTADDRREQ", libc::EDESTADDRREQ),
+    ("EMSGSIZE", libc::EMSGSIZE),
+    ("EPROTOTYPE", libc::EPROTOTYPE),
+    ("ENOPROTOOPT", libc::ENOPROTOOPT),
+    ("EPROTONOSUPPORT", libc::EPROTONOSUPPORT),
+    ("ESOCKTNOSUPPORT", libc::ESOCKTNOSUPPORT),
+    ("EOPNOTSUPP", libc::EOPNOTSUPP),
+    ("ENOTSUP", libc::ENOTSUP),
+    ("EPFNOSUPPORT", libc::EPFNOSUPPORT),
+    ("EAFNOSUPPORT", libc::EAFNOSUPPORT),
+    ("EADDRINUSE", libc::EADDRINUSE),
+    ("EADDRNOTAVAIL", libc::EADDRNOTAVAIL),
+    ("ENETDOWN", libc::ENETDOWN),
+    ("ENETUNREACH", libc::ENETUNREACH),
+    ("ENETRESET", libc::ENETRESET),
+    ("ECONNABORTED", libc::ECONNABORTED),
+    ("ECONNRESET", libc::ECONNRESET),
+    ("ENOBUFS", libc::ENOBUFS),
+    ("EISCONN", libc::EISCONN),
+    ("ENOTCONN", libc::ENOTCONN),
+    ("ESHUTDOWN", libc::ESHUTDOWN),
+    ("ETOOMANYREFS", libc::ETOOMANYREFS),
+    ("ETIMEDOUT", libc::ETIMEDOUT),
+    ("ECONNREFUSED", libc::ECONNREFUSED),
+    ("EHOSTDOWN", libc::EHOSTDOWN),
+    ("EHOSTUNREACH", libc::EHOSTUNREACH),
+    ("EALREADY", libc::EALREADY),
+    ("EINPROGRESS", libc::EINPROGRESS),
+    ("ESTALE", libc::ESTALE),
+    #[cfg(target_os = "linux")]
+    ("EUCLEAN", libc::EUCLEAN),
+    #[cfg(target_os = "linux")]
+    ("ENOTNAM", libc::ENOTNAM),
+    #[cfg(target_os = "linux")]
+    ("ENAVAIL", libc::ENAVAIL),
+    #[cfg(target_os = "linux")]
+    ("EISNAM", libc::EISNAM),
+    #[cfg(target_os = "linux")]
+    ("EREMOTEIO", libc::EREMOTEIO),
+    ("EDQUOT", libc::EDQUOT),
+    #[cfg(target_os = "linux")]
+    ("ENOMEDIUM", libc::ENOMEDIUM),
+    #[cfg(target_os = "linux")]
+    ("EMEDIUMTYPE", libc::EMEDIUMTYPE),
+    ("ECANCELED", libc::ECANCELED),
+    #[cfg(target_os = "linux")]
+    ("ENOKEY", libc::ENOKEY),
+    #[cfg(target_os = "linux")]
+    ("EKEYEXPIRED", libc::EKEYEXPIRED),
+    #[cfg(target_os = "linux")]
+    ("EKEYREVOKED", libc::EKEYREVOKED),
+    #[cfg(target_os = "linux")]
+    ("EKEYREJECTED", libc::EKEYREJECTED),
+    ("EOWNERDEAD", libc::EOWNERDEAD),
+    ("ENOTRECOVERABLE", libc::ENOTRECOVERABLE),
+    #[cfg(target_os = "linux")]
+    ("ERFKILL", libc::ERFKILL),
+    #[cfg(target_os = "linux")]
+    ("EHWPOISON", libc::EHWPOISON),
+    #[cfg(target_os = "macos")]
+    ("EPROCLIM", libc::EPROCLIM),
+    #[cfg(target_os = "macos")]
+    ("EBADRPC", libc::EBADRPC),
+    #[cfg(target_os = "macos")]
+    ("ERPCMISMATCH", libc::ERPCMISMATCH),
+    #[cfg(target_os = "macos")]
+    ("EPROGUNAVAIL", libc::EPROGUNAVAIL),
+    #[cfg(target_os = "macos")]
+    ("EPROGMISMATCH", libc::EPROGMISMATCH),
+    #[cfg(target_os = "macos")]
+    ("EPROCUNAVAIL", libc::EPROCUNAVAIL),
+    #[cfg(target_os = "macos")]
+    ("EFTYPE", libc::EFTYPE),
+    #[cfg(target_os = "macos")]
+    ("EAUTH", libc::EAUTH),
+    #[cfg(target_os = "macos")]
+    ("ENEEDAUTH", libc::ENEEDAUTH),
+    #[cfg(target_os = "macos")]
+    ("EPWROFF", libc::EPWROFF),
+    #[cfg(target_os = "macos")]
+    ("EDEVERR", libc::EDEVERR),
+    #[cfg(target_os = "macos")]
+    ("EBADEXEC", libc::EBADEXEC),
+    #[cfg(target_os = "macos")]
+    ("EBADARCH", libc::EBADARCH),
+    #[cfg(target_os = "macos")]
+    ("ESHLIBVERS", libc::ESHLIBVERS),
+    #[cfg(target_os = "macos")]
+    ("EBADMACHO", libc::EBADMACHO),
+    #[cfg(target_os = "macos")]
+    ("ENOATTR", libc::ENOATTR),
+    #[cfg(target_os = "macos")]
+    ("ENOPOLICY", libc::ENOPOLICY),
+    #[cfg(target_os = "macos")]
+    ("EQFULL", libc::EQFULL),
+    #[cfg(target_os = "macos")]
+    ("ENOTCAPABLE", libc::ENOTCAPABLE),
+];
+
+impl Errno {
+    /// the current errno
+    pub fn last() -> Errno {
+        Errno(errno())
+    }
+
+    pub fn number(&self) -> c_int {
+        self.0
+    }
+
+    /// the symbolic name, e.g. "EACCES". None for numbers the system doesn't define
+    pub fn name(&self) -> Option<&'static str> {
+        ERRNOS.iter().find(|&&(_, n)| n == self.0).map(|&(name, _)| name)
+    }
+
+    /// the message of strerror
+    pub fn message(&self) -> String {
+        strerror(self.0)
+    }
+
+    /// all errnos of the system by number, without aliases
+    pub fn all() -> Vec<Errno> {
+        let mut all: Vec<Errno> = ERRNOS.iter().map(|&(_, n)| Errno(n)).collect();
+        all.sort();
+        all.dedup();
+        all
+    }
+}
+
+impl fmt::Display for Errno {
+    /// "EACCES: Permission denied"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{}: {}", name, self.message()),
+            None => write!(f, "{}: {}", self.0, self.message()),
+        }
+    }
+}
+
+impl FromStr for Errno {
+    type Err = io::Error;
+
+    /// the symbolic name, aliases included, or the number
+    fn from_str(s: &str) -> io::Result<Errno> {
+        if let Some(&(_, n)) = ERRNOS.iter().find(|&&(name, _)| name == s) {
+            return Ok(Errno(n));
+        }
+        s.parse()
+            .map(Errno)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, format!("unknown errno {}", s)))
+    }
+}
+
+impl From<Errno> for io::Error {
+    fn from(e: Errno) -> io::Error {
+        io::Error::from_raw_os_error(e.0)
+    }
+}
+
+/// an `io::Error` together with the call that failed, see `Context`
+#[derive(Debug)]
+pub struct ContextError {
+    pub what: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ContextError {
+    /// "open /etc/shadow: EACCES: Permission denied"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.error.raw_os_error() {
+            Some(errno) => write!(f, "{}: {}", self.what, Errno(errno)),
+            None => write!(f, "{}: {}", self.what, self.error),
+        }
+    }
+}
+
+impl error::Error for ContextError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Attaches the failed call and its arguments to the error of an `io::Result`, e.g.
+/// `Fd::open(path, O_RDONLY, 0).context(format_args!("open {}", path))?`. The error
+/// keeps its `kind()`, `os_error` still finds the errno.
+pub trait Context<T> {
+    fn context<D: fmt::Display>(self, what: D) -> io::Result<T>;
+    /// `context` with the description only built on error
+    fn with_context<D: fmt::Display, F: FnOnce() -> D>(self, what: F) -> io::Result<T>;
+}
+
+impl<T> Context<T> for io::Result<T> {
+    fn context<D: fmt::Display>(self, what: D) -> io::Result<T> {
+        self.with_context(|| what)
+    }
+
+    fn with_context<D: fmt::Display, F: FnOnce() -> D>(self, what: F) -> io::Result<T> {
+        self.map_err(|error| {
+            let kind = error.kind();
+            io::Error::new(kind,
+                           ContextError {
+                               what: what().to_string(),
+                               error: error,
+                           })
+        })
+    }
+}
+
+/// the errno of `error`, also if it's wrapped by `Context`
+pub fn os_error(error: &io::Error) -> Option<c_int> {
+    error.raw_os_error().or_else(|| {
+        error.get_ref()
+            .and_then(|e| e.downcast_ref::<ContextError>())
+            .and_then(|e| os_error(&e.error))
+    })
+}
+
+/// nonfatal error related to a system call: print the message with the text of errno
+/// and return
+#[macro_export]
+macro_rules! err_ret {
+    ($($arg:tt)*) => {{
+        let errno = $crate::error::errno();
+        $crate::error::err_doit(Some(errno), &format!($($arg)*));
+    }}
+}
+
+/// fatal error related to a system call: print the message with the text of errno and
+/// exit(1)
+#[macro_export]
+macro_rules! err_sys {
+    ($($arg:tt)*) => {{
+        let errno = $crate::error::errno();
+        $crate::error::err_doit(Some(errno), &format!($($arg)*));
+        ::std::process::exit(1)
+    }}
+}
+
+/// fatal error related to a system call: print the message with the text of errno and
+/// abort(), which leaves a core file
+#[macro_export]
+macro_rules! err_dump {
+    ($($arg:tt)*) => {{
+        let errno = $crate::error::errno();
+        $crate::error::err_doit(Some(errno), &format!($($arg)*));
+        ::std::process::abort()
+    }}
+}
+
+/// nonfatal error unrelated to a system call: print the message and return
+#[macro_export]
+macro_rules! err_msg {
+    ($($arg:tt)*) => {{
+        $crate::error::err_doit(None, &format!($($arg)*));
+    }}
+}
+
+/// fatal error unrelated to a system call: print the message and exit(1)
+#[macro_export]
+macro_rules! err_quit {
+    ($($arg:tt)*) => {{
+        $crate::error::err_doit(None, &format!($($arg)*));
+        ::std::process::exit(1)
+    }}
+}
+
+/// nonfatal error with an error code (e.g. from pthreads) instead of errno: print the
+/// message with the text of `error` and return
+#[macro_export]
+macro_rules! err_cont {
+    ($error:expr, $($arg:tt)*) => {{
+        $crate::error::err_doit(Some($error), &format!($($arg)*));
+    }}
+}
+
+/// fatal error with an error code instead of errno: print the message with the text of
+/// `error` and exit(1)
+#[macro_export]
+macro_rules! err_exit {
+    ($error:expr, $($arg:tt)*) => {{
+        $crate::error::err_doit(Some($error), &format!($($arg)*));
+        ::std::process::exit(1)
+    }}
+}
+
+/// `err_ret` that logs to syslog in a daemon
+#[macro_export]
+macro_rules! log_ret {
+    ($($arg:tt)*) => {{
+        let errno = $crate::error::errno();
+        $crate::error::log_doit(Some(errno), &format!($($arg)*));
+    }}
+}
+
+/// `err_sys` that logs to syslog in a daemon, exits with 2
+#[macro_export]
+macro_rules! log_sys {
+    ($($arg:tt)*) => {{
+        let errno = $crate::error::errno();
+        $crate::error::log_doit(Some(errno), &format!($($arg)*));
+        ::std::process::exit(2)
+    }}
+}
+
+/// `err_msg` that logs to syslog in a daemon
+#[macro_export]
+macro_rules! log_msg {
+    ($($arg:tt)*) => {{
+        $crate::error::log_doit(None, &format!($($arg)*));
+    }}
+}
+
+/// `err_quit` that logs to syslog in a daemon, exits with 2
+#[macro_export]
+macro_rules! log_quit {
+    ($($arg:tt)*) => {{
+        $crate::error::log_doit(None, &format!($($arg)*));
+        ::std::process::exit(2)
+    }}
+}
+
+/// `err_exit` that logs to syslog in a daemon, exits with 2
+#[macro_export]
+macro_rules! log_exit {
+    ($error:expr, $($arg:tt)*) => {{
+        $crate::error::log_doit(Some($error), &format!($($arg)*));
+        ::std::process::exit(2)
+    }}
+}
@@ -0,0 +1,238 @@
+//! The exec functions, section 8.10
+//!
+//! Typed versions of the exec family: the arguments and the environment are slices of
+//! `OsStr` (the environment as "name=value" strings), the strings are converted with
+//! `cstr::CStrArgs`. Like exec itself the functions only return on error, so they
+//! return the `io::Error` instead of a `Result`.
+//!
+//! `execvp` and `execvpe` do the PATH search themselves with the rules of execvp(3):
+//!
+//! - a file name containing a slash is executed as it is, an empty one fails with ENOENT
+//! - otherwise every directory of PATH (/bin:/usr/bin if unset, an empty entry is the
+//!   working directory) is tried in turn. ENOENT, ENOTDIR and friends go on with the
+//!   next directory, EACCES as well but it's reported at the end if nothing else was
+//!   found. Any other error stops the search
+//! - a file the kernel refuses with ENOEXEC (no `#!` line, no known binary format) is
+//!   run with /bin/sh, as a shell script
+//!
+//! `interpreter` parses the `#!` line of an interpreter file (Figure 8.20) the way Linux
+//! does. The functions allocate, so in a multithreaded program don't call them between
+//! fork and exec, use `process::Command` instead.
+
+use cstr::{to_cstring, CStrArgs};
+use libc::{EACCES, ENODEV, ENOENT, ENOEXEC, ENOTDIR, ESTALE, ETIMEDOUT};
+use my_libc::environ;
+use std::env;
+use std::ffi::{CStr, OsStr, OsString};
+use std::fs::File;
+use std::io::{Error, ErrorKind, Read, Result};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+/// PATH if the environment has none, as _CS_PATH of glibc
+pub const DEFAULT_PATH: &str = "/bin:/usr/bin";
+
+/// the bytes of the first line an interpreter file may have, BINPRM_BUF_SIZE of Linux
+pub const INTERPRETER_LINE_MAX: usize = 256;
+
+macro_rules! try_exec {
+    ($e:expr) => {
+        match $e {
+            Ok(val) => val,
+            Err(e) => return e,
+        }
+    }
+}
+
+unsafe fn raw_execve(path: &CStr, argv: &CStrArgs, envp: Option<&CStrArgs>) -> Error {
+    let envp = match envp {
+        Some(envp) => envp.as_ptr(),
+        None => environ,
+    };
+    libc::execve(path.as_ptr(), argv.as_ptr(), envp);
+    Error::last_os_error()
+}
+
+/// execv(3): execute `path` with `argv`, argv[0] is usually the name of the program.
+/// The process keeps its environment
+pub fn execv<P, S>(path: P, argv: &[S]) -> Error
+    where P: AsRef<Path>,
+          S: AsRef<OsStr>
+{
+    let path = try_exec!(to_cstring(path.as_ref()));
+    let argv = try_exec!(CStrArgs::argv(argv));
+    unsafe { raw_execve(&path, &argv, None) }
+}
+
+/// execve(2): like `execv`, with the environment `envp`
+pub fn execve<P, S, E>(path: P, argv: &[S], envp: &[E]) -> Error
+    where P: AsRef<Path>,
+          S: AsRef<OsStr>,
+          E: AsRef<OsStr>
+{
+    let path = try_exec!(to_cstring(path.as_ref()));
+    let argv = try_exec!(CStrArgs::argv(argv));
+    let envp = try_exec!(CStrArgs::argv(envp));
+    unsafe { raw_execve(&path, &argv, Some(&envp)) }
+}
+
+/// execvp(3): like `execv`, but `file` is searched in PATH if it doesn't contain a
+/// slash, see the module documentation
+pub fn execvp<F, S>(file: F, argv: &[S]) -> Error
+    where F: AsRef<OsStr>,
+          S: AsRef<OsStr>
+{
+    let argv = try_exec!(CStrArgs::argv(argv));
+    search_path(file.as_ref(), &argv, None)
+}
+
+/// execvpe(3): `execvp` with the environment `envp`. PATH is still taken from the
+/// environment of the calling process, as glibc does
+pub fn execvpe<F, S, E>(file: F, argv: &[S], envp: &[E]) -> Error
+    where F: AsRef<OsStr>,
+          S: AsRef<OsStr>,
+          E: AsRef<OsStr>
+{
+    let argv = try_exec!(CStrArgs::argv(argv));
+    let envp = try_exec!(CStrArgs::argv(envp));
+    search_path(file.as_ref(), &argv, Some(&envp))
+}
+
+/// fexecve(3): execute the file open as `fd`, e.g. to run exactly the file that was
+/// checked before. An interpreter file has to be open without close-on-exec, the
+/// interpreter opens it by its /dev/fd path (ENOENT otherwise)
+#[cfg(target_os = "linux")]
+pub fn fexecve<F, S, E>(fd: &F, argv: &[S], envp: &[E]) -> Error
+    where F: AsRawFd,
+          S: AsRef<OsStr>,
+          E: AsRef<OsStr>
+{
+    let argv = try_exec!(CStrArgs::argv(argv));
+    let envp = try_exec!(CStrArgs::argv(envp));
+    unsafe { libc::fexecve(fd.as_raw_fd(), argv.as_ptr(), envp.as_ptr()) };
+    Error::last_os_error()
+}
+
+/// fexecve(3) isn't available, the file is executed by its /dev/fd path
+#[cfg(not(target_os = "linux"))]
+pub fn fexecve<F, S, E>(fd: &F, argv: &[S], envp: &[E]) -> Error
+    where F: AsRawFd,
+          S: AsRef<OsStr>,
+          E: AsRef<OsStr>
+{
+    execve(format!("/dev/fd/{}", fd.as_raw_fd()), argv, envp)
+}
+
+// errors after which execvp tries the next directory of PATH
+fn try_next(errno: i32) -> bool {
+    matches!(errno, EACCES | ENOENT | ENOTDIR | ESTALE | ENODEV | ETIMEDOUT)
+}
+
+/// execve `path`, running it with /bin/sh if the kernel doesn't know its format
+fn execve_or_sh(path: &CStr, argv: &CStrArgs, envp: Option<&CStrArgs>) -> Error {
+    let err = unsafe { raw_execve(path, argv, envp) };
+    if err.raw_os_error() != Some(ENOEXEC) {
+        return err;
+    }
+    // like glibc: argv[0] is replaced by the path of the script
+    let mut sh_argv = CStrArgs::new();
+    try_exec!(sh_argv.push("/bin/sh"));
+    try_exec!(sh_argv.push(OsStr::from_bytes(path.to_bytes())));
+    for arg in argv.iter().skip(1) {
+        try_exec!(sh_argv.push(OsStr::from_bytes(arg.as_bytes())));
+    }
+    let sh = try_exec!(to_cstring("/bin/sh"));
+    unsafe { raw_execve(&sh, &sh_argv, envp) }
+}
+
+fn search_path(file: &OsStr, argv: &CStrArgs, envp: Option<&CStrArgs>) -> Error {
+    if file.is_empty() {
+        return Error::from_raw_os_error(ENOENT);
+    }
+    if file.as_bytes().contains(&b'/') {
+        let path = try_exec!(to_cstring(file));
+        return execve_or_sh(&path, argv, envp);
+    }
+    let path = env::var_os("PATH").unwrap_or_else(|| OsString::from(DEFAULT_PATH));
+    let mut eacces = false;
+    let mut last = Error::from_raw_os_error(ENOENT);
+    for dir in path.as_bytes().split(|&b| b == b':') {
+        let dir = if dir.is_empty() { Path::new(".") } else { Path::new(OsStr::from_bytes(dir)) };
+        let candidate = try_exec!(to_cstring(dir.join(file)));
+        let err = execve_or_sh(&candidate, argv, envp);
+        match err.raw_os_error() {
+            Some(errno) if try_next(errno) => {
+                eacces |= errno == EACCES;
+                last = err;
+            }
+            _ => return err,
+        }
+    }
+    if eacces {
+        Error::from_raw_os_error(EACCES)
+    } else {
+        last
+    }
+}
+
+/// the `#!` line of an interpreter file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interpreter {
+    /// pathname of the interpreter
+    pub path: PathBuf,
+    /// everything after the pathname as one argument, Linux doesn't split it
+    pub arg: Option<OsString>,
+}
+
+impl Interpreter {
+    /// the argument vector the interpreter gets if `script` is executed with `argv`:
+    /// the interpreter, its optional argument, the path of `script` and argv[1..]
+    pub fn argv<P: AsRef<Path>, S: AsRef<OsStr>>(&self, script: P, argv: &[S]) -> Vec<OsString> {
+        let mut result = vec![self.path.clone().into_os_string()];
+        result.extend(self.arg.clone());
+        result.push(script.as_ref().as_os_str().to_owned());
+        result.extend(argv.iter().skip(1).map(|arg| arg.as_ref().to_owned()));
+        result
+    }
+}
+
+/// parse the first line of `path`: None if it doesn't start with `#!`. Blanks around
+/// the pathname and at the end are dropped. A line longer than `INTERPRETER_LINE_MAX`
+/// fails with `ErrorKind::InvalidData` (ENOEXEC for the kernel), as does a line
+/// without pathname
+pub fn interpreter<P: AsRef<Path>>(path: P) -> Result<Option<Interpreter>> {
+    let mut buf = Vec::with_capacity(INTERPRETER_LINE_MAX);
+    File::open(path)?.take(INTERPRETER_LINE_MAX as u64).read_to_end(&mut buf)?;
+    parse_interpreter(&buf)
+}
+
+fn parse_interpreter(buf: &[u8]) -> Result<Option<Interpreter>> {
+    if !buf.starts_with(b"#!") {
+        return Ok(None);
+    }
+    let line = match buf.iter().position(|&b| b == b'\n') {
+        Some(end) => &buf[2..end],
+        None if buf.len() < INTERPRETER_LINE_MAX => &buf[2..],
+        None => return Err(Error::new(ErrorKind::InvalidData, "#! line too long")),
+    };
+    let is_blank = |b: &u8| *b == b' ' || *b == b'\t';
+    let start = line.iter().position(|b| !is_blank(b)).unwrap_or(line.len());
+    let end = line.iter().rposition(|b| !is_blank(b)).map_or(start, |i| i + 1);
+    let line = &line[start..end];
+    if line.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "#! line without interpreter"));
+    }
+    let (path, arg) = match line.iter().position(is_blank) {
+        Some(i) => {
+            let rest = &line[i..];
+            let arg_start = rest.iter().position(|b| !is_blank(b)).unwrap_or(rest.len());
+            (&line[..i], Some(OsString::from_vec(rest[arg_start..].to_vec())))
+        }
+        None => (line, None),
+    };
+    Ok(Some(Interpreter {
+        path: PathBuf::from(OsStr::from_bytes(path)),
+        arg: arg,
+    }))
+}
@@ -0,0 +1,201 @@
+//! Owned file descriptor
+//!
+//! Chapter 3 passes raw `c_int` descriptors around and forgets to close them as soon
+//! as something goes wrong. `Fd` owns the descriptor and closes it when it goes out of
+//! scope, every method maps the -1 of the underlying call into an `io::Error`.
+
+use libc::{c_int, c_void, mode_t, off_t, F_GETFL, F_SETFL, O_NONBLOCK, SEEK_CUR, SEEK_END,
+           SEEK_SET};
+use cstr::to_cstring;
+use std::io::{self, Result, SeekFrom};
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::path::Path;
+use LibcResult;
+
+#[derive(Debug)]
+pub struct Fd(c_int);
+
+/// pipe(2) with both ends close-on-exec, returns (read end, write end). Children
+/// only get the ends that are explicitly dup2'ed onto their stdin/stdout
+pub fn pipe() -> Result<(Fd, Fd)> {
+    let mut fds = [0; 2];
+    unsafe {
+        pipe_cloexec(&mut fds).check_not_negative()?;
+    }
+    Ok((Fd(fds[0]), Fd(fds[1])))
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn pipe_cloexec(fds: &mut [c_int; 2]) -> c_int {
+    libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC)
+}
+
+// no pipe2 on macOS, so there's a window in which another thread could fork
+#[cfg(target_os = "macos")]
+unsafe fn pipe_cloexec(fds: &mut [c_int; 2]) -> c_int {
+    if libc::pipe(fds.as_mut_ptr()) < 0 {
+        return -1;
+    }
+    for fd in fds.iter() {
+        libc::fcntl(*fd, libc::F_SETFD, libc::FD_CLOEXEC);
+    }
+    0
+}
+
+/// the file status flags, fcntl(F_GETFL)
+pub fn get_fl<F: AsRawFd>(fd: &F) -> Result<c_int> {
+    unsafe { libc::fcntl(fd.as_raw_fd(), F_GETFL, 0) }.check_not_negative()
+}
+
+/// Figure 3.12: turn on one or more of the file status `flags`
+pub fn set_fl<F: AsRawFd>(fd: &F, flags: c_int) -> Result<()> {
+    let val = get_fl(fd)?;
+    unsafe { libc::fcntl(fd.as_raw_fd(), F_SETFL, val | flags) }.check_not_negative()?;
+    Ok(())
+}
+
+/// turn off one or more of the file status `flags`
+pub fn clr_fl<F: AsRawFd>(fd: &F, flags: c_int) -> Result<()> {
+    let val = get_fl(fd)?;
+    unsafe { libc::fcntl(fd.as_raw_fd(), F_SETFL, val & !flags) }.check_not_negative()?;
+    Ok(())
+}
+
+/// turn O_NONBLOCK on or off. The flag belongs to the open file description, so it
+/// also changes the descriptor for every process sharing it (e.g. a terminal on stdin)
+pub fn set_nonblocking<F: AsRawFd>(fd: &F, nonblocking: bool) -> Result<()> {
+    if nonblocking {
+        set_fl(fd, O_NONBLOCK)
+    } else {
+        clr_fl(fd, O_NONBLOCK)
+    }
+}
+
+impl Fd {
+    /// open(2), `mode` is only used when `O_CREAT` is part of `flags`
+    pub fn open<P: AsRef<Path>>(path: P, flags: c_int, mode: mode_t) -> Result<Fd> {
+        let path = to_cstring(path.as_ref())?;
+        let fd = unsafe { libc::open(path.as_ptr(), flags, mode as c_int) }.check_not_negative()?;
+        Ok(Fd(fd))
+    }
+
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize> {
+        let n = unsafe { libc::read(self.0, buf.as_mut_ptr() as *mut c_void, buf.len()) }
+            .check_not_negative()?;
+        Ok(n as usize)
+    }
+
+    pub fn write(&self, buf: &[u8]) -> Result<usize> {
+        let n = unsafe { libc::write(self.0, buf.as_ptr() as *const c_void, buf.len()) }
+            .check_not_negative()?;
+        Ok(n as usize)
+    }
+
+    /// read at `offset` without moving the file offset
+    pub fn pread(&self, buf: &mut [u8], offset: off_t) -> Result<usize> {
+        let n = unsafe {
+            libc::pread(self.0, buf.as_mut_ptr() as *mut c_void, buf.len(), offset)
+        }.check_not_negative()?;
+        Ok(n as usize)
+    }
+
+    /// write at `offset` without moving the file offset
+    pub fn pwrite(&self, buf: &[u8], offset: off_t) -> Result<usize> {
+        let n = unsafe {
+            libc::pwrite(self.0, buf.as_ptr() as *const c_void, buf.len(), offset)
+        }.check_not_negative()?;
+        Ok(n as usize)
+    }
+
+    pub fn lseek(&self, offset: off_t, whence: c_int) -> Result<off_t> {
+        let pos = unsafe { libc::lseek(self.0, offset, whence) }.check_not_negative()?;
+        Ok(pos as off_t)
+    }
+
+    /// size of the file according to fstat(2)
+    pub fn file_size(&self) -> Result<off_t> {
+        let mut buf: libc::stat = unsafe { ::std::mem::zeroed() };
+        unsafe { libc::fstat(self.0, &mut buf) }.check_not_negative()?;
+        Ok(buf.st_size)
+    }
+
+    /// duplicate into the lowest free descriptor
+    pub fn dup(&self) -> Result<Fd> {
+        let fd = unsafe { libc::dup(self.0) }.check_not_negative()?;
+        Ok(Fd(fd))
+    }
+
+    /// duplicate into `fd2`, an already open `fd2` is closed first. The returned
+    /// `Fd` owns `fd2`, so use `into_raw_fd` when e.g. redirecting stdout. The caller
+    /// must not own `fd2` through another `Fd`, it would be closed twice. `fd2` equal
+    /// to this descriptor fails with EINVAL, dup2 wouldn't duplicate anything
+    pub fn dup2(&self, fd2: RawFd) -> Result<Fd> {
+        if fd2 == self.0 {
+            return Err(io::Error::from_raw_os_error(libc::EINVAL));
+        }
+        let fd = unsafe { libc::dup2(self.0, fd2) }.check_not_negative()?;
+        Ok(Fd(fd))
+    }
+
+    /// fcntl(2) for the commands taking an int argument (F_GETFL, F_SETFD, F_DUPFD, ..)
+    pub fn fcntl(&self, cmd: c_int, arg: c_int) -> Result<c_int> {
+        unsafe { libc::fcntl(self.0, cmd, arg) }.check_not_negative()
+    }
+}
+
+impl Drop for Fd {
+    fn drop(&mut self) {
+        // nothing sensible to do with an error from close in a destructor
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+impl io::Read for Fd {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        Fd::read(self, buf)
+    }
+}
+
+impl io::Write for Fd {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        Fd::write(self, buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // there's no userspace buffer to flush
+        Ok(())
+    }
+}
+
+impl io::Seek for Fd {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(n) => (n as off_t, SEEK_SET),
+            SeekFrom::Current(n) => (n as off_t, SEEK_CUR),
+            SeekFrom::End(n) => (n as off_t, SEEK_END),
+        };
+        self.lseek(offset, whence).map(|pos| pos as u64)
+    }
+}
+
+impl AsRawFd for Fd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl IntoRawFd for Fd {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.0;
+        ::std::mem::forget(self);
+        fd
+    }
+}
+
+impl FromRawFd for Fd {
+    unsafe fn from_raw_fd(fd: RawFd) -> Fd {
+        Fd(fd)
+    }
+}
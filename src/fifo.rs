@@ -0,0 +1,59 @@
+//! FIFOs, section 15.5
+//!
+//! Opening a FIFO normally blocks until the other end is opened as well. The helpers
+//! here open with O_NONBLOCK instead: opening for reading always succeeds right away,
+//! opening for writing fails with ENXIO while nobody has the FIFO open for reading,
+//! `open_write` reports that as `Ok(None)` so the caller can try again later.
+//!
+//! The returned descriptors stay non-blocking: a read or write that would have to wait
+//! fails with `ErrorKind::WouldBlock` (EAGAIN). Writes of at most `PIPE_BUF` bytes are
+//! atomic, they are either written completely or fail with EAGAIN.
+
+use cstr::to_cstring;
+use fd::Fd;
+use libc::{mode_t, ENXIO, O_NONBLOCK, O_RDONLY, O_WRONLY};
+use std::fs;
+use std::io::{ErrorKind, Result};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use LibcResult;
+
+/// writes up to this size are never interleaved with data of other writers
+pub const PIPE_BUF: usize = libc::PIPE_BUF;
+
+/// mkfifo(3), the permissions in `mode` are modified by the umask
+pub fn mkfifo<P: AsRef<Path>>(path: P, mode: mode_t) -> Result<()> {
+    let path = to_cstring(path.as_ref())?;
+    unsafe { libc::mkfifo(path.as_ptr(), mode) }.check_not_negative()?;
+    Ok(())
+}
+
+/// like `mkfifo`, but an existing FIFO is fine. Anything else at `path` fails with EEXIST
+pub fn create<P: AsRef<Path>>(path: P, mode: mode_t) -> Result<()> {
+    match mkfifo(&path, mode) {
+        Err(ref e) if e.kind() == ErrorKind::AlreadyExists && is_fifo(&path)? => Ok(()),
+        res => res,
+    }
+}
+
+/// true if `path` is a FIFO, symbolic links are followed
+pub fn is_fifo<P: AsRef<Path>>(path: P) -> Result<bool> {
+    Ok(fs::metadata(path)?.file_type().is_fifo())
+}
+
+/// open for reading without waiting for a writer. read returns 0 as long as no writer
+/// has the FIFO open and fails with `WouldBlock` while a writer has nothing written yet
+pub fn open_read<P: AsRef<Path>>(path: P) -> Result<Fd> {
+    Fd::open(path, O_RDONLY | O_NONBLOCK, 0)
+}
+
+/// open for writing without waiting for a reader, `None` if there is no reader (ENXIO).
+/// write fails with `WouldBlock` while the FIFO is full and with EPIPE once all
+/// readers are gone (SIGPIPE is generated as well)
+pub fn open_write<P: AsRef<Path>>(path: P) -> Result<Option<Fd>> {
+    match Fd::open(path, O_WRONLY | O_NONBLOCK, 0) {
+        Ok(fd) => Ok(Some(fd)),
+        Err(ref e) if e.raw_os_error() == Some(ENXIO) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,133 @@
+//! Scatter/gather I/O (section 14.6) and readn/writen (section 14.7)
+//!
+//! `readv` and `writev` take the buffers as `IoSliceMut`/`IoSlice` of std. Both are
+//! guaranteed to have the layout of `struct iovec` on Unix, so the slices are passed to
+//! the system calls as they are. At most `iov_max()` buffers can be passed at once.
+//!
+//! Pipes, FIFOs, terminals and sockets may transfer less than asked for, `readn` and
+//! `writen` loop until everything is moved. When they fail in between `Partial` tells
+//! how many bytes were moved before the error.
+
+use libc::{c_int, c_void, iovec};
+use limits::{sysconf, Limit, Sysconf};
+use std::{cmp, error, fmt};
+use std::io::{Error, ErrorKind, IoSlice, IoSliceMut, Result};
+use std::os::unix::io::AsRawFd;
+use LibcResult;
+
+/// the maximum number of buffers for one call, sysconf(_SC_IOV_MAX)
+pub fn iov_max() -> usize {
+    match sysconf(Sysconf::IovMax) {
+        Ok(Limit::Value(n)) if n > 0 => n as usize,
+        _ => 16, // _XOPEN_IOV_MAX, the minimum POSIX allows
+    }
+}
+
+/// readv(2): fill `bufs` one after the other, returns the number of bytes read (0 at
+/// end of file)
+pub fn readv<F: AsRawFd>(fd: &F, bufs: &mut [IoSliceMut]) -> Result<usize> {
+    let n = unsafe {
+        libc::readv(fd.as_raw_fd(), bufs.as_ptr() as *const iovec, bufs.len() as c_int)
+    }.check_not_negative()?;
+    Ok(n as usize)
+}
+
+/// writev(2): write `bufs` one after the other with a single system call, returns the
+/// number of bytes written
+pub fn writev<F: AsRawFd>(fd: &F, bufs: &[IoSlice]) -> Result<usize> {
+    let n = unsafe {
+        libc::writev(fd.as_raw_fd(), bufs.as_ptr() as *const iovec, bufs.len() as c_int)
+    }.check_not_negative()?;
+    Ok(n as usize)
+}
+
+/// write all of `bufs`, continuing after partial writes (to pipes and sockets) and EINTR.
+/// More than `iov_max()` buffers take more than one writev. `bufs` is modified on the
+/// way, afterwards it's unspecified
+pub fn writev_all<F: AsRawFd>(fd: &F, mut bufs: &mut [IoSlice]) -> Result<()> {
+    let max = iov_max();
+    // skip empty buffers at the start
+    IoSlice::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        let count = cmp::min(bufs.len(), max);
+        match writev(fd, &bufs[..count]) {
+            Ok(0) => return Err(Error::new(ErrorKind::WriteZero, "failed to write whole buffer")),
+            Ok(n) => IoSlice::advance_slices(&mut bufs, n),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// what `readn` and `writen` do when a signal interrupts them (EINTR)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OnIntr {
+    /// call read/write again
+    Retry,
+    /// stop and return the error, e.g. to react to a signal caught with `signal_intr`
+    Stop,
+}
+
+/// error of `readn`/`writen`: `done` bytes were transferred before `error` happened
+#[derive(Debug)]
+pub struct Partial {
+    pub done: usize,
+    pub error: Error,
+}
+
+impl fmt::Display for Partial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (after {} bytes)", self.error, self.done)
+    }
+}
+
+impl error::Error for Partial {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl From<Partial> for Error {
+    fn from(p: Partial) -> Error {
+        p.error
+    }
+}
+
+/// Figure 14.24: read `buf.len()` bytes, less only at end of file. Returns the number
+/// of bytes read
+pub fn readn<F: AsRawFd>(fd: &F, buf: &mut [u8], on_intr: OnIntr)
+                         -> ::std::result::Result<usize, Partial> {
+    let mut done = 0;
+    while done < buf.len() {
+        let rest = &mut buf[done..];
+        match unsafe { libc::read(fd.as_raw_fd(), rest.as_mut_ptr() as *mut c_void, rest.len()) }
+            .check_not_negative() {
+            Ok(0) => break, // EOF
+            Ok(n) => done += n as usize,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted && on_intr == OnIntr::Retry => {}
+            Err(e) => return Err(Partial { done: done, error: e }),
+        }
+    }
+    Ok(done)
+}
+
+/// Figure 14.24: write all of `buf`, returns `buf.len()`
+pub fn writen<F: AsRawFd>(fd: &F, buf: &[u8], on_intr: OnIntr)
+                          -> ::std::result::Result<usize, Partial> {
+    let mut done = 0;
+    while done < buf.len() {
+        let rest = &buf[done..];
+        match unsafe { libc::write(fd.as_raw_fd(), rest.as_ptr() as *const c_void, rest.len()) }
+            .check_not_negative() {
+            Ok(0) => {
+                let error = Error::new(ErrorKind::WriteZero, "failed to write whole buffer");
+                return Err(Partial { done: done, error: error });
+            }
+            Ok(n) => done += n as usize,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted && on_intr == OnIntr::Retry => {}
+            Err(e) => return Err(Partial { done: done, error: e }),
+        }
+    }
+    Ok(done)
+}
@@ -0,0 +1,10 @@
+//! Shared memory and semaphores, sections 15.8 - 15.10
+//!
+//! - `shm`: shared mappings (`/dev/zero`, anonymous, POSIX `shm_open`) and XSI shared
+//!   memory segments
+//! - `sem`: XSI semaphore sets and POSIX named and unnamed semaphores
+//!
+//! The XSI message queues are in `apue::msgqueue`.
+
+pub mod sem;
+pub mod shm;
@@ -0,0 +1,292 @@
+//! Semaphores, sections 15.8 and 15.10
+//!
+//! - `SysvSem`: XSI semaphore set (semget, semop, semctl)
+//! - `NamedSem`: POSIX named semaphore (sem_open), closed when dropped
+//! - `UnnamedSem`: POSIX unnamed semaphore (sem_init) living in memory borrowed from
+//!   e.g. an `apue::ipc::shm::Region`. macOS doesn't implement unnamed semaphores, so
+//!   it's Linux only, as `value` of the POSIX semaphores
+//!
+//! All waiting operations restart after EINTR.
+
+use cstr::to_cstring;
+use libc::{c_int, c_short, c_uint, c_ushort, c_void, key_t, mode_t, sem_t, sembuf};
+use libc::{EAGAIN, GETVAL, IPC_CREAT, IPC_EXCL, IPC_NOWAIT, IPC_PRIVATE, IPC_RMID, O_CREAT, O_EXCL,
+           SEM_FAILED, SETVAL};
+use libc::{sem_close, sem_open, sem_post, sem_trywait, sem_unlink, sem_wait, semctl, semget, semop};
+use std::io::{Error, ErrorKind, Result};
+use LibcResult;
+
+// the fourth argument of semctl, the caller has to define it
+#[repr(C)]
+#[allow(dead_code)]
+union semun {
+    val: c_int,
+    buf: *mut c_void,
+    array: *mut c_ushort,
+}
+
+/// sembuf for `SysvSem::op`: add `op` to semaphore `num`, `flags` is a combination of
+/// IPC_NOWAIT and SEM_UNDO
+pub fn sembuf(num: c_ushort, op: c_short, flags: c_short) -> sembuf {
+    sembuf {
+        sem_num: num,
+        sem_op: op,
+        sem_flg: flags,
+    }
+}
+
+/// XSI semaphore set
+#[derive(Debug)]
+pub struct SysvSem {
+    id: c_int,
+}
+
+impl SysvSem {
+    /// create a set of `nsems` semaphores for `key`, fails with EEXIST if there's already
+    /// one. The values are undefined until `set_value` is called
+    pub fn create(key: key_t, nsems: c_int, mode: mode_t) -> Result<SysvSem> {
+        SysvSem::get(key, nsems, IPC_CREAT | IPC_EXCL | mode as c_int)
+    }
+
+    /// the existing set for `key`
+    pub fn open(key: key_t) -> Result<SysvSem> {
+        SysvSem::get(key, 0, 0)
+    }
+
+    /// create a set with IPC_PRIVATE, only reachable through `id` (or a fork)
+    pub fn private(nsems: c_int, mode: mode_t) -> Result<SysvSem> {
+        SysvSem::get(IPC_PRIVATE, nsems, mode as c_int)
+    }
+
+    /// a set by its identifier, e.g. from `id` of another process
+    pub fn from_id(id: c_int) -> SysvSem {
+        SysvSem { id: id }
+    }
+
+    fn get(key: key_t, nsems: c_int, flags: c_int) -> Result<SysvSem> {
+        let id = unsafe { semget(key, nsems, flags) }.check_not_negative()?;
+        Ok(SysvSem { id: id })
+    }
+
+    pub fn id(&self) -> c_int {
+        self.id
+    }
+
+    /// semctl(SETVAL)
+    pub fn set_value(&self, num: c_int, val: c_int) -> Result<()> {
+        unsafe { semctl(self.id, num, SETVAL, semun { val: val }) }.check_not_negative()?;
+        Ok(())
+    }
+
+    /// semctl(GETVAL)
+    pub fn value(&self, num: c_int) -> Result<c_int> {
+        unsafe { semctl(self.id, num, GETVAL) }.check_not_negative()
+    }
+
+    /// semop: all operations are done atomically, blocks unless IPC_NOWAIT is set
+    /// (then it fails with `ErrorKind::WouldBlock`)
+    pub fn op(&self, ops: &mut [sembuf]) -> Result<()> {
+        loop {
+            match unsafe { semop(self.id, ops.as_mut_ptr(), ops.len()) }.check_not_negative() {
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                res => return res.map(|_| ()),
+            }
+        }
+    }
+
+    /// decrement semaphore `num`, blocks while it's 0
+    pub fn wait(&self, num: c_ushort) -> Result<()> {
+        self.op(&mut [sembuf(num, -1, 0)])
+    }
+
+    /// like `wait`, but false instead of blocking
+    pub fn try_wait(&self, num: c_ushort) -> Result<bool> {
+        match self.op(&mut [sembuf(num, -1, IPC_NOWAIT as c_short)]) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.raw_os_error() == Some(EAGAIN) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// increment semaphore `num`
+    pub fn post(&self, num: c_ushort) -> Result<()> {
+        self.op(&mut [sembuf(num, 1, 0)])
+    }
+
+    /// semctl(IPC_RMID), processes blocked on the set fail with EIDRM
+    pub fn remove(self) -> Result<()> {
+        unsafe { semctl(self.id, 0, IPC_RMID) }.check_not_negative()?;
+        Ok(())
+    }
+}
+
+unsafe fn wait(sem: *mut sem_t) -> Result<()> {
+    loop {
+        match sem_wait(sem).check_not_negative() {
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            res => return res.map(|_| ()),
+        }
+    }
+}
+
+unsafe fn try_wait(sem: *mut sem_t) -> Result<bool> {
+    loop {
+        match sem_trywait(sem).check_not_negative() {
+            Ok(_) => return Ok(true),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(ref e) if e.raw_os_error() == Some(EAGAIN) => return Ok(false),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+unsafe fn post(sem: *mut sem_t) -> Result<()> {
+    sem_post(sem).check_not_negative()?;
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn value(sem: *mut sem_t) -> Result<c_int> {
+    let mut val = 0;
+    libc::sem_getvalue(sem, &mut val).check_not_negative()?;
+    Ok(val)
+}
+
+/// POSIX named semaphore, closed when dropped
+#[derive(Debug)]
+pub struct NamedSem {
+    sem: *mut sem_t,
+}
+
+unsafe impl Send for NamedSem {}
+unsafe impl Sync for NamedSem {}
+
+impl NamedSem {
+    /// create the semaphore `name` ("/something") with the initial `value`, fails with
+    /// EEXIST if it already exists
+    pub fn create(name: &str, mode: mode_t, value: c_uint) -> Result<NamedSem> {
+        let name = to_cstring(name)?;
+        let sem = unsafe { sem_open(name.as_ptr(), O_CREAT | O_EXCL, mode as c_uint, value) };
+        NamedSem::check(sem)
+    }
+
+    /// open an existing semaphore
+    pub fn open(name: &str) -> Result<NamedSem> {
+        let name = to_cstring(name)?;
+        NamedSem::check(unsafe { sem_open(name.as_ptr(), 0) })
+    }
+
+    fn check(sem: *mut sem_t) -> Result<NamedSem> {
+        if sem == SEM_FAILED {
+            return Err(Error::last_os_error());
+        }
+        Ok(NamedSem { sem: sem })
+    }
+
+    /// remove the name, the semaphore is destroyed when the last process closed it
+    pub fn unlink(name: &str) -> Result<()> {
+        let name = to_cstring(name)?;
+        unsafe { sem_unlink(name.as_ptr()) }.check_not_negative()?;
+        Ok(())
+    }
+
+    /// decrement, blocks while the value is 0
+    pub fn wait(&self) -> Result<()> {
+        unsafe { wait(self.sem) }
+    }
+
+    /// like `wait`, but false instead of blocking
+    pub fn try_wait(&self) -> Result<bool> {
+        unsafe { try_wait(self.sem) }
+    }
+
+    /// increment
+    pub fn post(&self) -> Result<()> {
+        unsafe { post(self.sem) }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn value(&self) -> Result<c_int> {
+        unsafe { value(self.sem) }
+    }
+}
+
+impl Drop for NamedSem {
+    fn drop(&mut self) {
+        unsafe {
+            sem_close(self.sem);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::unnamed::UnnamedSem;
+
+#[cfg(target_os = "linux")]
+mod unnamed {
+    use super::{post, try_wait, value, wait};
+    use libc::{c_int, c_uint, sem_t};
+    use libc::{sem_destroy, sem_init};
+    use std::io::{Error, ErrorKind, Result};
+    use std::marker::PhantomData;
+    use std::mem::{align_of, size_of};
+    use LibcResult;
+
+    /// POSIX unnamed semaphore in borrowed memory. To share it between processes the
+    /// memory has to be shared, e.g. part of an `apue::ipc::shm::Region` mapped before
+    /// fork. It's not destroyed when dropped because other processes might still use
+    /// it, call `destroy` when everybody is done
+    #[derive(Debug)]
+    pub struct UnnamedSem<'a> {
+        sem: *mut sem_t,
+        _mem: PhantomData<&'a mut [u8]>,
+    }
+
+    unsafe impl<'a> Send for UnnamedSem<'a> {}
+    unsafe impl<'a> Sync for UnnamedSem<'a> {}
+
+    impl<'a> UnnamedSem<'a> {
+        /// bytes `init` needs at the start of `mem`, which has to be aligned for sem_t
+        pub const SIZE: usize = size_of::<sem_t>();
+
+        /// sem_init at the start of `mem`, `pshared` makes it usable across processes
+        pub fn init(mem: &'a mut [u8], pshared: bool, value: c_uint) -> Result<UnnamedSem<'a>> {
+            let aligned = (mem.as_ptr() as usize).is_multiple_of(align_of::<sem_t>());
+            if mem.len() < UnnamedSem::SIZE || !aligned {
+                return Err(Error::new(ErrorKind::InvalidInput,
+                                      "memory too small or not aligned for sem_t"));
+            }
+            let sem = mem.as_mut_ptr() as *mut sem_t;
+            unsafe { sem_init(sem, pshared as c_int, value) }.check_not_negative()?;
+            Ok(UnnamedSem {
+                sem: sem,
+                _mem: PhantomData,
+            })
+        }
+
+        /// decrement, blocks while the value is 0
+        pub fn wait(&self) -> Result<()> {
+            unsafe { wait(self.sem) }
+        }
+
+        /// like `wait`, but false instead of blocking
+        pub fn try_wait(&self) -> Result<bool> {
+            unsafe { try_wait(self.sem) }
+        }
+
+        /// increment
+        pub fn post(&self) -> Result<()> {
+            unsafe { post(self.sem) }
+        }
+
+        pub fn value(&self) -> Result<c_int> {
+            unsafe { value(self.sem) }
+        }
+
+        /// sem_destroy, nobody may be blocked on it
+        pub fn destroy(self) -> Result<()> {
+            unsafe { sem_destroy(self.sem) }.check_not_negative()?;
+            Ok(())
+        }
+    }
+}
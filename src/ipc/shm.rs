@@ -0,0 +1,243 @@
+//! Shared memory, sections 14.8, 15.9 and 15.10
+//!
+//! `Region` is a MAP_SHARED `apue::mmap::MmapMut`, `Attachment` an attached XSI segment
+//! which is detached when dropped. Both hand out their memory as `&[u8]` / `&mut [u8]`
+//! borrowed from the guard, so the slices can't outlive the mapping.
+//!
+//! The memory is shared with other processes (e.g. after fork), Rust's aliasing rules
+//! only cover this process: the processes have to synchronize themselves, e.g. with
+//! the semaphores in `apue::ipc::sem` or `apue::sync_parent_child`.
+//!
+//! Like message queues, an XSI segment exists until `remove` is called and the last
+//! process detached from it, so `SysvShm` doesn't do anything when dropped. A POSIX
+//! shared memory object exists until it is unlinked.
+
+use cstr::to_cstring;
+use fd::Fd;
+use libc::{c_int, c_void, key_t, mode_t, off_t, size_t};
+use libc::{IPC_CREAT, IPC_EXCL, IPC_PRIVATE, IPC_RMID, O_CREAT, O_EXCL, O_RDWR};
+use libc::{ftruncate, shmat, shmctl, shmdt, shmget};
+use mmap::{MmapMut, Sharing};
+use std::ffi::CString;
+use std::io::{Error, Result};
+use std::os::unix::io::FromRawFd;
+use std::{ptr, slice};
+use LibcResult;
+
+/// Shared mapping, unmapped when dropped
+#[derive(Debug)]
+pub struct Region {
+    map: MmapMut,
+}
+
+impl Region {
+    /// map `len` bytes of `fd` starting at `offset` with MAP_SHARED for reading and writing
+    pub fn map_fd(fd: &Fd, len: usize, offset: off_t) -> Result<Region> {
+        Ok(Region { map: MmapMut::map(fd, offset, len, Sharing::Shared)? })
+    }
+
+    /// Figure 15.33: map `/dev/zero`, the zero filled memory is shared with the children
+    pub fn dev_zero(len: usize) -> Result<Region> {
+        let fd = Fd::open("/dev/zero", O_RDWR, 0)?;
+        // the mapping stays when the descriptor is closed
+        Region::map_fd(&fd, len, 0)
+    }
+
+    /// like `dev_zero` but without the device: MAP_ANON (section 15.9)
+    pub fn anonymous(len: usize) -> Result<Region> {
+        Ok(Region { map: MmapMut::anonymous(len, Sharing::Shared)? })
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// start of the mapping, page aligned
+    pub fn as_ptr(&self) -> *const u8 {
+        self.map.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.map.as_mut_ptr()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.map
+    }
+}
+
+/// POSIX shared memory object, the descriptor is closed when dropped
+#[derive(Debug)]
+pub struct PosixShm {
+    fd: Fd,
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn shm_open(name: &CString, oflag: c_int, mode: mode_t) -> c_int {
+    libc::shm_open(name.as_ptr(), oflag, mode)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn shm_open(name: &CString, oflag: c_int, mode: mode_t) -> c_int {
+    libc::shm_open(name.as_ptr(), oflag, mode as libc::c_uint)
+}
+
+impl PosixShm {
+    /// create the object `name` ("/something") with a size of `len` bytes, fails
+    /// with EEXIST if it already exists
+    pub fn create(name: &str, len: usize, mode: mode_t) -> Result<PosixShm> {
+        let name = to_cstring(name)?;
+        let fd = unsafe { shm_open(&name, O_RDWR | O_CREAT | O_EXCL, mode) }.check_not_negative()?;
+        let shm = PosixShm { fd: unsafe { Fd::from_raw_fd(fd) } };
+        if let Err(e) = unsafe { ftruncate(fd, len as off_t) }.check_not_negative() {
+            // we created it, don't leave an object of the wrong size behind
+            unsafe { libc::shm_unlink(name.as_ptr()) };
+            return Err(e);
+        }
+        Ok(shm)
+    }
+
+    /// open an existing object for reading and writing
+    pub fn open(name: &str) -> Result<PosixShm> {
+        let name = to_cstring(name)?;
+        let fd = unsafe { shm_open(&name, O_RDWR, 0) }.check_not_negative()?;
+        Ok(PosixShm { fd: unsafe { Fd::from_raw_fd(fd) } })
+    }
+
+    /// remove the name, existing mappings stay valid
+    pub fn unlink(name: &str) -> Result<()> {
+        let name = to_cstring(name)?;
+        unsafe { libc::shm_unlink(name.as_ptr()) }.check_not_negative()?;
+        Ok(())
+    }
+
+    /// size of the object
+    pub fn len(&self) -> Result<usize> {
+        self.fd.file_size().map(|size| size as usize)
+    }
+
+    /// whether the object has size 0
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// map the whole object
+    pub fn map(&self) -> Result<Region> {
+        Region::map_fd(&self.fd, self.len()?, 0)
+    }
+}
+
+/// XSI shared memory segment
+#[derive(Debug)]
+pub struct SysvShm {
+    id: c_int,
+}
+
+impl SysvShm {
+    /// create a segment of `size` bytes for `key`, fails with EEXIST if there's already one
+    pub fn create(key: key_t, size: usize, mode: mode_t) -> Result<SysvShm> {
+        SysvShm::get(key, size, IPC_CREAT | IPC_EXCL | mode as c_int)
+    }
+
+    /// the existing segment for `key`
+    pub fn open(key: key_t) -> Result<SysvShm> {
+        SysvShm::get(key, 0, 0)
+    }
+
+    /// create a segment with IPC_PRIVATE, only reachable through `id` (or a fork)
+    pub fn private(size: usize, mode: mode_t) -> Result<SysvShm> {
+        SysvShm::get(IPC_PRIVATE, size, mode as c_int)
+    }
+
+    /// a segment by its identifier, e.g. from `id` of another process
+    pub fn from_id(id: c_int) -> SysvShm {
+        SysvShm { id: id }
+    }
+
+    fn get(key: key_t, size: size_t, flags: c_int) -> Result<SysvShm> {
+        let id = unsafe { shmget(key, size, flags) }.check_not_negative()?;
+        Ok(SysvShm { id: id })
+    }
+
+    pub fn id(&self) -> c_int {
+        self.id
+    }
+
+    /// shmctl(IPC_STAT)
+    pub fn stat(&self) -> Result<libc::shmid_ds> {
+        let mut buf: libc::shmid_ds = unsafe { ::std::mem::zeroed() };
+        unsafe { shmctl(self.id, libc::IPC_STAT, &mut buf) }.check_not_negative()?;
+        Ok(buf)
+    }
+
+    /// attach the segment at an address chosen by the kernel, for reading and writing
+    pub fn attach(&self) -> Result<Attachment> {
+        let len = self.stat()?.shm_segsz;
+        let ptr = unsafe { shmat(self.id, ptr::null(), 0) };
+        if ptr as isize == -1 {
+            return Err(Error::last_os_error());
+        }
+        Ok(Attachment {
+            ptr: ptr as *mut u8,
+            len: len,
+        })
+    }
+
+    /// shmctl(IPC_RMID), the segment is destroyed when the last process detached it
+    pub fn remove(self) -> Result<()> {
+        unsafe { shmctl(self.id, IPC_RMID, ptr::null_mut()) }.check_not_negative()?;
+        Ok(())
+    }
+}
+
+/// Attached XSI shared memory segment, detached when dropped
+#[derive(Debug)]
+pub struct Attachment {
+    ptr: *mut u8,
+    len: usize,
+}
+
+unsafe impl Send for Attachment {}
+unsafe impl Sync for Attachment {}
+
+impl Attachment {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        unsafe {
+            shmdt(self.ptr as *const c_void);
+        }
+    }
+}
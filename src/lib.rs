@@ -2,20 +2,47 @@ extern crate libc;
 extern crate errno;
 extern crate num;
 
-use libc::{c_int, c_char, dev_t, utsname, sigset_t, PATH_MAX, SA_RESTART, EINTR};
-use libc::{SIG_ERR, SIG_BLOCK, SIG_IGN, SIG_SETMASK, SIGALRM, SIGINT, SIGUSR1, SIGQUIT, SIGCHLD};
-use libc::{WSTOPSIG, WEXITSTATUS, WIFSTOPPED, WCOREDUMP, WTERMSIG, WIFSIGNALED, WIFEXITED};
-use libc::{exit, _exit, sigemptyset, sigaddset, sigaction, sigismember, fork, waitpid};
-use my_libc::{sigprocmask, execl, sys_nerr, sys_errlist};
-use std::io::Write;
+use libc::{c_int, c_char, c_void, dev_t, sighandler_t, utsname, SA_RESTART, EINTR};
+use libc::{MAP_FAILED, SIG_ERR, SIGALRM, SIGINT, SIGQUIT, SIGCHLD};
+use libc::{_exit, fork, waitpid};
+use cstr::CStrArgs;
+use my_libc::execl;
+use signals::{SigSet, SigAction, Handler};
+use wait::WaitStatus;
+pub use error::{strerror, Context};
+pub use popen::{popen, pclose};
 use std::ffi::CStr;
-use std::mem::{zeroed, uninitialized};
-use std::ptr::{null, null_mut};
+use std::mem::uninitialized;
 use std::io::{Result, Error};
-use num::{Signed, NumCast};
-
-/// Turns a str into a c string. Warning: the cstring only lives as long the
-/// str lives. Don't e.g. assign the return value to a variable!
+use num::NumCast;
+
+pub mod aio;
+pub mod coprocess;
+pub mod cstr;
+pub mod daemon;
+pub mod error;
+pub mod exec;
+pub mod fd;
+pub mod fifo;
+pub mod io;
+pub mod ipc;
+pub mod limits;
+pub mod lock;
+pub mod mmap;
+pub mod msgqueue;
+pub mod poll;
+pub mod popen;
+pub mod process;
+pub mod signals;
+pub mod sync_parent_child;
+pub mod syslog;
+pub mod timing;
+pub mod wait;
+
+/// Turns a str into a c string. Warning: the pointer dangles once the statement
+/// is over. Don't e.g. assign the return value to a variable! Use `cstr::with_cstr`
+/// or `cstr::CStrArgs`, which own the strings, or a literal ending in `\0`.
+#[deprecated(note = "use cstr::with_cstr / CStrArgs")]
 #[macro_export]
 macro_rules! cstr {
     ($s:expr) => {{
@@ -56,18 +83,27 @@ macro_rules! print_err {
     )
 }
 
+/// Maps the return value of a libc call into an `io::Result`, taking errno when the
+/// value means failure. Implemented for the integer types behind `c_int`, `pid_t`,
+/// `off_t`, `ssize_t`, `c_long`, `size_t` and `sighandler_t`. Add `error::Context` to
+/// say which call failed
 pub trait LibcResult<T> {
     fn check_not_negative(&self) -> Result<T>;
     fn check_positive(&self) -> Result<T>;
-    /// verify that the command exited with -1, if true, then last error
-    /// is wrapped into Ok(last_error)
-    fn check_minus_one(&self) -> Result<i32>;
+    /// fail with errno if the value is -1 (all bits set for unsigned types, like
+    /// `(size_t) -1`), other values are returned as they are
+    fn check_minus_one(&self) -> Result<T>;
+    /// the value is an error number itself, as returned by the pthread functions
     fn check_zero(&self) -> Result<T>;
+    /// fail with errno if the value is `SIG_ERR`, as returned by signal(3)
     fn check_not_sigerr(&self) -> Result<T>;
 }
 
+/// `LibcResult` for functions returning pointers
 pub trait LibcPtrResult<T> {
     fn check_not_null(&self) -> Result<T>;
+    /// fail with errno if the pointer is `MAP_FAILED`, as returned by mmap(2)
+    fn check_not_map_failed(&self) -> Result<T>;
 }
 
 fn check_positive<N: num::Num + PartialOrd + Copy>(val: N) -> Result<N> {
@@ -87,14 +123,6 @@ fn check_not_negative<N: num::Num + PartialOrd + Copy>(val: N) -> Result<N> {
     }
 }
 
-fn check_minus_one<N: num::Num + PartialOrd + Copy + Signed>(val: N) -> Result<i32> {
-    if val.is_negative() && val.abs() == N::one() {
-        Ok(errno::errno().0)
-    } else {
-        Err(Error::from(std::io::ErrorKind::Other))
-    }
-}
-
 fn check_zero<N: num::Num + PartialOrd + Copy + NumCast>(val: N) -> Result<N> {
     if val == N::zero() {
         Ok(val)
@@ -103,95 +131,61 @@ fn check_zero<N: num::Num + PartialOrd + Copy + NumCast>(val: N) -> Result<N> {
     }
 }
 
-fn check_not_sigerr<N: num::Num + PartialOrd + Copy + NumCast>(val: N) -> Result<N> {
-    if val == NumCast::from(SIG_ERR).unwrap() {
-        Err(Error::from(std::io::ErrorKind::Other))
-    } else {
-        Ok(val)
-    }
-}
-
-impl LibcResult<c_int> for c_int {
-    fn check_not_negative(&self) -> Result<c_int> {
-        check_not_negative(*self)
-    }
-    fn check_positive(&self) -> Result<c_int> {
-        check_positive(*self)
-    }
-    fn check_minus_one(&self) -> Result<c_int> {
-        check_minus_one(*self)
-    }
-    fn check_zero(&self) -> Result<c_int> {
-        check_zero(*self)
-    }
-    fn check_not_sigerr(&self) -> Result<c_int> {
-        check_not_sigerr(*self)
-    }
-}
-impl LibcResult<i64> for i64 {
-    fn check_not_negative(&self) -> Result<i64> {
-        check_not_negative(*self)
-    }
-    fn check_positive(&self) -> Result<i64> {
-        check_positive(*self)
-    }
-    fn check_minus_one(&self) -> Result<i32> {
-        check_minus_one(*self)
-    }
-    fn check_zero(&self) -> Result<i64> {
-        check_zero(*self)
-    }
-    fn check_not_sigerr(&self) -> Result<i64> {
-        check_not_sigerr(*self)
-    }
-}
-
-impl LibcResult<isize> for isize {
-    fn check_not_negative(&self) -> Result<isize> {
-        check_not_negative(*self)
-    }
-    fn check_positive(&self) -> Result<isize> {
-        check_positive(*self)
-    }
-    fn check_minus_one(&self) -> Result<i32> {
-        check_minus_one(*self)
-    }
-    fn check_zero(&self) -> Result<isize> {
-        check_zero(*self)
-    }
-    fn check_not_sigerr(&self) -> Result<isize> {
-        check_not_sigerr(*self)
-    }
+macro_rules! impl_libc_result {
+    ($($t:ty)*) => ($(
+        impl LibcResult<$t> for $t {
+            fn check_not_negative(&self) -> Result<$t> {
+                check_not_negative(*self)
+            }
+            fn check_positive(&self) -> Result<$t> {
+                check_positive(*self)
+            }
+            fn check_minus_one(&self) -> Result<$t> {
+                if *self == -1i64 as $t {
+                    Err(Error::last_os_error())
+                } else {
+                    Ok(*self)
+                }
+            }
+            fn check_zero(&self) -> Result<$t> {
+                check_zero(*self)
+            }
+            fn check_not_sigerr(&self) -> Result<$t> {
+                if *self as sighandler_t == SIG_ERR {
+                    Err(Error::last_os_error())
+                } else {
+                    Ok(*self)
+                }
+            }
+        }
+    )*)
 }
 
-impl LibcResult<usize> for usize {
-    fn check_not_negative(&self) -> Result<usize> {
-        check_not_negative(*self)
-    }
-    fn check_positive(&self) -> Result<usize> {
-        check_positive(*self)
-    }
-    fn check_minus_one(&self) -> Result<i32> {
-        unimplemented!();
-    }
-    fn check_zero(&self) -> Result<usize> {
-        check_zero(*self)
-    }
-    fn check_not_sigerr(&self) -> Result<usize> {
-        check_not_sigerr(*self)
-    }
-}
+impl_libc_result!(i32 i64 isize usize);
 
-impl<T> LibcPtrResult<*mut T> for *mut T {
-    fn check_not_null(&self) -> Result<*mut T> {
-        if self.is_null() {
-            Err(Error::last_os_error())
-        } else {
-            Ok(*self)
+macro_rules! impl_libc_ptr_result {
+    ($($t:ty)*) => ($(
+        impl<T> LibcPtrResult<$t> for $t {
+            fn check_not_null(&self) -> Result<$t> {
+                if self.is_null() {
+                    Err(Error::last_os_error())
+                } else {
+                    Ok(*self)
+                }
+            }
+            fn check_not_map_failed(&self) -> Result<$t> {
+                if *self as *const c_void == MAP_FAILED as *const c_void {
+                    Err(Error::last_os_error())
+                } else {
+                    Ok(*self)
+                }
+            }
         }
-    }
+    )*)
 }
 
+impl_libc_ptr_result!(*mut T *const T);
+
 pub unsafe fn array_to_string(sl: &[i8]) -> &str {
     CStr::from_ptr(sl.as_ptr()).to_str().expect("invalid string")
 }
@@ -208,33 +202,10 @@ pub fn uname() -> Option<String> {
     None
 }
 
-pub fn err_sys(msg: &str) {
-    std::io::stderr().write(format!("{}{}", msg, "\n").as_bytes()).unwrap();
-    unsafe {
-        exit(1);
-    }
-}
-
-pub fn strerror(error: i32) -> String {
-    unsafe {
-        if error >= 0 && error <= sys_nerr {
-            // avoiding rusts array bound checking (sys_errlist has length 0usize as the length
-            // is not known at compile time)
-            CStr::from_ptr(*sys_errlist.as_ptr().offset(error as _) as _)
-                .to_owned()
-                .into_string()
-                .expect("not valid string")
-        } else {
-            format!("Unknown error {}", error)
-        }
-    }
-}
 
+/// buffer for a pathname, sized by `limits::path_max` for the root file system
 pub fn path_alloc() -> std::vec::Vec<c_char> {
-    // Before POSIX.1-2001, we aren’t guaranteed that PATH_MAX includes
-    // the terminating null byte. For simplicity sake we don't check for the posix
-    // version and just increase by one
-    Vec::with_capacity((PATH_MAX + 1) as usize)
+    Vec::with_capacity(limits::path_max("/"))
 }
 // major device number, impl ported from /usr/include/sys/types.h
 pub fn major(x: dev_t) -> dev_t {
@@ -247,75 +218,49 @@ pub fn minor(x: dev_t) -> dev_t {
 }
 
 pub fn pr_exit(status: c_int) {
-    unsafe {
-        if WIFEXITED(status) {
-            println!("normal termination, exit status = {}", WEXITSTATUS(status));
-        } else if WIFSIGNALED(status) {
-            println!("abnormal termination, signal number = {} {}",
-                     WTERMSIG(status),
-                     if WCOREDUMP(status) {
-                         " (core file generated)"
-                     } else {
-                         ""
-                     });
-        } else if WIFSTOPPED(status) {
-            println!("child stopped, signal number = {}", WSTOPSIG(status));
-        }
-    }
-}
-
-macro_rules! print_sig {
-    ($set:expr, $s:expr) => {{
-        if sigismember($set, $s) == 1 {
-            print!(" {}", stringify!($s));
-        }
-    }}
+    println!("{}", WaitStatus::from_raw(status));
 }
 
 pub fn pr_mask(s: &str) {
-    unsafe {
-        let errno_save = errno::errno();
-        let mut sigset: sigset_t = std::mem::uninitialized();
-        sigprocmask(0, null(), &mut sigset).check_not_negative().expect("sigprocmask error");
-        print!("{}", s);
-        print_sig!(&sigset, SIGINT);
-        print_sig!(&sigset, SIGQUIT);
-        print_sig!(&sigset, SIGUSR1);
-        print_sig!(&sigset, SIGALRM);
-        print!("\n");
-        errno::set_errno(errno_save);
+    let errno_save = errno::errno();
+    let sigset = SigSet::current().expect("sigprocmask error");
+    print!("{}", s);
+    for signo in sigset.iter() {
+        match signals::sig2str(signo) {
+            Some(name) => print!(" {}", name),
+            None => print!(" {}", signo),
+        }
     }
+    print!("\n");
+    errno::set_errno(errno_save);
 }
 
 // Figure 10.18: Reliable version of signal(), using POSIX sigaction()
 pub unsafe fn signal(signo: i32, func: fn(c_int)) -> usize {
-    let mut act: sigaction = zeroed();
-    let mut oact: sigaction = uninitialized();
-    act.sa_sigaction = func as usize;
-    sigemptyset(&mut act.sa_mask);
-    act.sa_flags = 0;
+    let mut act = SigAction::new(Handler::from_raw(func as usize, 0));
     if signo != SIGALRM {
-        act.sa_flags |= SA_RESTART;
+        act = act.flags(SA_RESTART);
     }
-    if sigaction(signo, &act, &mut oact) < 0 {
-        SIG_ERR
-    } else {
-        oact.sa_sigaction as usize
+    match act.install(signo) {
+        Ok(saved) => saved.keep().as_raw(),
+        Err(_) => SIG_ERR,
     }
 }
 
 // Figure 8.22 The system function, without signal handling
 pub fn system(cmdstring: &str) -> Result<i32> {
+    // allocated before fork, the child only execs
+    let argv = CStrArgs::argv(["sh", "-c", cmdstring])?;
     unsafe {
         let pid = fork().check_not_negative()?;
         match pid {
             0 => {
                 // child
-                execl(cstr!("/bin/sh"),
-                      cstr!("sh"),
-                      cstr!("-c"),
-                      cstr!(cmdstring),
-                      0 as *const c_char);
+                execl(b"/bin/sh\0".as_ptr() as *const c_char,
+                      argv.ptr(0),
+                      argv.ptr(1),
+                      argv.ptr(2),
+                      argv.ptr(3));
                 _exit(127);
             }
             _ => {
@@ -336,41 +281,40 @@ pub fn system(cmdstring: &str) -> Result<i32> {
 // Figure 10.28 Correct POSIX.1 implementation of system function
 // (with signal handling)
 pub fn system2(cmdstring: &str) -> Result<i32> {
+    let argv = CStrArgs::argv(["sh", "-c", cmdstring])?;
     unsafe {
-        let mut ignore: sigaction = std::mem::zeroed();
-        let (mut saveintr, mut savemask, mut savequit) = uninitialized();
-        ignore.sa_sigaction = SIG_IGN; // ignore SIGINT and SIGQUIT
-        sigemptyset(&mut ignore.sa_mask);
-        ignore.sa_flags = 0;
-        sigaction(SIGINT, &ignore, &mut saveintr).check_not_negative()?;
-        sigaction(SIGQUIT, &ignore, &mut savequit).check_not_negative()?;
-        let mut chldmask = uninitialized();
-        sigemptyset(&mut chldmask);
-        sigaddset(&mut chldmask, SIGCHLD);
-        sigprocmask(SIG_BLOCK, &chldmask, &mut savemask).check_not_negative()?;
-
-        let pid = fork().check_not_negative()?;
+        // ignore SIGINT and SIGQUIT, the previous actions are restored on return
+        let saveintr = SigAction::new(Handler::Ignore).install(SIGINT)?;
+        let savequit = SigAction::new(Handler::Ignore).install(SIGQUIT)?;
+        let savemask = [SIGCHLD].iter().cloned().collect::<SigSet>().block()?;
+
+        // the actions restore themselves when dropped, the mask has to be reset by hand
+        let pid = fork().check_not_negative().map_err(|e| {
+            let _ = savemask.set_mask();
+            e
+        })?;
         let mut status = 0;
         if pid == 0 {
-            sigaction(SIGINT, &mut saveintr, null_mut());
-            sigaction(SIGQUIT, &mut savequit, null_mut());
-            sigprocmask(SIG_SETMASK, &mut savemask, null_mut());
-            execl(cstr!("/bin/sh"),
-                  cstr!("sh"),
-                  cstr!("-c"),
-                  cstr!(cmdstring),
-                  0 as *const c_char);
+            let _ = saveintr.restore();
+            let _ = savequit.restore();
+            let _ = savemask.set_mask();
+            execl(b"/bin/sh\0".as_ptr() as *const c_char,
+                  argv.ptr(0),
+                  argv.ptr(1),
+                  argv.ptr(2),
+                  argv.ptr(3));
             _exit(127); // exec error
         } else {
             while waitpid(pid, &mut status, 0) < 0 {
                 if errno::errno().0 != EINTR {
+                    let _ = savemask.set_mask();
                     return Err(Error::from_raw_os_error(errno::errno().0));
                 }
             }
         }
-        sigaction(SIGINT, &saveintr, null_mut()).check_not_negative()?;
-        sigaction(SIGQUIT, &savequit, null_mut()).check_not_negative()?;
-        sigprocmask(SIG_SETMASK, &savemask, null_mut()).check_not_negative()?;
+        saveintr.restore()?;
+        savequit.restore()?;
+        savemask.set_mask()?;
         Ok(status)
     }
 }
@@ -380,98 +324,12 @@ pub fn system2(cmdstring: &str) -> Result<i32> {
 // with the only difference that no system call is restarted
 pub fn signal_intr(signo: i32, func: fn(c_int)) -> Result<usize> {
     unsafe {
-        let mut act: sigaction = zeroed();
-        let mut oact: sigaction = uninitialized();
-        act.sa_sigaction = func as usize;
-        sigemptyset(&mut act.sa_mask);
-        act.sa_flags = 0;
-
-        sigaction(signo, &act, &mut oact).check_not_negative()?;
-        Ok(oact.sa_sigaction as usize)
+        let saved = SigAction::new(Handler::from_raw(func as usize, 0)).install(signo)?;
+        Ok(saved.keep().as_raw())
     }
 }
 
 
-/// Figure 10.24 Routines to allow a parent and child to synchronize
-///
-/// Status: only compiles, did not yet run it to check for correctness
-/// has still bugs for sure..
-pub mod sync_parent_child {
-    use my_libc::{sigprocmask, sigsuspend};
-    use LibcResult;
-    use libc::{SIGUSR1, SIGUSR2, SIG_BLOCK, SIG_SETMASK, c_int, pid_t, sigset_t};
-    use libc::{signal, sigemptyset, sigaddset, kill};
-    use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
-    use std::mem::uninitialized;
-    use std::ptr::null_mut;
-
-    static mut SIGFLAG: AtomicBool = ATOMIC_BOOL_INIT;
-    //    #[cfg(target_os = "macos")]
-    //    static mut OLDMASK: sigset_t = 0;
-    //    #[cfg(target_os = "macos")]
-    //    static mut ZEROMASK: sigset_t = 0;
-    //    #[cfg(target_os = "linux")]
-    //    static mut OLDMASK: sigset_t = sigset_t { __val: [0;16] };
-    //    #[cfg(target_os = "linux")]
-    //    static mut ZEROMASK: sigset_t = sigset_t { __val: [0;16] };
-
-    pub fn sig_usr(_: c_int) {
-        unsafe {
-            SIGFLAG.store(true, Ordering::SeqCst);
-        }
-    }
-
-    pub fn tell_wait() -> sigset_t {
-        unsafe {
-            let mut newmask = uninitialized();
-            signal(SIGUSR1, sig_usr as usize).check_not_sigerr().expect("signal(SIGUSR1) error");
-            signal(SIGUSR2, sig_usr as usize).check_not_sigerr().expect("signal(SIGUSR2) error");
-            let mut zeromask = uninitialized();
-            sigemptyset(&mut zeromask);
-            sigemptyset(&mut newmask);
-            sigaddset(&mut newmask, SIGUSR1);
-            sigaddset(&mut newmask, SIGUSR2);
-
-            // Block SIGUSR1 and SIGUSR2 and save current signal mask
-            let mut oldmask = uninitialized();
-            sigprocmask(SIG_BLOCK, &newmask, &mut oldmask).check_not_negative().expect("SIG_BLOCK error");
-            oldmask
-        }
-    }
-
-    pub unsafe fn tell_parent(pid: pid_t) {
-        kill(pid, SIGUSR2); // tell parent we're done
-    }
-
-    pub unsafe fn wait_parent(oldmask: sigset_t) {
-        let mut zeromask = uninitialized();
-        sigemptyset(&mut zeromask);
-        // run until sigflag becomes true, then set it to false again immediately
-        while !SIGFLAG.compare_and_swap(true, false, Ordering::SeqCst) {
-            sigsuspend(&zeromask);
-        }
-        // Reset signal mask to original value
-        sigprocmask(SIG_SETMASK, &oldmask, null_mut()).check_not_negative().expect("SIG_SETMASK error");
-    }
-
-    pub unsafe fn tell_child(pid: pid_t) {
-        kill(pid, SIGUSR1); // tell child we’re done
-    }
-
-    pub unsafe fn wait_child(oldmask: sigset_t) {
-        let mut zeromask = uninitialized();
-        sigemptyset(&mut zeromask);
-        // run until sigflag becomes true, then set it to false again immediately
-        while !SIGFLAG.compare_and_swap(true, false, Ordering::SeqCst) {
-            sigsuspend(&zeromask);
-        }
-        // Reset signal mask to original value
-        sigprocmask(SIG_SETMASK, &oldmask, null_mut())
-            .check_not_negative()
-            .expect("SIG_SETMASK error");
-    }
-}
-
 #[allow(non_camel_case_types)]
 pub mod my_libc {
     use libc::{dirent, c_void, c_int, c_char, c_long, c_ulong, c_uint, pid_t, clock_t, siginfo_t,
@@ -595,6 +453,8 @@ pub mod my_libc {
         pub fn execle(__path: *const c_char, __arg0: *const c_char, ...) -> c_int;
         pub fn execlp(__file: *const c_char, __arg0: *const c_char, ...) -> c_int;
 
+        pub static mut environ: *const *const c_char;
+
         pub fn waitid(arg1: idtype_t, arg2: id_t, arg3: *mut siginfo_t, arg4: c_int) -> c_int;
 
         #[cfg(target_os = "macos")]
@@ -637,9 +497,6 @@ pub mod my_libc {
                             arg2: Option<unsafe extern "C" fn()>)
                             -> c_int;
 
-        pub static mut sys_errlist: [*const c_char; 0usize];
-        pub static sys_nerr: c_int;
-
         pub fn qsort(__base: *mut c_void,
                      __nel: size_t,
                      __width: size_t,
@@ -654,5 +511,20 @@ pub mod my_libc {
         pub fn random() -> c_long;
         pub fn srandom(arg1: c_uint);
         pub fn clock_gettime(__clock_id: clockid_t, __tp: *mut timespec) -> c_int;
+
+        // XSI message queues are missing in libc for macOS
+        #[cfg(target_os = "macos")]
+        pub fn msgget(key: ::libc::key_t, msgflg: c_int) -> c_int;
+        #[cfg(target_os = "macos")]
+        pub fn msgsnd(msqid: c_int, msgp: *const c_void, msgsz: size_t, msgflg: c_int) -> c_int;
+        #[cfg(target_os = "macos")]
+        pub fn msgrcv(msqid: c_int,
+                      msgp: *mut c_void,
+                      msgsz: size_t,
+                      msgtyp: c_long,
+                      msgflg: c_int)
+                      -> ::libc::ssize_t;
+        #[cfg(target_os = "macos")]
+        pub fn msgctl(msqid: c_int, cmd: c_int, buf: *mut c_void) -> c_int;
     }
 }
@@ -0,0 +1,485 @@
+//! Limits, section 2.5
+//!
+//! Every name sysconf(3), pathconf(3) and fpathconf(3) know is a variant of `Sysconf`
+//! or `Pathconf`, taken from the constants of libc. Those which only exist on one
+//! system are only defined there. The result of a query is a `Limit`, which tells an
+//! indeterminate limit (-1 without errno, usually "unlimited") from a name the system
+//! doesn't support (EINVAL).
+
+use cstr::to_cstring;
+use errno::{errno, set_errno, Errno};
+use libc::{c_int, c_long, EINVAL};
+use std::fmt;
+use std::io::{Error, Result};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+// the enum, `as_raw`, `name` and `all` of a list of libc constants
+macro_rules! limit_names {
+    ($(#[$attr:meta])* pub enum $name:ident { $($(#[$cfg:meta])* $variant:ident = $raw:ident,)* }) => {
+        $(#[$attr])*
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum $name {
+            $($(#[$cfg])* $variant,)*
+        }
+
+        impl $name {
+            pub fn as_raw(&self) -> c_int {
+                match *self {
+                    $($(#[$cfg])* $name::$variant => libc::$raw,)*
+                }
+            }
+
+            /// the name of the libc constant, e.g. "_SC_ARG_MAX"
+            pub fn name(&self) -> &'static str {
+                match *self {
+                    $($(#[$cfg])* $name::$variant => stringify!($raw),)*
+                }
+            }
+
+            /// all names defined on this system, sorted by name
+            pub fn all() -> Vec<$name> {
+                vec![$($(#[$cfg])* $name::$variant,)*]
+            }
+        }
+    }
+}
+
+limit_names! {
+    /// names for `sysconf`
+    pub enum Sysconf {
+        Posix2CharTerm = _SC_2_CHAR_TERM,
+        Posix2CBind = _SC_2_C_BIND,
+        Posix2CDev = _SC_2_C_DEV,
+        #[cfg(target_os = "linux")]
+        Posix2CVersion = _SC_2_C_VERSION,
+        Posix2FortDev = _SC_2_FORT_DEV,
+        Posix2FortRun = _SC_2_FORT_RUN,
+        Posix2Localedef = _SC_2_LOCALEDEF,
+        Posix2Pbs = _SC_2_PBS,
+        Posix2PbsAccounting = _SC_2_PBS_ACCOUNTING,
+        Posix2PbsCheckpoint = _SC_2_PBS_CHECKPOINT,
+        Posix2PbsLocate = _SC_2_PBS_LOCATE,
+        Posix2PbsMessage = _SC_2_PBS_MESSAGE,
+        Posix2PbsTrack = _SC_2_PBS_TRACK,
+        Posix2SwDev = _SC_2_SW_DEV,
+        Posix2Upe = _SC_2_UPE,
+        Posix2Version = _SC_2_VERSION,
+        AdvisoryInfo = _SC_ADVISORY_INFO,
+        AioListioMax = _SC_AIO_LISTIO_MAX,
+        AioMax = _SC_AIO_MAX,
+        AioPrioDeltaMax = _SC_AIO_PRIO_DELTA_MAX,
+        ArgMax = _SC_ARG_MAX,
+        AsynchronousIo = _SC_ASYNCHRONOUS_IO,
+        AtexitMax = _SC_ATEXIT_MAX,
+        #[cfg(target_os = "linux")]
+        AvphysPages = _SC_AVPHYS_PAGES,
+        Barriers = _SC_BARRIERS,
+        #[cfg(target_os = "linux")]
+        Base = _SC_BASE,
+        BcBaseMax = _SC_BC_BASE_MAX,
+        BcDimMax = _SC_BC_DIM_MAX,
+        BcScaleMax = _SC_BC_SCALE_MAX,
+        BcStringMax = _SC_BC_STRING_MAX,
+        #[cfg(target_os = "linux")]
+        CharclassNameMax = _SC_CHARCLASS_NAME_MAX,
+        #[cfg(target_os = "linux")]
+        CharBit = _SC_CHAR_BIT,
+        #[cfg(target_os = "linux")]
+        CharMax = _SC_CHAR_MAX,
+        #[cfg(target_os = "linux")]
+        CharMin = _SC_CHAR_MIN,
+        ChildMax = _SC_CHILD_MAX,
+        ClkTck = _SC_CLK_TCK,
+        ClockSelection = _SC_CLOCK_SELECTION,
+        CollWeightsMax = _SC_COLL_WEIGHTS_MAX,
+        Cputime = _SC_CPUTIME,
+        #[cfg(target_os = "linux")]
+        CLangSupport = _SC_C_LANG_SUPPORT,
+        #[cfg(target_os = "linux")]
+        CLangSupportR = _SC_C_LANG_SUPPORT_R,
+        DelaytimerMax = _SC_DELAYTIMER_MAX,
+        #[cfg(target_os = "linux")]
+        DeviceIo = _SC_DEVICE_IO,
+        #[cfg(target_os = "linux")]
+        DeviceSpecific = _SC_DEVICE_SPECIFIC,
+        #[cfg(target_os = "linux")]
+        DeviceSpecificR = _SC_DEVICE_SPECIFIC_R,
+        #[cfg(target_os = "linux")]
+        EquivClassMax = _SC_EQUIV_CLASS_MAX,
+        ExprNestMax = _SC_EXPR_NEST_MAX,
+        #[cfg(target_os = "linux")]
+        FdMgmt = _SC_FD_MGMT,
+        #[cfg(target_os = "linux")]
+        Fifo = _SC_FIFO,
+        #[cfg(target_os = "linux")]
+        FileAttributes = _SC_FILE_ATTRIBUTES,
+        FileLocking = _SC_FILE_LOCKING,
+        #[cfg(target_os = "linux")]
+        FileSystem = _SC_FILE_SYSTEM,
+        Fsync = _SC_FSYNC,
+        GetgrRSizeMax = _SC_GETGR_R_SIZE_MAX,
+        GetpwRSizeMax = _SC_GETPW_R_SIZE_MAX,
+        HostNameMax = _SC_HOST_NAME_MAX,
+        #[cfg(target_os = "linux")]
+        IntMax = _SC_INT_MAX,
+        #[cfg(target_os = "linux")]
+        IntMin = _SC_INT_MIN,
+        IovMax = _SC_IOV_MAX,
+        Ipv6 = _SC_IPV6,
+        JobControl = _SC_JOB_CONTROL,
+        #[cfg(target_os = "linux")]
+        Level1DcacheAssoc = _SC_LEVEL1_DCACHE_ASSOC,
+        #[cfg(target_os = "linux")]
+        Level1DcacheLinesize = _SC_LEVEL1_DCACHE_LINESIZE,
+        #[cfg(target_os = "linux")]
+        Level1DcacheSize = _SC_LEVEL1_DCACHE_SIZE,
+        #[cfg(target_os = "linux")]
+        Level1IcacheAssoc = _SC_LEVEL1_ICACHE_ASSOC,
+        #[cfg(target_os = "linux")]
+        Level1IcacheLinesize = _SC_LEVEL1_ICACHE_LINESIZE,
+        #[cfg(target_os = "linux")]
+        Level1IcacheSize = _SC_LEVEL1_ICACHE_SIZE,
+        #[cfg(target_os = "linux")]
+        Level2CacheAssoc = _SC_LEVEL2_CACHE_ASSOC,
+        #[cfg(target_os = "linux")]
+        Level2CacheLinesize = _SC_LEVEL2_CACHE_LINESIZE,
+        #[cfg(target_os = "linux")]
+        Level2CacheSize = _SC_LEVEL2_CACHE_SIZE,
+        #[cfg(target_os = "linux")]
+        Level3CacheAssoc = _SC_LEVEL3_CACHE_ASSOC,
+        #[cfg(target_os = "linux")]
+        Level3CacheLinesize = _SC_LEVEL3_CACHE_LINESIZE,
+        #[cfg(target_os = "linux")]
+        Level3CacheSize = _SC_LEVEL3_CACHE_SIZE,
+        #[cfg(target_os = "linux")]
+        Level4CacheAssoc = _SC_LEVEL4_CACHE_ASSOC,
+        #[cfg(target_os = "linux")]
+        Level4CacheLinesize = _SC_LEVEL4_CACHE_LINESIZE,
+        #[cfg(target_os = "linux")]
+        Level4CacheSize = _SC_LEVEL4_CACHE_SIZE,
+        LineMax = _SC_LINE_MAX,
+        LoginNameMax = _SC_LOGIN_NAME_MAX,
+        #[cfg(target_os = "linux")]
+        LongBit = _SC_LONG_BIT,
+        MappedFiles = _SC_MAPPED_FILES,
+        #[cfg(target_os = "linux")]
+        MbLenMax = _SC_MB_LEN_MAX,
+        Memlock = _SC_MEMLOCK,
+        MemlockRange = _SC_MEMLOCK_RANGE,
+        MemoryProtection = _SC_MEMORY_PROTECTION,
+        MessagePassing = _SC_MESSAGE_PASSING,
+        MonotonicClock = _SC_MONOTONIC_CLOCK,
+        MqOpenMax = _SC_MQ_OPEN_MAX,
+        MqPrioMax = _SC_MQ_PRIO_MAX,
+        #[cfg(target_os = "linux")]
+        MultiProcess = _SC_MULTI_PROCESS,
+        #[cfg(target_os = "linux")]
+        Networking = _SC_NETWORKING,
+        NgroupsMax = _SC_NGROUPS_MAX,
+        #[cfg(target_os = "linux")]
+        NlArgmax = _SC_NL_ARGMAX,
+        #[cfg(target_os = "linux")]
+        NlLangmax = _SC_NL_LANGMAX,
+        #[cfg(target_os = "linux")]
+        NlMsgmax = _SC_NL_MSGMAX,
+        #[cfg(target_os = "linux")]
+        NlNmax = _SC_NL_NMAX,
+        #[cfg(target_os = "linux")]
+        NlSetmax = _SC_NL_SETMAX,
+        #[cfg(target_os = "linux")]
+        NlTextmax = _SC_NL_TEXTMAX,
+        NprocessorsConf = _SC_NPROCESSORS_CONF,
+        NprocessorsOnln = _SC_NPROCESSORS_ONLN,
+        #[cfg(target_os = "linux")]
+        Nzero = _SC_NZERO,
+        OpenMax = _SC_OPEN_MAX,
+        Pagesize = _SC_PAGESIZE,
+        PageSize = _SC_PAGE_SIZE,
+        PassMax = _SC_PASS_MAX,
+        PhysPages = _SC_PHYS_PAGES,
+        #[cfg(target_os = "linux")]
+        Pii = _SC_PII,
+        #[cfg(target_os = "linux")]
+        PiiInternet = _SC_PII_INTERNET,
+        #[cfg(target_os = "linux")]
+        PiiInternetDgram = _SC_PII_INTERNET_DGRAM,
+        #[cfg(target_os = "linux")]
+        PiiInternetStream = _SC_PII_INTERNET_STREAM,
+        #[cfg(target_os = "linux")]
+        PiiOsi = _SC_PII_OSI,
+        #[cfg(target_os = "linux")]
+        PiiOsiClts = _SC_PII_OSI_CLTS,
+        #[cfg(target_os = "linux")]
+        PiiOsiCots = _SC_PII_OSI_COTS,
+        #[cfg(target_os = "linux")]
+        PiiOsiM = _SC_PII_OSI_M,
+        #[cfg(target_os = "linux")]
+        PiiSocket = _SC_PII_SOCKET,
+        #[cfg(target_os = "linux")]
+        PiiXti = _SC_PII_XTI,
+        #[cfg(target_os = "linux")]
+        Pipe = _SC_PIPE,
+        #[cfg(target_os = "linux")]
+        Poll = _SC_POLL,
+        PrioritizedIo = _SC_PRIORITIZED_IO,
+        PriorityScheduling = _SC_PRIORITY_SCHEDULING,
+        RawSockets = _SC_RAW_SOCKETS,
+        ReaderWriterLocks = _SC_READER_WRITER_LOCKS,
+        RealtimeSignals = _SC_REALTIME_SIGNALS,
+        Regexp = _SC_REGEXP,
+        #[cfg(target_os = "linux")]
+        RegexVersion = _SC_REGEX_VERSION,
+        ReDupMax = _SC_RE_DUP_MAX,
+        RtsigMax = _SC_RTSIG_MAX,
+        SavedIds = _SC_SAVED_IDS,
+        #[cfg(target_os = "linux")]
+        ScharMax = _SC_SCHAR_MAX,
+        #[cfg(target_os = "linux")]
+        ScharMin = _SC_SCHAR_MIN,
+        #[cfg(target_os = "linux")]
+        Select = _SC_SELECT,
+        Semaphores = _SC_SEMAPHORES,
+        SemNsemsMax = _SC_SEM_NSEMS_MAX,
+        SemValueMax = _SC_SEM_VALUE_MAX,
+        SharedMemoryObjects = _SC_SHARED_MEMORY_OBJECTS,
+        Shell = _SC_SHELL,
+        #[cfg(target_os = "linux")]
+        ShrtMax = _SC_SHRT_MAX,
+        #[cfg(target_os = "linux")]
+        ShrtMin = _SC_SHRT_MIN,
+        #[cfg(target_os = "linux")]
+        Signals = _SC_SIGNALS,
+        SigqueueMax = _SC_SIGQUEUE_MAX,
+        #[cfg(target_os = "linux")]
+        SingleProcess = _SC_SINGLE_PROCESS,
+        Spawn = _SC_SPAWN,
+        SpinLocks = _SC_SPIN_LOCKS,
+        SporadicServer = _SC_SPORADIC_SERVER,
+        #[cfg(target_os = "linux")]
+        SsizeMax = _SC_SSIZE_MAX,
+        SsReplMax = _SC_SS_REPL_MAX,
+        #[cfg(target_os = "linux")]
+        Streams = _SC_STREAMS,
+        StreamMax = _SC_STREAM_MAX,
+        SymloopMax = _SC_SYMLOOP_MAX,
+        SynchronizedIo = _SC_SYNCHRONIZED_IO,
+        #[cfg(target_os = "linux")]
+        SystemDatabase = _SC_SYSTEM_DATABASE,
+        #[cfg(target_os = "linux")]
+        SystemDatabaseR = _SC_SYSTEM_DATABASE_R,
+        Threads = _SC_THREADS,
+        ThreadAttrStackaddr = _SC_THREAD_ATTR_STACKADDR,
+        ThreadAttrStacksize = _SC_THREAD_ATTR_STACKSIZE,
+        ThreadCputime = _SC_THREAD_CPUTIME,
+        ThreadDestructorIterations = _SC_THREAD_DESTRUCTOR_ITERATIONS,
+        ThreadKeysMax = _SC_THREAD_KEYS_MAX,
+        ThreadPriorityScheduling = _SC_THREAD_PRIORITY_SCHEDULING,
+        ThreadPrioInherit = _SC_THREAD_PRIO_INHERIT,
+        ThreadPrioProtect = _SC_THREAD_PRIO_PROTECT,
+        ThreadProcessShared = _SC_THREAD_PROCESS_SHARED,
+        #[cfg(target_os = "linux")]
+        ThreadRobustPrioInherit = _SC_THREAD_ROBUST_PRIO_INHERIT,
+        #[cfg(target_os = "linux")]
+        ThreadRobustPrioProtect = _SC_THREAD_ROBUST_PRIO_PROTECT,
+        ThreadSafeFunctions = _SC_THREAD_SAFE_FUNCTIONS,
+        ThreadSporadicServer = _SC_THREAD_SPORADIC_SERVER,
+        ThreadStackMin = _SC_THREAD_STACK_MIN,
+        ThreadThreadsMax = _SC_THREAD_THREADS_MAX,
+        Timeouts = _SC_TIMEOUTS,
+        Timers = _SC_TIMERS,
+        TimerMax = _SC_TIMER_MAX,
+        Trace = _SC_TRACE,
+        TraceEventFilter = _SC_TRACE_EVENT_FILTER,
+        TraceEventNameMax = _SC_TRACE_EVENT_NAME_MAX,
+        TraceInherit = _SC_TRACE_INHERIT,
+        TraceLog = _SC_TRACE_LOG,
+        TraceNameMax = _SC_TRACE_NAME_MAX,
+        TraceSysMax = _SC_TRACE_SYS_MAX,
+        TraceUserEventMax = _SC_TRACE_USER_EVENT_MAX,
+        TtyNameMax = _SC_TTY_NAME_MAX,
+        TypedMemoryObjects = _SC_TYPED_MEMORY_OBJECTS,
+        TznameMax = _SC_TZNAME_MAX,
+        #[cfg(target_os = "linux")]
+        TIovMax = _SC_T_IOV_MAX,
+        #[cfg(target_os = "linux")]
+        UcharMax = _SC_UCHAR_MAX,
+        #[cfg(target_os = "linux")]
+        UintMax = _SC_UINT_MAX,
+        #[cfg(target_os = "linux")]
+        UioMaxiov = _SC_UIO_MAXIOV,
+        #[cfg(target_os = "linux")]
+        UlongMax = _SC_ULONG_MAX,
+        #[cfg(target_os = "linux")]
+        UserGroups = _SC_USER_GROUPS,
+        #[cfg(target_os = "linux")]
+        UserGroupsR = _SC_USER_GROUPS_R,
+        #[cfg(target_os = "linux")]
+        UshrtMax = _SC_USHRT_MAX,
+        V6Ilp32Off32 = _SC_V6_ILP32_OFF32,
+        V6Ilp32Offbig = _SC_V6_ILP32_OFFBIG,
+        V6Lp64Off64 = _SC_V6_LP64_OFF64,
+        V6LpbigOffbig = _SC_V6_LPBIG_OFFBIG,
+        #[cfg(target_os = "linux")]
+        V7Ilp32Off32 = _SC_V7_ILP32_OFF32,
+        #[cfg(target_os = "linux")]
+        V7Ilp32Offbig = _SC_V7_ILP32_OFFBIG,
+        #[cfg(target_os = "linux")]
+        V7Lp64Off64 = _SC_V7_LP64_OFF64,
+        #[cfg(target_os = "linux")]
+        V7LpbigOffbig = _SC_V7_LPBIG_OFFBIG,
+        Version = _SC_VERSION,
+        #[cfg(target_os = "linux")]
+        WordBit = _SC_WORD_BIT,
+        Xbs5Ilp32Off32 = _SC_XBS5_ILP32_OFF32,
+        Xbs5Ilp32Offbig = _SC_XBS5_ILP32_OFFBIG,
+        Xbs5Lp64Off64 = _SC_XBS5_LP64_OFF64,
+        Xbs5LpbigOffbig = _SC_XBS5_LPBIG_OFFBIG,
+        XopenCrypt = _SC_XOPEN_CRYPT,
+        XopenEnhI18n = _SC_XOPEN_ENH_I18N,
+        XopenLegacy = _SC_XOPEN_LEGACY,
+        XopenRealtime = _SC_XOPEN_REALTIME,
+        XopenRealtimeThreads = _SC_XOPEN_REALTIME_THREADS,
+        XopenShm = _SC_XOPEN_SHM,
+        XopenStreams = _SC_XOPEN_STREAMS,
+        XopenUnix = _SC_XOPEN_UNIX,
+        XopenVersion = _SC_XOPEN_VERSION,
+        XopenXcuVersion = _SC_XOPEN_XCU_VERSION,
+        #[cfg(target_os = "linux")]
+        XopenXpg2 = _SC_XOPEN_XPG2,
+        #[cfg(target_os = "linux")]
+        XopenXpg3 = _SC_XOPEN_XPG3,
+        #[cfg(target_os = "linux")]
+        XopenXpg4 = _SC_XOPEN_XPG4,
+    }
+}
+
+limit_names! {
+    /// names for `pathconf` and `fpathconf`
+    pub enum Pathconf {
+        Posix2Symlinks = _PC_2_SYMLINKS,
+        AllocSizeMin = _PC_ALLOC_SIZE_MIN,
+        AsyncIo = _PC_ASYNC_IO,
+        #[cfg(target_os = "macos")]
+        AuthOpaqueNp = _PC_AUTH_OPAQUE_NP,
+        #[cfg(target_os = "macos")]
+        CasePreserving = _PC_CASE_PRESERVING,
+        #[cfg(target_os = "macos")]
+        CaseSensitive = _PC_CASE_SENSITIVE,
+        ChownRestricted = _PC_CHOWN_RESTRICTED,
+        #[cfg(target_os = "macos")]
+        ExtendedSecurityNp = _PC_EXTENDED_SECURITY_NP,
+        Filesizebits = _PC_FILESIZEBITS,
+        LinkMax = _PC_LINK_MAX,
+        MaxCanon = _PC_MAX_CANON,
+        MaxInput = _PC_MAX_INPUT,
+        #[cfg(target_os = "macos")]
+        MinHoleSize = _PC_MIN_HOLE_SIZE,
+        #[cfg(target_os = "macos")]
+        NameCharsMax = _PC_NAME_CHARS_MAX,
+        NameMax = _PC_NAME_MAX,
+        NoTrunc = _PC_NO_TRUNC,
+        PathMax = _PC_PATH_MAX,
+        PipeBuf = _PC_PIPE_BUF,
+        PrioIo = _PC_PRIO_IO,
+        RecIncrXferSize = _PC_REC_INCR_XFER_SIZE,
+        RecMaxXferSize = _PC_REC_MAX_XFER_SIZE,
+        RecMinXferSize = _PC_REC_MIN_XFER_SIZE,
+        RecXferAlign = _PC_REC_XFER_ALIGN,
+        #[cfg(target_os = "linux")]
+        SockMaxbuf = _PC_SOCK_MAXBUF,
+        SymlinkMax = _PC_SYMLINK_MAX,
+        SyncIo = _PC_SYNC_IO,
+        Vdisable = _PC_VDISABLE,
+        #[cfg(target_os = "macos")]
+        XattrSizeBits = _PC_XATTR_SIZE_BITS,
+    }
+}
+
+/// value of a limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Value(c_long),
+    /// indeterminate: there is no limit, or it can't be told in advance. For the
+    /// options among the names (e.g. `Sysconf::Posix2Pbs`) the option isn't available
+    Unlimited,
+    /// the name isn't supported here (EINVAL)
+    Unsupported,
+}
+
+impl Limit {
+    /// the value, `None` if unlimited or unsupported
+    pub fn value(&self) -> Option<c_long> {
+        match *self {
+            Limit::Value(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Limit::Value(n) => write!(f, "{}", n),
+            Limit::Unlimited => write!(f, "unlimited"),
+            Limit::Unsupported => write!(f, "unsupported"),
+        }
+    }
+}
+
+// -1 and errno 0 means indeterminate, so errno has to be cleared before the call
+fn limit<F: FnOnce() -> c_long>(query: F) -> Result<Limit> {
+    set_errno(Errno(0));
+    match query() {
+        -1 => match errno().0 {
+            0 => Ok(Limit::Unlimited),
+            EINVAL => Ok(Limit::Unsupported),
+            e => Err(Error::from_raw_os_error(e)),
+        },
+        n => Ok(Limit::Value(n)),
+    }
+}
+
+/// sysconf(3)
+pub fn sysconf(name: Sysconf) -> Result<Limit> {
+    limit(|| unsafe { libc::sysconf(name.as_raw()) })
+}
+
+/// pathconf(3): the limit for the file system `path` is on
+pub fn pathconf<P: AsRef<Path>>(path: P, name: Pathconf) -> Result<Limit> {
+    let path = to_cstring(path.as_ref())?;
+    limit(|| unsafe { libc::pathconf(path.as_ptr(), name.as_raw()) })
+}
+
+/// fpathconf(3): the limit for the open file `fd`
+pub fn fpathconf<F: AsRawFd>(fd: &F, name: Pathconf) -> Result<Limit> {
+    limit(|| unsafe { libc::fpathconf(fd.as_raw_fd(), name.as_raw()) })
+}
+
+// Figure 2.17: a guess if the limit is indeterminate
+const OPEN_MAX_GUESS: usize = 256;
+// Figure 2.16: a guess if the limit is indeterminate
+const PATH_MAX_GUESS: usize = 1024;
+
+/// Figure 2.17: the maximum number of open files, a guess if sysconf can't tell
+pub fn open_max() -> usize {
+    match sysconf(Sysconf::OpenMax) {
+        Ok(Limit::Value(n)) if n > 0 => n as usize,
+        _ => OPEN_MAX_GUESS,
+    }
+}
+
+/// Figure 2.16: the size of a buffer for a pathname on the file system of `path`,
+/// including the terminating null byte. A guess if pathconf can't tell
+pub fn path_max<P: AsRef<Path>>(path: P) -> usize {
+    let max = match pathconf(path, Pathconf::PathMax) {
+        Ok(Limit::Value(n)) if n > 0 => n as usize,
+        _ => PATH_MAX_GUESS,
+    };
+    // before POSIX.1-2001 PATH_MAX might not include the null byte
+    match (sysconf(Sysconf::Version), sysconf(Sysconf::XopenVersion)) {
+        (Ok(Limit::Value(posix)), _) if posix >= 200112 => max,
+        (_, Ok(Limit::Value(xsi))) if xsi >= 4 => max,
+        _ => max + 1,
+    }
+}
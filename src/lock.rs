@@ -0,0 +1,256 @@
+//! Record locking, section 14.3
+//!
+//! `lock_reg` and `lock_test` are the functions of Figures 14.5 and 14.6. On top of them
+//! `Lock` describes a byte range to lock, and locking it returns a `LockGuard` which
+//! unlocks the range when dropped.
+//!
+//! POSIX record locks belong to the process: they aren't inherited by fork, and closing
+//! *any* descriptor of the file releases all locks the process holds on it. Linux also
+//! has open file description locks (`Lock::open_file`, F_OFD_SETLK and friends). These
+//! belong to the open file description: they are shared by duplicated and inherited
+//! descriptors, released when its last descriptor is closed and conflict with locks
+//! through another open(2) of the same file even within one process. The kernel only
+//! detects deadlocks (EDEADLK) between process locks.
+
+use libc::{c_int, c_short, flock, off_t, pid_t};
+use libc::{EACCES, EAGAIN, F_GETLK, F_RDLCK, F_SETLK, F_SETLKW, F_UNLCK, F_WRLCK, SEEK_CUR,
+           SEEK_SET};
+use libc::{fcntl, lseek};
+use std::io::{Error, Result};
+use std::marker::PhantomData;
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+use LibcResult;
+
+/// l_type of the lock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockType {
+    /// F_RDLCK: shared, the descriptor has to be open for reading
+    Read,
+    /// F_WRLCK: exclusive, the descriptor has to be open for writing
+    Write,
+    /// F_UNLCK
+    Unlock,
+}
+
+impl LockType {
+    fn as_raw(&self) -> c_short {
+        (match *self {
+            LockType::Read => F_RDLCK,
+            LockType::Write => F_WRLCK,
+            LockType::Unlock => F_UNLCK,
+        }) as c_short
+    }
+}
+
+fn to_flock(kind: LockType, offset: off_t, whence: c_int, len: off_t) -> flock {
+    let mut lock: flock = unsafe { mem::zeroed() };
+    lock.l_type = kind.as_raw();
+    lock.l_start = offset;
+    lock.l_whence = whence as c_short;
+    lock.l_len = len;
+    lock
+}
+
+/// Figure 14.5: fcntl(`cmd`) for `len` bytes at `offset` relative to `whence`, a `len`
+/// of 0 means up to the end of the file, however large it grows. `cmd` is F_SETLK or
+/// F_SETLKW (or on Linux F_OFD_SETLK/F_OFD_SETLKW).
+///
+/// Like in the book the error is whatever fcntl returned: F_SETLK fails with EACCES or
+/// EAGAIN if the range is locked by somebody else
+pub fn lock_reg<F: AsRawFd>(fd: &F,
+                            cmd: c_int,
+                            kind: LockType,
+                            offset: off_t,
+                            whence: c_int,
+                            len: off_t)
+                            -> Result<()> {
+    let mut lock = to_flock(kind, offset, whence, len);
+    unsafe { fcntl(fd.as_raw_fd(), cmd, &mut lock) }.check_not_negative()?;
+    Ok(())
+}
+
+fn get_lock<F: AsRawFd>(fd: &F,
+                        cmd: c_int,
+                        kind: LockType,
+                        offset: off_t,
+                        whence: c_int,
+                        len: off_t)
+                        -> Result<Option<pid_t>> {
+    let mut lock = to_flock(kind, offset, whence, len);
+    unsafe { fcntl(fd.as_raw_fd(), cmd, &mut lock) }.check_not_negative()?;
+    if lock.l_type == F_UNLCK as c_short {
+        Ok(None) // false, region isn't locked by another proc
+    } else {
+        Ok(Some(lock.l_pid)) // true, return pid of lock owner
+    }
+}
+
+/// Figure 14.6: the pid of a process whose lock would block a `kind` lock of the range,
+/// None if it could be locked. The process's own locks never block it
+pub fn lock_test<F: AsRawFd>(fd: &F,
+                             kind: LockType,
+                             offset: off_t,
+                             whence: c_int,
+                             len: off_t)
+                             -> Result<Option<pid_t>> {
+    get_lock(fd, F_GETLK, kind, offset, whence, len)
+}
+
+/// Byte range to lock, built with `read` or `write`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lock {
+    kind: LockType,
+    offset: off_t,
+    whence: c_int,
+    len: off_t,
+    ofd: bool,
+}
+
+impl Lock {
+    /// shared lock of `len` bytes at `offset` relative to `whence` (0 bytes: up to the
+    /// end of the file, however large it grows)
+    pub fn read(offset: off_t, whence: c_int, len: off_t) -> Lock {
+        Lock::new(LockType::Read, offset, whence, len)
+    }
+
+    /// exclusive lock, the range is given as for `read`
+    pub fn write(offset: off_t, whence: c_int, len: off_t) -> Lock {
+        Lock::new(LockType::Write, offset, whence, len)
+    }
+
+    fn new(kind: LockType, offset: off_t, whence: c_int, len: off_t) -> Lock {
+        Lock {
+            kind: kind,
+            offset: offset,
+            whence: whence,
+            len: len,
+            ofd: false,
+        }
+    }
+
+    /// make it an open file description lock instead of a process lock
+    #[cfg(target_os = "linux")]
+    pub fn open_file(mut self) -> Lock {
+        self.ofd = true;
+        self
+    }
+
+    pub fn kind(&self) -> LockType {
+        self.kind
+    }
+
+    // F_SETLK, F_SETLKW and F_GETLK or their OFD counterparts
+    #[cfg(target_os = "linux")]
+    fn cmds(&self) -> (c_int, c_int, c_int) {
+        if self.ofd {
+            (libc::F_OFD_SETLK, libc::F_OFD_SETLKW, libc::F_OFD_GETLK)
+        } else {
+            (F_SETLK, F_SETLKW, F_GETLK)
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn cmds(&self) -> (c_int, c_int, c_int) {
+        (F_SETLK, F_SETLKW, F_GETLK)
+    }
+
+    /// F_SETLK: lock without waiting, fails with EAGAIN (`ErrorKind::WouldBlock`) if
+    /// somebody else holds a conflicting lock
+    pub fn try_lock<'a, F: AsRawFd>(&self, fd: &'a F) -> Result<LockGuard<'a>> {
+        let (setlk, _, _) = self.cmds();
+        match self.set(fd, setlk) {
+            // POSIX allows both, don't make the caller check for two errors
+            Err(ref e) if e.raw_os_error() == Some(EACCES) => Err(Error::from_raw_os_error(EAGAIN)),
+            res => res,
+        }
+    }
+
+    /// F_SETLKW: wait until the range can be locked. Fails with EDEADLK if waiting
+    /// would deadlock with another process, and with `ErrorKind::Interrupted` when a
+    /// signal handler interrupts the wait, so an alarm can limit it
+    pub fn lock<'a, F: AsRawFd>(&self, fd: &'a F) -> Result<LockGuard<'a>> {
+        let (_, setlkw, _) = self.cmds();
+        self.set(fd, setlkw)
+    }
+
+    /// F_GETLK: the pid of a process holding a lock which blocks this one, None if it
+    /// could be locked. An open file description lock has no process, its pid is -1
+    pub fn test<F: AsRawFd>(&self, fd: &F) -> Result<Option<pid_t>> {
+        let (_, _, getlk) = self.cmds();
+        get_lock(fd, getlk, self.kind, self.offset, self.whence, self.len)
+    }
+
+    fn set<'a, F: AsRawFd>(&self, fd: &'a F, cmd: c_int) -> Result<LockGuard<'a>> {
+        // unlocking relative to the current offset would hit another range if the
+        // offset moves while the lock is held
+        let mut lock = *self;
+        if lock.whence == SEEK_CUR {
+            lock.offset += unsafe { lseek(fd.as_raw_fd(), 0, SEEK_CUR) }.check_not_negative()?;
+            lock.whence = SEEK_SET;
+        }
+        lock_reg(fd, cmd, lock.kind, lock.offset, lock.whence, lock.len)?;
+        Ok(LockGuard {
+            fd: fd.as_raw_fd(),
+            lock: lock,
+            _fd: PhantomData,
+        })
+    }
+}
+
+/// Figure 13.6: write lock all of `fd` without waiting. A daemon that must only run
+/// once locks its pid file, and a second instance fails with EAGAIN. Call `leak` on the
+/// guard to keep the lock until the process exits
+pub fn lockfile<'a, F: AsRawFd>(fd: &'a F) -> Result<LockGuard<'a>> {
+    Lock::write(0, SEEK_SET, 0).try_lock(fd)
+}
+
+/// Locked byte range, unlocked when dropped
+///
+/// The kernel doesn't know about guards: locks of the same owner (the process, or the
+/// open file description for `Lock::open_file`) on overlapping or adjacent ranges are
+/// merged into one. Dropping a guard unlocks its whole range, including bytes that
+/// another guard of the same owner still covers, so don't hold guards with overlapping
+/// ranges of one file.
+#[derive(Debug)]
+#[must_use]
+pub struct LockGuard<'a> {
+    fd: RawFd,
+    lock: Lock,
+    _fd: PhantomData<&'a ()>,
+}
+
+impl<'a> LockGuard<'a> {
+    pub fn lock(&self) -> &Lock {
+        &self.lock
+    }
+
+    /// unlock now, other than dropping the guard this reports errors
+    pub fn unlock(self) -> Result<()> {
+        let res = self.release();
+        mem::forget(self);
+        res
+    }
+
+    /// keep the lock until the descriptor is closed or the process exits
+    pub fn leak(self) {
+        mem::forget(self);
+    }
+
+    fn release(&self) -> Result<()> {
+        let (setlk, _, _) = self.lock.cmds();
+        let lock = &self.lock;
+        lock_reg(&self.fd,
+                 setlk,
+                 LockType::Unlock,
+                 lock.offset,
+                 lock.whence,
+                 lock.len)
+    }
+}
+
+impl<'a> Drop for LockGuard<'a> {
+    fn drop(&mut self) {
+        let _ = self.release();
+    }
+}
@@ -0,0 +1,268 @@
+//! Memory-mapped I/O, section 14.8
+//!
+//! `Mmap` is a read-only mapping, `MmapMut` a writable one. Both are unmapped when
+//! dropped and deref to `[u8]`. mmap(2) wants the file offset to be a multiple of the
+//! page size, here any offset works: the mapping starts at the page boundary below it
+//! and the slice starts at the requested offset.
+//!
+//! A mapping of length 0 is allowed (mmap fails with EINVAL for it), so an empty file
+//! doesn't need special treatment. A negative offset fails with EINVAL, as for mmap.
+
+use libc::{c_int, c_void, off_t, size_t};
+use libc::{EINVAL, MAP_ANON, MAP_PRIVATE, MAP_SHARED, MS_ASYNC, MS_SYNC, PROT_READ,
+           PROT_WRITE, _SC_PAGESIZE};
+use libc::{MADV_DONTNEED, MADV_NORMAL, MADV_RANDOM, MADV_SEQUENTIAL, MADV_WILLNEED};
+use libc::{madvise, mmap, mprotect, msync, munmap, sysconf};
+use std::io::{Error, Result};
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::AsRawFd;
+use std::{ptr, slice};
+use {LibcPtrResult, LibcResult};
+
+/// who sees the changes to a writable mapping
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sharing {
+    /// MAP_SHARED: changes go to the file and are visible to other processes
+    Shared,
+    /// MAP_PRIVATE: changes are copy on write and stay in this process
+    Private,
+}
+
+/// madvise(2) hints about the access pattern
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Advice {
+    Normal,
+    Random,
+    Sequential,
+    WillNeed,
+    DontNeed,
+}
+
+impl Advice {
+    fn as_raw(&self) -> c_int {
+        match *self {
+            Advice::Normal => MADV_NORMAL,
+            Advice::Random => MADV_RANDOM,
+            Advice::Sequential => MADV_SEQUENTIAL,
+            Advice::WillNeed => MADV_WILLNEED,
+            Advice::DontNeed => MADV_DONTNEED,
+        }
+    }
+}
+
+// the mapping itself: `ptr` is page aligned, the user visible part starts `delta`
+// bytes after it
+#[derive(Debug)]
+struct Mapping {
+    ptr: *mut u8,
+    delta: usize,
+    len: usize,
+}
+
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+fn page_size() -> usize {
+    unsafe { sysconf(_SC_PAGESIZE) as usize }
+}
+
+impl Mapping {
+    fn new(len: usize, prot: c_int, flags: c_int, fd: c_int, offset: off_t) -> Result<Mapping> {
+        if offset < 0 {
+            return Err(Error::from_raw_os_error(EINVAL));
+        }
+        let delta = if fd < 0 { 0 } else { offset as usize % page_size() };
+        if len == 0 {
+            return Ok(Mapping {
+                ptr: ptr::NonNull::dangling().as_ptr(),
+                delta: 0,
+                len: 0,
+            });
+        }
+        let ptr = unsafe {
+            mmap(ptr::null_mut(),
+                 len + delta,
+                 prot,
+                 flags,
+                 fd,
+                 offset - delta as off_t)
+        }.check_not_map_failed()?;
+        Ok(Mapping {
+            ptr: ptr as *mut u8,
+            delta: delta,
+            len: len,
+        })
+    }
+
+    fn data(&self) -> *mut u8 {
+        unsafe { self.ptr.add(self.delta) }
+    }
+
+    // the whole mapping, for the calls that want a page aligned address
+    fn raw(&self) -> (*mut c_void, size_t) {
+        (self.ptr as *mut c_void, self.len + self.delta)
+    }
+
+    fn advise(&self, advice: Advice) -> Result<()> {
+        if self.len > 0 {
+            let (addr, len) = self.raw();
+            unsafe { madvise(addr, len, advice.as_raw()) }.check_not_negative()?;
+        }
+        Ok(())
+    }
+
+    fn sync(&self, flags: c_int) -> Result<()> {
+        if self.len > 0 {
+            let (addr, len) = self.raw();
+            unsafe { msync(addr, len, flags) }.check_not_negative()?;
+        }
+        Ok(())
+    }
+
+    fn protect(&self, prot: c_int) -> Result<()> {
+        if self.len > 0 {
+            let (addr, len) = self.raw();
+            unsafe { mprotect(addr, len, prot) }.check_not_negative()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            let (addr, len) = self.raw();
+            unsafe {
+                munmap(addr, len);
+            }
+        }
+    }
+}
+
+/// Read-only memory mapping
+#[derive(Debug)]
+pub struct Mmap {
+    inner: Mapping,
+}
+
+impl Mmap {
+    /// map `len` bytes of `fd` starting at `offset`, `fd` has to be open for reading.
+    /// The mapping stays valid when `fd` is closed
+    pub fn map<F: AsRawFd>(fd: &F, offset: off_t, len: usize) -> Result<Mmap> {
+        let inner = Mapping::new(len, PROT_READ, MAP_SHARED, fd.as_raw_fd(), offset)?;
+        Ok(Mmap { inner: inner })
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.inner.data()
+    }
+
+    pub fn advise(&self, advice: Advice) -> Result<()> {
+        self.inner.advise(advice)
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.inner.data(), self.inner.len) }
+    }
+}
+
+/// Writable memory mapping
+#[derive(Debug)]
+pub struct MmapMut {
+    inner: Mapping,
+}
+
+impl MmapMut {
+    /// map `len` bytes of `fd` starting at `offset` for reading and writing. With
+    /// `Sharing::Shared` `fd` has to be open for reading and writing, and the file must
+    /// be large enough: accessing a page beyond its end raises SIGBUS
+    pub fn map<F: AsRawFd>(fd: &F, offset: off_t, len: usize, sharing: Sharing) -> Result<MmapMut> {
+        let inner = Mapping::new(len,
+                                 PROT_READ | PROT_WRITE,
+                                 MmapMut::flags(sharing),
+                                 fd.as_raw_fd(),
+                                 offset)?;
+        Ok(MmapMut { inner: inner })
+    }
+
+    /// zero filled memory not backed by a file, with `Sharing::Shared` it's shared
+    /// with children created by fork
+    pub fn anonymous(len: usize, sharing: Sharing) -> Result<MmapMut> {
+        let inner = Mapping::new(len,
+                                 PROT_READ | PROT_WRITE,
+                                 MmapMut::flags(sharing) | MAP_ANON,
+                                 -1,
+                                 0)?;
+        Ok(MmapMut { inner: inner })
+    }
+
+    fn flags(sharing: Sharing) -> c_int {
+        match sharing {
+            Sharing::Shared => MAP_SHARED,
+            Sharing::Private => MAP_PRIVATE,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.len == 0
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.inner.data()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.inner.data()
+    }
+
+    pub fn advise(&self, advice: Advice) -> Result<()> {
+        self.inner.advise(advice)
+    }
+
+    /// msync(MS_SYNC): write the changes of a shared file mapping to the file and
+    /// wait until it's done
+    pub fn flush(&self) -> Result<()> {
+        self.inner.sync(MS_SYNC)
+    }
+
+    /// msync(MS_ASYNC): schedule the write and return
+    pub fn flush_async(&self) -> Result<()> {
+        self.inner.sync(MS_ASYNC)
+    }
+
+    /// mprotect the mapping to PROT_READ
+    pub fn make_read_only(self) -> Result<Mmap> {
+        self.inner.protect(PROT_READ)?;
+        Ok(Mmap { inner: self.inner })
+    }
+}
+
+impl Deref for MmapMut {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.inner.data(), self.inner.len) }
+    }
+}
+
+impl DerefMut for MmapMut {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.inner.data(), self.inner.len) }
+    }
+}
@@ -0,0 +1,359 @@
+//! Message queues, section 15.7
+//!
+//! `MsgQueue` wraps the XSI message queues (msgget, msgsnd, msgrcv, msgctl), `Mq` the
+//! POSIX ones (mq_open, mq_send, mq_receive, mq_notify). macOS doesn't implement POSIX
+//! message queues, so `Mq` is Linux only.
+//!
+//! Messages are typed: everything implementing `Message` is sent as its raw bytes and
+//! a received message must have exactly the size of the requested type. The `*_bytes`
+//! methods send and receive untyped data.
+//!
+//! An XSI queue isn't owned by the process that created it, it exists until `remove` is
+//! called (or ipcrm(1) is run), so `MsgQueue` doesn't do anything when dropped.
+
+use libc::{c_int, c_long, c_void, key_t, mode_t};
+use libc::{ENOMSG, IPC_CREAT, IPC_EXCL, IPC_NOWAIT, IPC_PRIVATE, IPC_RMID};
+#[cfg(target_os = "linux")]
+use libc::{msgctl, msgget, msgrcv, msgsnd};
+#[cfg(target_os = "macos")]
+use my_libc::{msgctl, msgget, msgrcv, msgsnd};
+use cstr::to_cstring;
+use std::io::{Error, ErrorKind, Result};
+use std::mem::{size_of, MaybeUninit};
+use std::path::Path;
+use std::ptr;
+use LibcResult;
+
+/// Types that can be sent as their raw bytes
+///
+/// # Safety
+///
+/// The type has to be plain old data: no pointers, references or padding bytes, and
+/// every bit pattern of its size is a valid value. Received messages are made from
+/// whatever bytes the sender put on the queue.
+pub unsafe trait Message: Copy {}
+
+unsafe impl Message for u8 {}
+unsafe impl Message for i8 {}
+unsafe impl Message for u16 {}
+unsafe impl Message for i16 {}
+unsafe impl Message for u32 {}
+unsafe impl Message for i32 {}
+unsafe impl Message for u64 {}
+unsafe impl Message for i64 {}
+unsafe impl Message for usize {}
+unsafe impl Message for isize {}
+unsafe impl Message for f32 {}
+unsafe impl Message for f64 {}
+unsafe impl<T: Message, const N: usize> Message for [T; N] {}
+
+fn check_len<T: Message>(len: usize) -> Result<()> {
+    if len != size_of::<T>() {
+        return Err(Error::new(ErrorKind::InvalidData,
+                              format!("message has {} bytes, expected {}", len, size_of::<T>())));
+    }
+    Ok(())
+}
+
+// struct mymesg { long mtype; char mtext[]; } with room for a `T`, packed so the text
+// starts right after mtype whatever the alignment of `T`
+#[repr(C, packed)]
+#[derive(Clone, Copy)]
+struct Mymesg<T> {
+    mtype: c_long,
+    mtext: T,
+}
+
+/// ftok(3), derive a key from an existing `path` and the low 8 bits of `id`
+pub fn ftok<P: AsRef<Path>>(path: P, id: c_int) -> Result<key_t> {
+    let path = to_cstring(path.as_ref())?;
+    unsafe { libc::ftok(path.as_ptr(), id) }.check_not_negative()
+}
+
+/// XSI message queue
+#[derive(Debug)]
+pub struct MsgQueue {
+    id: c_int,
+}
+
+impl MsgQueue {
+    /// create a new queue for `key`, fails with EEXIST if there's already one
+    pub fn create(key: key_t, mode: mode_t) -> Result<MsgQueue> {
+        MsgQueue::get(key, IPC_CREAT | IPC_EXCL | mode as c_int)
+    }
+
+    /// open the existing queue for `key`
+    pub fn open(key: key_t) -> Result<MsgQueue> {
+        MsgQueue::get(key, 0)
+    }
+
+    /// create a queue with IPC_PRIVATE, only reachable through `id` (or a fork)
+    pub fn private(mode: mode_t) -> Result<MsgQueue> {
+        MsgQueue::get(IPC_PRIVATE, mode as c_int)
+    }
+
+    /// a queue by its identifier, e.g. from `id` of another process
+    pub fn from_id(id: c_int) -> MsgQueue {
+        MsgQueue { id: id }
+    }
+
+    fn get(key: key_t, flags: c_int) -> Result<MsgQueue> {
+        let id = unsafe { msgget(key, flags) }.check_not_negative()?;
+        Ok(MsgQueue { id: id })
+    }
+
+    pub fn id(&self) -> c_int {
+        self.id
+    }
+
+    /// send `msg` with the type `mtype` (which has to be > 0), blocks while the queue is full
+    pub fn send<T: Message>(&self, mtype: c_long, msg: &T) -> Result<()> {
+        self.send_msg(mtype, msg, 0)
+    }
+
+    /// like `send`, but fails with `ErrorKind::WouldBlock` if the queue is full
+    pub fn try_send<T: Message>(&self, mtype: c_long, msg: &T) -> Result<()> {
+        self.send_msg(mtype, msg, IPC_NOWAIT)
+    }
+
+    /// receive a message, `mtype` selects which one:
+    ///
+    /// - 0: the first message on the queue
+    /// - > 0: the first message of this type
+    /// - < 0: the first message with the lowest type <= |mtype|
+    ///
+    /// returns the type and the message, blocks until there is one. A message longer
+    /// than `T` fails with E2BIG and stays on the queue, a shorter one fails with
+    /// `ErrorKind::InvalidData` and is lost: msgrcv already removed it (use `recv_bytes`
+    /// if the queue may hold messages of other sizes)
+    pub fn recv<T: Message>(&self, mtype: c_long) -> Result<(c_long, T)> {
+        self.recv_msg(mtype, 0)
+    }
+
+    /// like `recv`, but `None` if there's no matching message (ENOMSG)
+    pub fn try_recv<T: Message>(&self, mtype: c_long) -> Result<Option<(c_long, T)>> {
+        match self.recv_msg(mtype, IPC_NOWAIT) {
+            Ok(msg) => Ok(Some(msg)),
+            Err(ref e) if e.raw_os_error() == Some(ENOMSG) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn send_bytes(&self, mtype: c_long, data: &[u8]) -> Result<()> {
+        let mut msg = Vec::with_capacity(size_of::<c_long>() + data.len());
+        msg.extend_from_slice(&mtype.to_ne_bytes());
+        msg.extend_from_slice(data);
+        self.snd(msg.as_ptr() as *const c_void, data.len(), 0)
+    }
+
+    /// receive into `buf`, returns the type and the message length. A message
+    /// longer than `buf` fails with E2BIG and stays on the queue
+    pub fn recv_bytes(&self, mtype: c_long, buf: &mut [u8]) -> Result<(c_long, usize)> {
+        let mut msg = vec![0u8; size_of::<c_long>() + buf.len()];
+        let n = self.rcv(msg.as_mut_ptr() as *mut c_void, buf.len(), mtype, 0)?;
+        let mtype = unsafe { ptr::read_unaligned(msg.as_ptr() as *const c_long) };
+        buf[..n].copy_from_slice(&msg[size_of::<c_long>()..size_of::<c_long>() + n]);
+        Ok((mtype, n))
+    }
+
+    // the typed messages go through a `Mymesg` on the stack, without allocating
+    fn send_msg<T: Message>(&self, mtype: c_long, msg: &T, flags: c_int) -> Result<()> {
+        let msg = Mymesg {
+            mtype: mtype,
+            mtext: *msg,
+        };
+        self.snd(&msg as *const Mymesg<T> as *const c_void, size_of::<T>(), flags)
+    }
+
+    fn recv_msg<T: Message>(&self, mtype: c_long, flags: c_int) -> Result<(c_long, T)> {
+        let mut msg = MaybeUninit::<Mymesg<T>>::uninit();
+        let n = self.rcv(msg.as_mut_ptr() as *mut c_void, size_of::<T>(), mtype, flags)?;
+        check_len::<T>(n)?;
+        // msgrcv filled in mtype and all bytes of mtext
+        let msg = unsafe { msg.assume_init() };
+        Ok((msg.mtype, msg.mtext))
+    }
+
+    // `msg` points to a mymesg with `len` bytes of text
+    fn snd(&self, msg: *const c_void, len: usize, flags: c_int) -> Result<()> {
+        loop {
+            match unsafe { msgsnd(self.id, msg, len, flags) }.check_not_negative() {
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                res => return res.map(|_| ()),
+            }
+        }
+    }
+
+    // `msg` points to a mymesg with room for `len` bytes of text, returns the length
+    fn rcv(&self, msg: *mut c_void, len: usize, mtype: c_long, flags: c_int) -> Result<usize> {
+        loop {
+            match unsafe { msgrcv(self.id, msg, len, mtype, flags) }.check_not_negative() {
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                res => return res.map(|n| n as usize),
+            }
+        }
+    }
+
+    /// msgctl(IPC_STAT)
+    #[cfg(target_os = "linux")]
+    pub fn stat(&self) -> Result<libc::msqid_ds> {
+        let mut buf: libc::msqid_ds = unsafe { ::std::mem::zeroed() };
+        unsafe { msgctl(self.id, libc::IPC_STAT, &mut buf) }.check_not_negative()?;
+        Ok(buf)
+    }
+
+    /// msgctl(IPC_RMID), removes the queue and wakes up everybody blocked on it
+    pub fn remove(self) -> Result<()> {
+        unsafe { msgctl(self.id, IPC_RMID, ptr::null_mut()) }.check_not_negative()?;
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::posix::Mq;
+
+#[cfg(target_os = "linux")]
+mod posix {
+    use super::{check_len, Message};
+    use libc::{c_char, c_int, c_uint, mode_t, mq_attr, mqd_t, sigevent};
+    use libc::{O_CREAT, O_EXCL, O_RDWR, SIGEV_SIGNAL};
+    use libc::{mq_close, mq_getattr, mq_notify, mq_open, mq_receive, mq_send, mq_unlink};
+    use cstr::to_cstring;
+    use std::io::{ErrorKind, Result};
+    use std::mem::{size_of, zeroed, MaybeUninit};
+    use std::{ptr, slice};
+    use LibcResult;
+
+    fn as_bytes<T: Message>(msg: &T) -> &[u8] {
+        unsafe { slice::from_raw_parts(msg as *const T as *const u8, size_of::<T>()) }
+    }
+
+    fn from_bytes<T: Message>(buf: &[u8]) -> Result<T> {
+        check_len::<T>(buf.len())?;
+        Ok(unsafe { ptr::read_unaligned(buf.as_ptr() as *const T) })
+    }
+
+    /// POSIX message queue, closed when dropped. Names start with a slash and contain
+    /// no other slashes, e.g. "/apue"
+    #[derive(Debug)]
+    pub struct Mq {
+        mqd: mqd_t,
+        // mq_msgsize, the buffer size mq_receive insists on
+        msgsize: usize,
+    }
+
+    impl Mq {
+        /// create a new queue for at most `maxmsg` messages of at most `msgsize` bytes,
+        /// fails with EEXIST if the name is taken
+        pub fn create(name: &str, mode: mode_t, maxmsg: usize, msgsize: usize) -> Result<Mq> {
+            let name = to_cstring(name)?;
+            let mut attr: mq_attr = unsafe { zeroed() };
+            attr.mq_maxmsg = maxmsg as _;
+            attr.mq_msgsize = msgsize as _;
+            let mqd = unsafe {
+                mq_open(name.as_ptr(), O_RDWR | O_CREAT | O_EXCL, mode as c_uint, &attr)
+            }.check_not_negative()?;
+            Ok(Mq {
+                mqd: mqd,
+                msgsize: msgsize,
+            })
+        }
+
+        /// open an existing queue for reading and writing
+        pub fn open(name: &str) -> Result<Mq> {
+            let name = to_cstring(name)?;
+            let mqd = unsafe { mq_open(name.as_ptr(), O_RDWR) }.check_not_negative()?;
+            let mut mq = Mq {
+                mqd: mqd,
+                msgsize: 0,
+            };
+            mq.msgsize = mq.attr()?.mq_msgsize as usize;
+            Ok(mq)
+        }
+
+        /// remove the name, the queue is destroyed once nobody has it open any more
+        pub fn unlink(name: &str) -> Result<()> {
+            let name = to_cstring(name)?;
+            unsafe { mq_unlink(name.as_ptr()) }.check_not_negative()?;
+            Ok(())
+        }
+
+        pub fn attr(&self) -> Result<mq_attr> {
+            let mut attr: mq_attr = unsafe { zeroed() };
+            unsafe { mq_getattr(self.mqd, &mut attr) }.check_not_negative()?;
+            Ok(attr)
+        }
+
+        /// send `msg` with priority `prio`, blocks while the queue is full
+        pub fn send<T: Message>(&self, msg: &T, prio: u32) -> Result<()> {
+            self.send_bytes(as_bytes(msg), prio)
+        }
+
+        /// receive the oldest message with the highest priority, returns it and its priority
+        pub fn recv<T: Message>(&self) -> Result<(T, u32)> {
+            if self.msgsize > size_of::<T>() {
+                // mq_receive insists on a buffer for the largest possible message
+                let mut buf = vec![0; self.msgsize];
+                let (n, prio) = self.recv_bytes(&mut buf)?;
+                return Ok((from_bytes(&buf[..n])?, prio));
+            }
+            let mut msg = MaybeUninit::<T>::uninit();
+            let buf = unsafe {
+                slice::from_raw_parts_mut(msg.as_mut_ptr() as *mut u8, size_of::<T>())
+            };
+            let (n, prio) = self.recv_bytes(buf)?;
+            check_len::<T>(n)?;
+            Ok((unsafe { msg.assume_init() }, prio))
+        }
+
+        pub fn send_bytes(&self, data: &[u8], prio: u32) -> Result<()> {
+            loop {
+                match unsafe {
+                    mq_send(self.mqd, data.as_ptr() as *const c_char, data.len(), prio)
+                }.check_not_negative() {
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                    res => return res.map(|_| ()),
+                }
+            }
+        }
+
+        /// receive into `buf`, returns the message length and priority. `buf` must be
+        /// at least `mq_msgsize` bytes, otherwise it fails with EMSGSIZE
+        pub fn recv_bytes(&self, buf: &mut [u8]) -> Result<(usize, u32)> {
+            let mut prio = 0;
+            loop {
+                match unsafe {
+                    mq_receive(self.mqd, buf.as_mut_ptr() as *mut c_char, buf.len(), &mut prio)
+                }.check_not_negative() {
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                    res => return res.map(|n| (n as usize, prio)),
+                }
+            }
+        }
+
+        /// mq_notify: send `signo` to this process when a message arrives on the empty
+        /// queue. Only one process can be registered, the registration is removed after
+        /// the notification
+        pub fn notify_signal(&self, signo: c_int) -> Result<()> {
+            let mut sev: sigevent = unsafe { zeroed() };
+            sev.sigev_notify = SIGEV_SIGNAL;
+            sev.sigev_signo = signo;
+            unsafe { mq_notify(self.mqd, &sev) }.check_not_negative()?;
+            Ok(())
+        }
+
+        /// remove the registration of `notify_signal`
+        pub fn notify_cancel(&self) -> Result<()> {
+            unsafe { mq_notify(self.mqd, ptr::null()) }.check_not_negative()?;
+            Ok(())
+        }
+    }
+
+    impl Drop for Mq {
+        fn drop(&mut self) {
+            unsafe {
+                mq_close(self.mqd);
+            }
+        }
+    }
+}
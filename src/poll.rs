@@ -0,0 +1,353 @@
+//! I/O multiplexing, section 14.4
+//!
+//! Register descriptors with the events they're interested in, then `wait` until some
+//! of them are ready. Three implementations of the `Poller` trait:
+//!
+//! - `Select` (Figure 14.16): the fd_sets are rebuilt for every call, the descriptors
+//!   have to be below FD_SETSIZE
+//! - `Poll` (Figure 14.17): an array of pollfd
+//! - `Epoll` (epoll(7), Linux only): the kernel keeps the interest list, a wait only
+//!   costs something for the descriptors that are ready
+//!
+//! All are level triggered: a descriptor is reported as long as it's ready. Readiness
+//! only means a read or write won't block for *some* amount of data, so the descriptors
+//! should be nonblocking (`apue::fd::set_nonblocking`). Like `Lock::lock` a wait fails
+//! with `ErrorKind::Interrupted` when a signal handler interrupts it.
+
+use libc::{c_int, c_short, fd_set, pollfd, timeval};
+use libc::{EBADF, EEXIST, EINVAL, ENOENT, FD_SETSIZE, POLLERR, POLLHUP, POLLIN, POLLNVAL,
+           POLLOUT};
+use libc::{poll, select, FD_ISSET, FD_SET, FD_ZERO};
+use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::RawFd;
+use std::time::Duration;
+use std::{cmp, mem, ptr};
+use LibcResult;
+
+/// events a descriptor is registered for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interest {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Interest {
+    fn read(&self) -> bool {
+        *self != Interest::Write
+    }
+
+    fn write(&self) -> bool {
+        *self != Interest::Read
+    }
+}
+
+/// readiness of one descriptor
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    pub fd: RawFd,
+    /// read won't block: there is data, end of file or an error to report
+    pub readable: bool,
+    /// write won't block for at least one byte
+    pub writable: bool,
+    /// POLLHUP: the other end is gone, `select` reports this as readable
+    pub hangup: bool,
+    /// POLLERR or POLLNVAL, `select` fails with EBADF for a closed descriptor instead
+    pub error: bool,
+}
+
+pub trait Poller {
+    /// start watching `fd`, fails with EEXIST if it's already registered
+    fn add(&mut self, fd: RawFd, interest: Interest) -> Result<()>;
+    /// change what a registered `fd` is watched for
+    fn modify(&mut self, fd: RawFd, interest: Interest) -> Result<()>;
+    /// stop watching `fd`, has to be done before it's closed
+    fn remove(&mut self, fd: RawFd) -> Result<()>;
+    /// wait up to `timeout` (None: forever) until registered descriptors are ready and
+    /// return them, an empty vector means the timeout expired
+    fn wait(&mut self, timeout: Option<Duration>) -> Result<Vec<Event>>;
+}
+
+fn not_registered() -> Error {
+    Error::from_raw_os_error(ENOENT)
+}
+
+/// Poller using select(2)
+#[derive(Debug, Default)]
+pub struct Select {
+    fds: Vec<(RawFd, Interest)>,
+}
+
+impl Select {
+    pub fn new() -> Select {
+        Select { fds: Vec::new() }
+    }
+
+    fn position(&self, fd: RawFd) -> Option<usize> {
+        self.fds.iter().position(|&(i, _)| i == fd)
+    }
+}
+
+impl Poller for Select {
+    fn add(&mut self, fd: RawFd, interest: Interest) -> Result<()> {
+        if fd < 0 || fd as usize >= FD_SETSIZE {
+            return Err(Error::from_raw_os_error(EINVAL));
+        }
+        if self.position(fd).is_some() {
+            return Err(Error::from_raw_os_error(EEXIST));
+        }
+        self.fds.push((fd, interest));
+        Ok(())
+    }
+
+    fn modify(&mut self, fd: RawFd, interest: Interest) -> Result<()> {
+        let i = self.position(fd).ok_or_else(not_registered)?;
+        self.fds[i].1 = interest;
+        Ok(())
+    }
+
+    fn remove(&mut self, fd: RawFd) -> Result<()> {
+        let i = self.position(fd).ok_or_else(not_registered)?;
+        self.fds.remove(i);
+        Ok(())
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> Result<Vec<Event>> {
+        let mut readset: fd_set = unsafe { mem::zeroed() };
+        let mut writeset: fd_set = unsafe { mem::zeroed() };
+        unsafe {
+            FD_ZERO(&mut readset);
+            FD_ZERO(&mut writeset);
+        }
+        let mut maxfd = -1;
+        for &(fd, interest) in &self.fds {
+            if interest.read() {
+                unsafe { FD_SET(fd, &mut readset) };
+            }
+            if interest.write() {
+                unsafe { FD_SET(fd, &mut writeset) };
+            }
+            maxfd = cmp::max(maxfd, fd);
+        }
+        let mut tv = timeout.map(|t| {
+            timeval {
+                tv_sec: t.as_secs() as _,
+                tv_usec: t.subsec_micros() as _,
+            }
+        });
+        let tvptr = tv.as_mut().map_or(ptr::null_mut(), |tv| tv as *mut timeval);
+        unsafe { select(maxfd + 1, &mut readset, &mut writeset, ptr::null_mut(), tvptr) }
+            .check_not_negative()?;
+        let mut events = Vec::new();
+        for &(fd, _) in &self.fds {
+            let readable = unsafe { FD_ISSET(fd, &readset) };
+            let writable = unsafe { FD_ISSET(fd, &writeset) };
+            if readable || writable {
+                events.push(Event {
+                    fd: fd,
+                    readable: readable,
+                    writable: writable,
+                    hangup: false,
+                    error: false,
+                });
+            }
+        }
+        Ok(events)
+    }
+}
+
+/// Poller using poll(2)
+#[derive(Debug, Default)]
+pub struct Poll {
+    fds: Vec<pollfd>,
+}
+
+impl Poll {
+    pub fn new() -> Poll {
+        Poll { fds: Vec::new() }
+    }
+
+    fn position(&self, fd: RawFd) -> Option<usize> {
+        self.fds.iter().position(|pfd| pfd.fd == fd)
+    }
+
+    fn events(interest: Interest) -> c_short {
+        let mut events = 0;
+        if interest.read() {
+            events |= POLLIN;
+        }
+        if interest.write() {
+            events |= POLLOUT;
+        }
+        events
+    }
+}
+
+// milliseconds for poll and epoll_wait, rounded up so a short timeout doesn't
+// turn into a busy loop
+fn timeout_ms(timeout: Option<Duration>) -> c_int {
+    match timeout {
+        None => -1,
+        Some(t) => {
+            let ms = t.as_secs() * 1000 + (t.subsec_nanos() as u64).div_ceil(1_000_000);
+            cmp::min(ms, c_int::MAX as u64) as c_int
+        }
+    }
+}
+
+impl Poller for Poll {
+    fn add(&mut self, fd: RawFd, interest: Interest) -> Result<()> {
+        if fd < 0 {
+            return Err(Error::from_raw_os_error(EBADF));
+        }
+        if self.position(fd).is_some() {
+            return Err(Error::from_raw_os_error(EEXIST));
+        }
+        self.fds.push(pollfd {
+            fd: fd,
+            events: Poll::events(interest),
+            revents: 0,
+        });
+        Ok(())
+    }
+
+    fn modify(&mut self, fd: RawFd, interest: Interest) -> Result<()> {
+        let i = self.position(fd).ok_or_else(not_registered)?;
+        self.fds[i].events = Poll::events(interest);
+        Ok(())
+    }
+
+    fn remove(&mut self, fd: RawFd) -> Result<()> {
+        let i = self.position(fd).ok_or_else(not_registered)?;
+        self.fds.remove(i);
+        Ok(())
+    }
+
+    fn wait(&mut self, timeout: Option<Duration>) -> Result<Vec<Event>> {
+        let n = unsafe { poll(self.fds.as_mut_ptr(), self.fds.len() as _, timeout_ms(timeout)) }
+            .check_not_negative()?;
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(self.fds
+            .iter()
+            .filter(|pfd| pfd.revents != 0)
+            .map(|pfd| {
+                Event {
+                    fd: pfd.fd,
+                    readable: pfd.revents & POLLIN != 0,
+                    writable: pfd.revents & POLLOUT != 0,
+                    hangup: pfd.revents & POLLHUP != 0,
+                    error: pfd.revents & (POLLERR | POLLNVAL) != 0,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use self::epoll::Epoll;
+
+#[cfg(target_os = "linux")]
+mod epoll {
+    use super::{timeout_ms, Event, Interest, Poller};
+    use fd::Fd;
+    use libc::{c_int, epoll_event};
+    use libc::{EPOLLERR, EPOLLHUP, EPOLLIN, EPOLLOUT, EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL,
+               EPOLL_CTL_MOD};
+    use libc::{epoll_create1, epoll_ctl, epoll_wait};
+    use std::io::Result;
+    use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+    use std::ptr;
+    use std::time::Duration;
+    use LibcResult;
+
+    /// Poller using epoll(7), the epoll instance is closed when dropped. `add` fails with
+    /// EPERM for regular files and directories, which select and poll always report ready
+    #[derive(Debug)]
+    pub struct Epoll {
+        epfd: Fd,
+        // registered descriptors, to size the buffer for epoll_wait
+        count: usize,
+    }
+
+    impl Epoll {
+        pub fn new() -> Result<Epoll> {
+            let epfd = unsafe { epoll_create1(EPOLL_CLOEXEC) }.check_not_negative()?;
+            Ok(Epoll {
+                epfd: unsafe { Fd::from_raw_fd(epfd) },
+                count: 0,
+            })
+        }
+
+        fn ctl(&self, op: c_int, fd: RawFd, interest: Interest) -> Result<()> {
+            let mut events = 0;
+            if interest.read() {
+                events |= EPOLLIN;
+            }
+            if interest.write() {
+                events |= EPOLLOUT;
+            }
+            let mut event = epoll_event {
+                events: events as u32,
+                u64: fd as u64,
+            };
+            unsafe { epoll_ctl(self.epfd.as_raw_fd(), op, fd, &mut event) }.check_not_negative()?;
+            Ok(())
+        }
+    }
+
+    impl Poller for Epoll {
+        fn add(&mut self, fd: RawFd, interest: Interest) -> Result<()> {
+            self.ctl(EPOLL_CTL_ADD, fd, interest)?;
+            self.count += 1;
+            Ok(())
+        }
+
+        fn modify(&mut self, fd: RawFd, interest: Interest) -> Result<()> {
+            self.ctl(EPOLL_CTL_MOD, fd, interest)
+        }
+
+        fn remove(&mut self, fd: RawFd) -> Result<()> {
+            unsafe { epoll_ctl(self.epfd.as_raw_fd(), EPOLL_CTL_DEL, fd, ptr::null_mut()) }
+                .check_not_negative()?;
+            self.count -= 1;
+            Ok(())
+        }
+
+        fn wait(&mut self, timeout: Option<Duration>) -> Result<Vec<Event>> {
+            let mut buf = vec![epoll_event { events: 0, u64: 0 }; ::std::cmp::max(self.count, 1)];
+            let n = unsafe {
+                epoll_wait(self.epfd.as_raw_fd(),
+                           buf.as_mut_ptr(),
+                           buf.len() as c_int,
+                           timeout_ms(timeout))
+            }.check_not_negative()?;
+            Ok(buf[..n as usize]
+                .iter()
+                .map(|ev| {
+                    let events = ev.events as c_int;
+                    Event {
+                        fd: ev.u64 as RawFd,
+                        readable: events & EPOLLIN != 0,
+                        writable: events & EPOLLOUT != 0,
+                        hangup: events & EPOLLHUP != 0,
+                        error: events & EPOLLERR != 0,
+                    }
+                })
+                .collect())
+        }
+    }
+}
+
+/// pick the implementation by name ("select", "poll" or on Linux "epoll"), for the
+/// examples
+pub fn by_name(name: &str) -> Result<Box<dyn Poller>> {
+    match name {
+        "select" => Ok(Box::new(Select::new())),
+        "poll" => Ok(Box::new(Poll::new())),
+        #[cfg(target_os = "linux")]
+        "epoll" => Ok(Box::new(Epoll::new()?)),
+        _ => Err(Error::new(ErrorKind::InvalidInput, format!("unknown poller {}", name))),
+    }
+}
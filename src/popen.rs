@@ -0,0 +1,143 @@
+//! Figure 15.12: The popen and pclose functions
+//!
+//! Like in the book the pid of every child is stored in a table indexed by the
+//! descriptor of its pipe, so `pclose` knows which child to wait for. The book closes
+//! the descriptors of the table in every new child so it doesn't inherit the pipes of
+//! earlier popen calls. With threads that's not enough: another thread could fork between
+//! `pipe` and the table update. Therefore the pipes are created close-on-exec (`fd::pipe`)
+//! and the table stays locked from `pipe` until `fork` returned.
+
+use cstr::CStrArgs;
+use fd::{self, Fd};
+use libc::{c_char, pid_t};
+use libc::{EINTR, SIGPIPE, SIG_DFL, STDIN_FILENO, STDOUT_FILENO};
+use libc::{_exit, close, dup2, fork, waitpid};
+use my_libc::execl;
+use std::collections::HashMap;
+use std::io::{self, Error, Result};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::Mutex;
+use wait::WaitStatus;
+use LibcResult;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// read the standard output of the command
+    Read,
+    /// write to the standard input of the command
+    Write,
+}
+
+// pid of the child for every descriptor returned by popen (the book's childpid array)
+static CHILDPID: Mutex<Option<HashMap<RawFd, pid_t>>> = Mutex::new(None);
+
+/// Pipe to or from a command started by `popen`
+#[derive(Debug)]
+pub struct Popen {
+    fd: Option<Fd>,
+}
+
+/// run `cmdstring` with `/bin/sh -c` connected to a pipe
+pub fn popen(cmdstring: &str, mode: Mode) -> Result<Popen> {
+    let argv = CStrArgs::argv(["sh", "-c", cmdstring])?;
+
+    let mut table = CHILDPID.lock().unwrap();
+    let childpid = table.get_or_insert_with(HashMap::new);
+    let (readfd, writefd) = fd::pipe()?;
+    let (parent_end, child_end, child_fileno) = match mode {
+        Mode::Read => (readfd, writefd, STDOUT_FILENO),
+        Mode::Write => (writefd, readfd, STDIN_FILENO),
+    };
+
+    let pid = unsafe { fork() }.check_not_negative()?;
+    if pid == 0 {
+        let child_end = child_end.as_raw_fd();
+        unsafe {
+            // dup2 clears close-on-exec on the new descriptor
+            if child_end != child_fileno {
+                dup2(child_end, child_fileno);
+            } else {
+                libc::fcntl(child_end, libc::F_SETFD, 0);
+            }
+            // close all descriptors in childpid, already close-on-exec on Linux
+            for fd in childpid.keys() {
+                close(*fd);
+            }
+            // Rust ignores SIGPIPE, the command would inherit that
+            libc::signal(SIGPIPE, SIG_DFL);
+            execl(b"/bin/sh\0".as_ptr() as *const c_char,
+                  argv.ptr(0),
+                  argv.ptr(1),
+                  argv.ptr(2),
+                  argv.ptr(3));
+            _exit(127);
+        }
+    }
+
+    // parent continues, child_end is closed when it goes out of scope
+    childpid.insert(parent_end.as_raw_fd(), pid);
+    Ok(Popen { fd: Some(parent_end) })
+}
+
+/// close the pipe and wait for the command to terminate
+pub fn pclose(mut stream: Popen) -> Result<WaitStatus> {
+    stream.close()
+}
+
+impl Popen {
+    fn close(&mut self) -> Result<WaitStatus> {
+        let fd = match self.fd.take() {
+            Some(fd) => fd,
+            None => return Err(Error::from_raw_os_error(libc::EINVAL)),
+        };
+        let pid = {
+            let mut table = CHILDPID.lock().unwrap();
+            match table.as_mut().and_then(|t| t.remove(&fd.as_raw_fd())) {
+                Some(pid) => pid,
+                // fp wasn't opened by popen()
+                None => return Err(Error::from_raw_os_error(libc::EINVAL)),
+            }
+        };
+        drop(fd);
+
+        let mut stat = 0;
+        while unsafe { waitpid(pid, &mut stat, 0) } < 0 {
+            let err = Error::last_os_error();
+            if err.raw_os_error() != Some(EINTR) {
+                return Err(err); // error other than EINTR from waitpid()
+            }
+        }
+        Ok(WaitStatus::from_raw(stat))
+    }
+}
+
+impl io::Read for Popen {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        self.fd.as_ref().unwrap().read(buf)
+    }
+}
+
+impl io::Write for Popen {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.fd.as_ref().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for Popen {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_ref().unwrap().as_raw_fd()
+    }
+}
+
+impl Drop for Popen {
+    /// same as pclose, but the exit status is lost
+    fn drop(&mut self) {
+        if self.fd.is_some() {
+            let _ = self.close();
+        }
+    }
+}
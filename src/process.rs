@@ -0,0 +1,397 @@
+//! Spawning child processes
+//!
+//! `Command` follows the signal discipline of `system2` (Figure 10.28): while children
+//! are running the parent ignores SIGINT and SIGQUIT and blocks SIGCHLD, the child gets the
+//! original dispositions and mask back before it execs. SIGPIPE, which Rust ignores, is set
+//! back to its default action. In contrast to `system2` the program
+//! is executed directly (no `/bin/sh -c`) and the child's environment can be set up
+//! beforehand.
+//!
+//! Everything the child needs is allocated before `fork`, between `fork` and `exec` the
+//! child only calls async-signal-safe functions. If one of them fails the errno is sent
+//! back over a close-on-exec pipe, so `spawn` reports e.g. a missing program as error.
+
+use libc::{c_int, mode_t, pid_t, rlim_t, rlimit, sigaction};
+use libc::{ECHILD, EINTR, FD_CLOEXEC, F_GETFD, F_SETFD, SIGCHLD, SIGINT, SIGPIPE, SIGQUIT,
+           SIG_DFL, SIG_ERR, SIG_SETMASK, WNOHANG};
+use libc::{_exit, chdir, dup2, fcntl, fork, read, setpgid, setsid, umask, waitpid, write};
+use cstr::{to_cstring, CStrArgs};
+use fd;
+use my_libc::{environ, sigprocmask};
+use signals::{Handler, SavedAction, SigAction, SigSet};
+use std::collections::BTreeMap;
+use std::ffi::{CString, OsStr, OsString};
+use std::io::{Error, Result};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use wait::WaitStatus;
+use LibcResult;
+
+/// dispositions and mask to restore once the last child is reaped
+struct SavedSignals {
+    children: usize,
+    intr: SavedAction,
+    quit: SavedAction,
+    mask: SigSet,
+}
+
+// sigaction only holds plain data and function pointers
+unsafe impl Send for SavedSignals {}
+
+static SAVED_SIGNALS: Mutex<Option<SavedSignals>> = Mutex::new(None);
+
+/// ignore SIGINT/SIGQUIT and block SIGCHLD for the first running child,
+/// returns the dispositions and mask the child has to restore
+fn enter_child() -> Result<(sigaction, sigaction, SigSet)> {
+    let mut saved = SAVED_SIGNALS.lock().unwrap();
+    if saved.is_none() {
+        let intr = SigAction::new(Handler::Ignore).install(SIGINT)?;
+        let quit = SigAction::new(Handler::Ignore).install(SIGQUIT)?;
+        let mask = [SIGCHLD].iter().cloned().collect::<SigSet>().block()?;
+        *saved = Some(SavedSignals {
+            children: 0,
+            intr: intr,
+            quit: quit,
+            mask: mask,
+        });
+    }
+    let state = saved.as_mut().unwrap();
+    state.children += 1;
+    Ok((state.intr.raw(), state.quit.raw(), state.mask))
+}
+
+/// restore dispositions and mask when the last child is gone
+fn leave_child() {
+    let mut saved = SAVED_SIGNALS.lock().unwrap();
+    let last = match *saved {
+        Some(ref mut state) => {
+            state.children -= 1;
+            state.children == 0
+        }
+        None => false,
+    };
+    if last {
+        let state = saved.take().unwrap();
+        let _ = state.mask.set_mask();
+        // intr and quit are reinstalled when they are dropped here
+    }
+}
+
+/// Builder for a child process
+/// fork and run `exec` in the child, it returns the errno of the failed step. The errno
+/// is sent back over a close-on-exec pipe, `parent` runs in the parent right after
+/// fork. Returns the pid of the child and the errno if it couldn't exec, the child
+/// then exited with 127 and still has to be reaped.
+///
+/// # Safety
+///
+/// `exec` runs in the child between fork and exec, it may only call async-signal-safe
+/// functions (no allocation, no locks).
+pub unsafe fn fork_exec<C, P>(exec: C, parent: P) -> Result<(pid_t, Option<c_int>)>
+    where C: FnOnce() -> c_int,
+          P: FnOnce(pid_t)
+{
+    // both ends are close-on-exec from the start, so a concurrent fork+exec in
+    // another thread doesn't inherit them
+    let (readfd, writefd) = fd::pipe()?;
+    let pid = fork().check_not_negative()?;
+    if pid == 0 {
+        let bytes = exec().to_ne_bytes();
+        write(writefd.as_raw_fd(), bytes.as_ptr() as *const _, bytes.len());
+        _exit(127);
+    }
+    drop(writefd);
+    parent(pid);
+
+    // wait until exec succeeded (pipe closed) or the child sent its errno
+    let mut bytes = [0u8; 4];
+    let n = loop {
+        let n = read(readfd.as_raw_fd(), bytes.as_mut_ptr() as *mut _, 4);
+        if n >= 0 || Error::last_os_error().raw_os_error() != Some(EINTR) {
+            break n;
+        }
+    };
+    Ok((pid, if n == 4 { Some(c_int::from_ne_bytes(bytes)) } else { None }))
+}
+
+pub struct Command {
+    program: OsString,
+    args: Vec<OsString>,
+    env: Option<BTreeMap<OsString, OsString>>,
+    cwd: Option<PathBuf>,
+    umask: Option<mode_t>,
+    rlimits: Vec<(c_int, rlimit)>,
+    pgid: Option<pid_t>,
+    setsid: bool,
+    redirects: Vec<(RawFd, RawFd)>,
+}
+
+impl Command {
+    /// `program` is searched in PATH if it doesn't contain a slash, it is also argv[0]
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        Command {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            env: None,
+            cwd: None,
+            umask: None,
+            rlimits: Vec::new(),
+            pgid: None,
+            setsid: false,
+            redirects: Vec::new(),
+        }
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+        where I: IntoIterator<Item = S>,
+              S: AsRef<OsStr>
+    {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    fn env_map(&mut self) -> &mut BTreeMap<OsString, OsString> {
+        if self.env.is_none() {
+            self.env = Some(::std::env::vars_os().collect());
+        }
+        self.env.as_mut().unwrap()
+    }
+
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, val: V) -> &mut Command {
+        self.env_map().insert(key.as_ref().to_owned(), val.as_ref().to_owned());
+        self
+    }
+
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Command {
+        self.env_map().remove(key.as_ref());
+        self
+    }
+
+    /// start with an empty environment
+    pub fn env_clear(&mut self) -> &mut Command {
+        self.env = Some(BTreeMap::new());
+        self
+    }
+
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Command {
+        self.cwd = Some(dir.as_ref().to_owned());
+        self
+    }
+
+    pub fn umask(&mut self, mask: mode_t) -> &mut Command {
+        self.umask = Some(mask);
+        self
+    }
+
+    /// setrlimit(resource) in the child, e.g. `RLIMIT_NOFILE`
+    pub fn rlimit(&mut self, resource: c_int, soft: rlim_t, hard: rlim_t) -> &mut Command {
+        self.rlimits.push((resource,
+                           rlimit {
+                               rlim_cur: soft,
+                               rlim_max: hard,
+                           }));
+        self
+    }
+
+    /// put the child into process group `pgid`, 0 makes it leader of a new group
+    pub fn process_group(&mut self, pgid: pid_t) -> &mut Command {
+        self.pgid = Some(pgid);
+        self
+    }
+
+    /// make the child leader of a new session (setsid), this overrides `process_group`
+    pub fn new_session(&mut self) -> &mut Command {
+        self.setsid = true;
+        self
+    }
+
+    /// dup2(from, to) in the child, e.g. `redirect(fd.as_raw_fd(), STDOUT_FILENO)`.
+    /// Redirections are applied in the order they were added
+    pub fn redirect(&mut self, from: RawFd, to: RawFd) -> &mut Command {
+        self.redirects.push((from, to));
+        self
+    }
+
+    pub fn spawn(&self) -> Result<Child> {
+        let argv = CStrArgs::argv(Some(&self.program).into_iter().chain(&self.args))?;
+        let envp = match self.env {
+            Some(ref env) => Some(CStrArgs::envp(env)?),
+            None => None,
+        };
+        let cwd = match self.cwd {
+            Some(ref cwd) => Some(to_cstring(cwd)?),
+            None => None,
+        };
+
+        let (intr, quit, mask) = enter_child()?;
+        let setpgid_parent = |pid| {
+            if let Some(pgid) = self.pgid {
+                if !self.setsid {
+                    // also done in the parent so the group exists when spawn returns,
+                    // no matter which process runs first (Section 9.4)
+                    unsafe {
+                        setpgid(pid, if pgid == 0 { pid } else { pgid });
+                    }
+                }
+            }
+        };
+        let spawned = unsafe {
+            fork_exec(|| self.exec_child(&argv, &envp, &cwd, &intr, &quit, &mask),
+                      setpgid_parent)
+        };
+        let (pid, errno) = match spawned {
+            Ok(spawned) => spawned,
+            Err(err) => {
+                leave_child();
+                return Err(err);
+            }
+        };
+
+        let mut child = Child {
+            pid: pid,
+            status: None,
+            reaped: false,
+        };
+        if let Some(errno) = errno {
+            child.wait()?;
+            return Err(Error::from_raw_os_error(errno));
+        }
+        Ok(child)
+    }
+
+    /// runs in the child between fork and exec, only returns on error with the errno
+    unsafe fn exec_child(&self,
+                         argv: &CStrArgs,
+                         envp: &Option<CStrArgs>,
+                         cwd: &Option<CString>,
+                         intr: &sigaction,
+                         quit: &sigaction,
+                         mask: &SigSet)
+                         -> c_int {
+        macro_rules! try_child {
+            ($e:expr) => {
+                if $e < 0 {
+                    return Error::last_os_error().raw_os_error().unwrap_or(0);
+                }
+            }
+        }
+        try_child!(libc::sigaction(SIGINT, intr, ::std::ptr::null_mut()));
+        try_child!(libc::sigaction(SIGQUIT, quit, ::std::ptr::null_mut()));
+        // Rust ignores SIGPIPE, the ignored disposition would survive exec
+        if libc::signal(SIGPIPE, SIG_DFL) == SIG_ERR {
+            return Error::last_os_error().raw_os_error().unwrap_or(0);
+        }
+        try_child!(sigprocmask(SIG_SETMASK, mask.as_ptr(), ::std::ptr::null_mut()));
+        if self.setsid {
+            try_child!(setsid());
+        } else if let Some(pgid) = self.pgid {
+            try_child!(setpgid(0, pgid));
+        }
+        if let Some(ref cwd) = *cwd {
+            try_child!(chdir(cwd.as_ptr()));
+        }
+        if let Some(mask) = self.umask {
+            umask(mask);
+        }
+        for &(resource, ref limit) in &self.rlimits {
+            try_child!(libc::setrlimit(resource as _, limit));
+        }
+        for &(from, to) in &self.redirects {
+            if from == to {
+                // dup2 would be a no-op, but the descriptor has to survive exec
+                let flags = fcntl(from, F_GETFD);
+                try_child!(fcntl(from, F_SETFD, flags & !FD_CLOEXEC));
+            } else {
+                try_child!(dup2(from, to));
+            }
+        }
+        if let Some(ref envp) = *envp {
+            environ = envp.as_ptr();
+        }
+        libc::execvp(argv.ptr(0), argv.as_ptr());
+        Error::last_os_error().raw_os_error().unwrap_or(0)
+    }
+
+    /// spawn and wait for the child
+    pub fn status(&self) -> Result<WaitStatus> {
+        self.spawn()?.wait()
+    }
+}
+
+/// Handle to a running child, dropping it without `wait` leaves a zombie
+/// until the parent exits
+pub struct Child {
+    pid: pid_t,
+    status: Option<WaitStatus>,
+    reaped: bool,
+}
+
+impl Child {
+    pub fn id(&self) -> pid_t {
+        self.pid
+    }
+
+    fn waitpid(&mut self, options: c_int) -> Result<Option<WaitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+        let mut status = 0;
+        loop {
+            match unsafe { waitpid(self.pid, &mut status, options) } {
+                0 => return Ok(None),
+                pid if pid > 0 => break,
+                _ => {
+                    let err = Error::last_os_error();
+                    if err.raw_os_error() != Some(EINTR) {
+                        if err.raw_os_error() == Some(ECHILD) && !self.reaped {
+                            // somebody else reaped it, don't keep the signals changed
+                            self.reaped = true;
+                            leave_child();
+                        }
+                        return Err(err);
+                    }
+                }
+            }
+        }
+        let status = WaitStatus::from_raw(status);
+        self.status = Some(status);
+        self.reaped = true;
+        leave_child();
+        Ok(Some(status))
+    }
+
+    /// wait for the child to terminate, retried on EINTR like in `system2`
+    pub fn wait(&mut self) -> Result<WaitStatus> {
+        self.waitpid(0).map(|status| status.unwrap())
+    }
+
+    /// the exit status if the child already terminated
+    pub fn try_wait(&mut self) -> Result<Option<WaitStatus>> {
+        self.waitpid(WNOHANG)
+    }
+
+    /// send `sig` to the child, fails with ESRCH after it was reaped
+    pub fn kill(&mut self, sig: c_int) -> Result<()> {
+        if self.reaped {
+            return Err(Error::from_raw_os_error(libc::ESRCH));
+        }
+        unsafe { libc::kill(self.pid, sig) }.check_not_negative()?;
+        Ok(())
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if !self.reaped {
+            leave_child();
+        }
+    }
+}
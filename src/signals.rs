@@ -0,0 +1,494 @@
+//! Signal sets and signal actions
+//!
+//! `SigSet` wraps `sigset_t` so it never needs to be created via `mem::uninitialized()`,
+//! `SigAction` is a builder around `sigaction(2)` which hands back the previous action as a
+//! guard that reinstalls it when it goes out of scope.
+
+use libc::{c_int, c_void, sigaction, siginfo_t, sighandler_t, sigset_t};
+use libc::{SA_SIGINFO, SIG_BLOCK, SIG_DFL, SIG_IGN, SIG_SETMASK, SIG_UNBLOCK};
+use libc::{sigaddset, sigdelset, sigemptyset, sigfillset, sigismember};
+use libc::{SIGHUP, SIGINT, SIGQUIT, SIGILL, SIGTRAP, SIGABRT, SIGBUS, SIGFPE, SIGKILL, SIGUSR1,
+           SIGSEGV, SIGUSR2, SIGPIPE, SIGALRM, SIGTERM, SIGCHLD, SIGCONT, SIGSTOP, SIGTSTP,
+           SIGTTIN, SIGTTOU, SIGURG, SIGXCPU, SIGXFSZ, SIGVTALRM, SIGPROF, SIGWINCH, SIGIO,
+           SIGSYS};
+#[cfg(target_os = "linux")]
+use libc::{SIGSTKFLT, SIGPWR, SIGRTMIN, SIGRTMAX};
+#[cfg(target_os = "macos")]
+use libc::{SIGEMT, SIGINFO};
+use my_libc::{sigpending, sigprocmask, sigsuspend};
+use std::fmt;
+use std::io::Result;
+use std::iter::FromIterator;
+use std::mem::{transmute, zeroed};
+use std::ptr::{null, null_mut};
+use LibcResult;
+
+/// highest signal number + 1
+#[cfg(target_os = "linux")]
+pub const NSIG: c_int = 65;
+#[cfg(target_os = "macos")]
+pub const NSIG: c_int = 32;
+
+// number, name and the description strsignal(3) returns on Linux
+static SIGNALS: &[(c_int, &str, &str)] = &[
+    (SIGHUP, "SIGHUP", "Hangup"),
+    (SIGINT, "SIGINT", "Interrupt"),
+    (SIGQUIT, "SIGQUIT", "Quit"),
+    (SIGILL, "SIGILL", "Illegal instruction"),
+    (SIGTRAP, "SIGTRAP", "Trace/breakpoint trap"),
+    (SIGABRT, "SIGABRT", "Aborted"),
+    (SIGBUS, "SIGBUS", "Bus error"),
+    (SIGFPE, "SIGFPE", "Floating point exception"),
+    (SIGKILL, "SIGKILL", "Killed"),
+    (SIGUSR1, "SIGUSR1", "User defined signal 1"),
+    (SIGSEGV, "SIGSEGV", "Segmentation fault"),
+    (SIGUSR2, "SIGUSR2", "User defined signal 2"),
+    (SIGPIPE, "SIGPIPE", "Broken pipe"),
+    (SIGALRM, "SIGALRM", "Alarm clock"),
+    (SIGTERM, "SIGTERM", "Terminated"),
+    (SIGCHLD, "SIGCHLD", "Child exited"),
+    (SIGCONT, "SIGCONT", "Continued"),
+    (SIGSTOP, "SIGSTOP", "Stopped (signal)"),
+    (SIGTSTP, "SIGTSTP", "Stopped"),
+    (SIGTTIN, "SIGTTIN", "Stopped (tty input)"),
+    (SIGTTOU, "SIGTTOU", "Stopped (tty output)"),
+    (SIGURG, "SIGURG", "Urgent I/O condition"),
+    (SIGXCPU, "SIGXCPU", "CPU time limit exceeded"),
+    (SIGXFSZ, "SIGXFSZ", "File size limit exceeded"),
+    (SIGVTALRM, "SIGVTALRM", "Virtual timer expired"),
+    (SIGPROF, "SIGPROF", "Profiling timer expired"),
+    (SIGWINCH, "SIGWINCH", "Window changed"),
+    (SIGIO, "SIGIO", "I/O possible"),
+    (SIGSYS, "SIGSYS", "Bad system call"),
+];
+
+#[cfg(target_os = "linux")]
+static PLATFORM_SIGNALS: &[(c_int, &str, &str)] = &[
+    (SIGSTKFLT, "SIGSTKFLT", "Stack fault"),
+    (SIGPWR, "SIGPWR", "Power failure"),
+];
+
+#[cfg(target_os = "macos")]
+static PLATFORM_SIGNALS: &[(c_int, &str, &str)] = &[
+    (SIGEMT, "SIGEMT", "EMT trap"),
+    (SIGINFO, "SIGINFO", "Information request"),
+];
+
+// alternative names accepted by str2sig
+static ALIASES: &[(&str, c_int)] = &[
+    ("SIGIOT", SIGABRT),
+    ("SIGCLD", SIGCHLD),
+    ("SIGPOLL", SIGIO),
+];
+
+// glibc reserves the lowest realtime signals for itself, so SIGRTMIN is only known at runtime
+#[cfg(target_os = "linux")]
+static RT_NAMES: [&str; 33] = [
+    "SIGRTMIN", "SIGRTMIN+1", "SIGRTMIN+2", "SIGRTMIN+3", "SIGRTMIN+4", "SIGRTMIN+5",
+    "SIGRTMIN+6", "SIGRTMIN+7", "SIGRTMIN+8", "SIGRTMIN+9", "SIGRTMIN+10", "SIGRTMIN+11",
+    "SIGRTMIN+12", "SIGRTMIN+13", "SIGRTMIN+14", "SIGRTMIN+15", "SIGRTMIN+16", "SIGRTMIN+17",
+    "SIGRTMIN+18", "SIGRTMIN+19", "SIGRTMIN+20", "SIGRTMIN+21", "SIGRTMIN+22", "SIGRTMIN+23",
+    "SIGRTMIN+24", "SIGRTMIN+25", "SIGRTMIN+26", "SIGRTMIN+27", "SIGRTMIN+28", "SIGRTMIN+29",
+    "SIGRTMIN+30", "SIGRTMIN+31", "SIGRTMIN+32"
+];
+
+#[cfg(target_os = "linux")]
+fn realtime_range() -> ::std::ops::RangeInclusive<c_int> {
+    SIGRTMIN()..=SIGRTMAX()
+}
+
+#[cfg(target_os = "macos")]
+fn realtime_range() -> ::std::ops::RangeInclusive<c_int> {
+    // no realtime signals, this range is empty
+    1..=0
+}
+
+fn lookup(signo: c_int) -> Option<&'static (c_int, &'static str, &'static str)> {
+    SIGNALS.iter().chain(PLATFORM_SIGNALS.iter()).find(|s| s.0 == signo)
+}
+
+/// name of the signal, e.g. `SIGINT` or `SIGRTMIN+3`
+pub fn sig2str(signo: c_int) -> Option<&'static str> {
+    if let Some(&(_, name, _)) = lookup(signo) {
+        return Some(name);
+    }
+    sig2str_realtime(signo)
+}
+
+#[cfg(target_os = "linux")]
+fn sig2str_realtime(signo: c_int) -> Option<&'static str> {
+    if signo == SIGRTMAX() {
+        Some("SIGRTMAX")
+    } else if realtime_range().contains(&signo) {
+        RT_NAMES.get((signo - SIGRTMIN()) as usize).cloned()
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn sig2str_realtime(_: c_int) -> Option<&'static str> {
+    None
+}
+
+/// signal number for a name, with or without the `SIG` prefix (`INT`, `SIGINT`),
+/// `RTMIN+n`/`RTMAX-n` or the number itself
+pub fn str2sig(name: &str) -> Option<c_int> {
+    if let Ok(signo) = name.parse::<c_int>() {
+        return if signo > 0 && signo < NSIG { Some(signo) } else { None };
+    }
+    let name = name.strip_prefix("SIG").unwrap_or(name);
+    if let Some(&(signo, _, _)) = SIGNALS.iter()
+        .chain(PLATFORM_SIGNALS.iter())
+        .find(|s| &s.1[3..] == name) {
+        return Some(signo);
+    }
+    if let Some(&(_, signo)) = ALIASES.iter().find(|a| &a.0[3..] == name) {
+        return Some(signo);
+    }
+    let rt = realtime_range();
+    let signo = if name == "RTMIN" {
+        *rt.start()
+    } else if name == "RTMAX" {
+        *rt.end()
+    } else if let Some(n) = name.strip_prefix("RTMIN+") {
+        rt.start().checked_add(n.parse::<c_int>().ok()?)?
+    } else if let Some(n) = name.strip_prefix("RTMAX-") {
+        rt.end().checked_sub(n.parse::<c_int>().ok()?)?
+    } else {
+        return None;
+    };
+    if rt.contains(&signo) { Some(signo) } else { None }
+}
+
+/// description as printed by strsignal(3), without its static buffer
+pub fn description(signo: c_int) -> Option<&'static str> {
+    if let Some(&(_, _, desc)) = lookup(signo) {
+        Some(desc)
+    } else if realtime_range().contains(&signo) {
+        Some("Real-time signal")
+    } else {
+        None
+    }
+}
+
+/// every signal number the platform defines, in ascending order
+pub fn all() -> Vec<c_int> {
+    let mut signals: Vec<c_int> = SIGNALS.iter()
+        .chain(PLATFORM_SIGNALS.iter())
+        .map(|s| s.0)
+        .chain(realtime_range())
+        .collect();
+    signals.sort();
+    signals
+}
+
+#[derive(Clone, Copy)]
+pub struct SigSet(sigset_t);
+
+impl SigSet {
+    pub fn empty() -> SigSet {
+        unsafe {
+            let mut set: sigset_t = zeroed();
+            sigemptyset(&mut set);
+            SigSet(set)
+        }
+    }
+
+    pub fn full() -> SigSet {
+        unsafe {
+            let mut set: sigset_t = zeroed();
+            sigfillset(&mut set);
+            SigSet(set)
+        }
+    }
+
+    /// signal mask of the calling process
+    pub fn current() -> Result<SigSet> {
+        unsafe {
+            let mut set: sigset_t = zeroed();
+            sigprocmask(0, null(), &mut set).check_not_negative()?;
+            Ok(SigSet(set))
+        }
+    }
+
+    /// signals which are blocked and pending
+    pub fn pending() -> Result<SigSet> {
+        unsafe {
+            let mut set: sigset_t = zeroed();
+            sigpending(&mut set).check_not_negative()?;
+            Ok(SigSet(set))
+        }
+    }
+
+    pub fn insert(&mut self, signo: c_int) {
+        unsafe {
+            sigaddset(&mut self.0, signo);
+        }
+    }
+
+    pub fn remove(&mut self, signo: c_int) {
+        unsafe {
+            sigdelset(&mut self.0, signo);
+        }
+    }
+
+    pub fn contains(&self, signo: c_int) -> bool {
+        unsafe { sigismember(&self.0, signo) == 1 }
+    }
+
+    pub fn union(&self, other: &SigSet) -> SigSet {
+        let mut set = *self;
+        set.extend(other.iter());
+        set
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// all signal numbers in the set in ascending order
+    pub fn iter(&self) -> Iter {
+        Iter { set: *self, next: 1 }
+    }
+
+    /// sigprocmask(how, self), returns the previous mask
+    pub fn procmask(&self, how: c_int) -> Result<SigSet> {
+        unsafe {
+            let mut old: sigset_t = zeroed();
+            sigprocmask(how, &self.0, &mut old).check_not_negative()?;
+            Ok(SigSet(old))
+        }
+    }
+
+    /// add the signals to the process mask, returns the previous mask
+    pub fn block(&self) -> Result<SigSet> {
+        self.procmask(SIG_BLOCK)
+    }
+
+    /// remove the signals from the process mask, returns the previous mask
+    pub fn unblock(&self) -> Result<SigSet> {
+        self.procmask(SIG_UNBLOCK)
+    }
+
+    /// replace the process mask, returns the previous mask
+    pub fn set_mask(&self) -> Result<SigSet> {
+        self.procmask(SIG_SETMASK)
+    }
+
+    /// sigsuspend: replace the mask by this set and pause until a signal is caught,
+    /// returns after the signal handler returned and the old mask is restored
+    pub fn suspend(&self) {
+        unsafe {
+            sigsuspend(&self.0);
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const sigset_t {
+        &self.0
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut sigset_t {
+        &mut self.0
+    }
+}
+
+impl From<sigset_t> for SigSet {
+    fn from(set: sigset_t) -> SigSet {
+        SigSet(set)
+    }
+}
+
+impl FromIterator<c_int> for SigSet {
+    fn from_iter<I: IntoIterator<Item = c_int>>(iter: I) -> SigSet {
+        let mut set = SigSet::empty();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<c_int> for SigSet {
+    fn extend<I: IntoIterator<Item = c_int>>(&mut self, iter: I) {
+        for signo in iter {
+            self.insert(signo);
+        }
+    }
+}
+
+impl PartialEq for SigSet {
+    fn eq(&self, other: &SigSet) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl fmt::Debug for SigSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, signo) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match sig2str(signo) {
+                Some(name) => write!(f, "{}", name)?,
+                None => write!(f, "{}", signo)?,
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+pub struct Iter {
+    set: SigSet,
+    next: c_int,
+}
+
+impl Iterator for Iter {
+    type Item = c_int;
+
+    fn next(&mut self) -> Option<c_int> {
+        while self.next < NSIG {
+            let signo = self.next;
+            self.next += 1;
+            if self.set.contains(signo) {
+                return Some(signo);
+            }
+        }
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Handler {
+    Default,
+    Ignore,
+    Handler(extern "C" fn(c_int)),
+    /// installed with `SA_SIGINFO`
+    SigInfo(extern "C" fn(c_int, *mut siginfo_t, *mut c_void)),
+}
+
+impl Handler {
+    /// interpret the `sa_sigaction` field of a sigaction struct, `flags` tells if
+    /// it is a `SA_SIGINFO` handler
+    ///
+    /// # Safety
+    ///
+    /// Unless it is `SIG_DFL` or `SIG_IGN`, `handler` must be the address of a function
+    /// with the signature `flags` implies, e.g. as returned by sigaction(2).
+    pub unsafe fn from_raw(handler: sighandler_t, flags: c_int) -> Handler {
+        match handler {
+            SIG_DFL => Handler::Default,
+            SIG_IGN => Handler::Ignore,
+            h if flags & SA_SIGINFO != 0 => {
+                Handler::SigInfo(transmute::<sighandler_t,
+                                             extern "C" fn(c_int, *mut siginfo_t, *mut c_void)>(h))
+            }
+            h => Handler::Handler(transmute::<sighandler_t, extern "C" fn(c_int)>(h)),
+        }
+    }
+
+    pub fn as_raw(&self) -> sighandler_t {
+        match *self {
+            Handler::Default => SIG_DFL,
+            Handler::Ignore => SIG_IGN,
+            Handler::Handler(f) => f as sighandler_t,
+            Handler::SigInfo(f) => f as sighandler_t,
+        }
+    }
+}
+
+/// Builder for sigaction(2)
+#[derive(Clone, Copy, Debug)]
+pub struct SigAction {
+    handler: Handler,
+    flags: c_int,
+    mask: SigSet,
+}
+
+impl SigAction {
+    pub fn new(handler: Handler) -> SigAction {
+        SigAction {
+            handler,
+            flags: 0,
+            mask: SigSet::empty(),
+        }
+    }
+
+    /// sa_flags, `SA_SIGINFO` is added automatically for `Handler::SigInfo`
+    pub fn flags(mut self, flags: c_int) -> SigAction {
+        self.flags = flags;
+        self
+    }
+
+    /// signals blocked while the handler runs
+    pub fn mask(mut self, mask: SigSet) -> SigAction {
+        self.mask = mask;
+        self
+    }
+
+    pub fn install(&self, signo: c_int) -> Result<SavedAction> {
+        unsafe {
+            let mut act: sigaction = zeroed();
+            let mut oact: sigaction = zeroed();
+            act.sa_sigaction = self.handler.as_raw();
+            act.sa_mask = self.mask.0;
+            act.sa_flags = self.flags;
+            if let Handler::SigInfo(_) = self.handler {
+                act.sa_flags |= SA_SIGINFO;
+            }
+            sigaction(signo, &act, &mut oact).check_not_negative()?;
+            Ok(SavedAction {
+                signo,
+                old: oact,
+                active: true,
+            })
+        }
+    }
+}
+
+/// The action that was installed before `SigAction::install`,
+/// it is reinstalled on drop unless `keep` is called
+#[must_use]
+pub struct SavedAction {
+    signo: c_int,
+    old: sigaction,
+    active: bool,
+}
+
+impl SavedAction {
+    pub fn signo(&self) -> c_int {
+        self.signo
+    }
+
+    /// the previous action as passed to sigaction(2)
+    pub fn raw(&self) -> sigaction {
+        self.old
+    }
+
+    /// the previous handler
+    pub fn handler(&self) -> Handler {
+        unsafe { Handler::from_raw(self.old.sa_sigaction, self.old.sa_flags) }
+    }
+
+    /// leave the new action installed, returns the previous handler
+    pub fn keep(mut self) -> Handler {
+        self.active = false;
+        self.handler()
+    }
+
+    /// reinstall the previous action now and report errors (drop ignores them)
+    pub fn restore(mut self) -> Result<()> {
+        self.active = false;
+        unsafe { sigaction(self.signo, &self.old, null_mut()) }.check_not_negative()?;
+        Ok(())
+    }
+}
+
+impl Drop for SavedAction {
+    fn drop(&mut self) {
+        if self.active {
+            unsafe {
+                sigaction(self.signo, &self.old, null_mut());
+            }
+        }
+    }
+}
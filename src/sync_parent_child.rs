@@ -0,0 +1,173 @@
+//! Routines to allow a parent and child to synchronize
+//!
+//! Two implementations of the book's TELL_WAIT, TELL_PARENT, WAIT_PARENT, TELL_CHILD and
+//! WAIT_CHILD behind the `TellWait` trait:
+//!
+//! - `SignalTellWait` (Figure 10.24): the parent signals the child with SIGUSR1, the child
+//!   the parent with SIGUSR2
+//! - `PipeTellWait` (Figure 15.7): one pipe per direction, telling writes a byte, waiting
+//!   reads it
+//!
+//! Both are created before `fork`, afterwards parent and child use their copy of it.
+//!
+//! Differences to the book's signal version: SIGUSR1 and SIGUSR2 stay blocked until the
+//! object is dropped (resetting the mask after every wait as in the book lets a signal slip
+//! in between testing the flag and sigsuspend), and each signal has its own flag.
+
+use fd::{self, Fd};
+use libc::{c_int, pid_t};
+use libc::{kill, SIGUSR1, SIGUSR2};
+use signals::{Handler, SavedAction, SigAction, SigSet};
+use std::io::{Error, ErrorKind, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use LibcResult;
+
+pub trait TellWait {
+    /// child: tell the parent (`pid`) that we're done
+    fn tell_parent(&self, pid: pid_t) -> Result<()>;
+    /// child: block until the parent called `tell_child`
+    fn wait_parent(&self) -> Result<()>;
+    /// parent: tell the child (`pid`) that we're done
+    fn tell_child(&self, pid: pid_t) -> Result<()>;
+    /// parent: block until the child called `tell_parent`
+    fn wait_child(&self) -> Result<()>;
+}
+
+// set by the signal handler, per process after the fork
+static USR1_FLAG: AtomicBool = AtomicBool::new(false);
+static USR2_FLAG: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn sig_usr(signo: c_int) {
+    match signo {
+        SIGUSR1 => USR1_FLAG.store(true, Ordering::SeqCst),
+        SIGUSR2 => USR2_FLAG.store(true, Ordering::SeqCst),
+        _ => {}
+    }
+}
+
+/// Figure 10.24, synchronization with SIGUSR1 and SIGUSR2
+pub struct SignalTellWait {
+    // the mask before TELL_WAIT, restored on drop
+    oldmask: SigSet,
+    // oldmask without SIGUSR1/SIGUSR2, used while waiting
+    waitmask: SigSet,
+    _usr1: SavedAction,
+    _usr2: SavedAction,
+}
+
+impl SignalTellWait {
+    /// TELL_WAIT: catch SIGUSR1 and SIGUSR2 and block them
+    pub fn new() -> Result<SignalTellWait> {
+        USR1_FLAG.store(false, Ordering::SeqCst);
+        USR2_FLAG.store(false, Ordering::SeqCst);
+        let usr1 = SigAction::new(Handler::Handler(sig_usr)).install(SIGUSR1)?;
+        let usr2 = SigAction::new(Handler::Handler(sig_usr)).install(SIGUSR2)?;
+
+        // Block SIGUSR1 and SIGUSR2 and save current signal mask
+        let newmask: SigSet = [SIGUSR1, SIGUSR2].iter().cloned().collect();
+        let oldmask = newmask.block()?;
+        let mut waitmask = oldmask;
+        waitmask.remove(SIGUSR1);
+        waitmask.remove(SIGUSR2);
+        Ok(SignalTellWait {
+            oldmask: oldmask,
+            waitmask: waitmask,
+            _usr1: usr1,
+            _usr2: usr2,
+        })
+    }
+
+    fn wait_for(&self, flag: &AtomicBool) {
+        // the signal is blocked, so it can only be delivered inside sigsuspend
+        while !flag.swap(false, Ordering::SeqCst) {
+            self.waitmask.suspend();
+        }
+    }
+}
+
+impl TellWait for SignalTellWait {
+    fn tell_parent(&self, pid: pid_t) -> Result<()> {
+        unsafe { kill(pid, SIGUSR2) }.check_not_negative()?;
+        Ok(())
+    }
+
+    fn wait_parent(&self) -> Result<()> {
+        self.wait_for(&USR1_FLAG);
+        Ok(())
+    }
+
+    fn tell_child(&self, pid: pid_t) -> Result<()> {
+        unsafe { kill(pid, SIGUSR1) }.check_not_negative()?;
+        Ok(())
+    }
+
+    fn wait_child(&self) -> Result<()> {
+        self.wait_for(&USR2_FLAG);
+        Ok(())
+    }
+}
+
+impl Drop for SignalTellWait {
+    fn drop(&mut self) {
+        // a signal still pending is handled right here, the handlers are restored after
+        let _ = self.oldmask.set_mask();
+    }
+}
+
+/// Figure 15.7, synchronization with two pipes
+pub struct PipeTellWait {
+    // parent -> child
+    pfd1: (Fd, Fd),
+    // child -> parent
+    pfd2: (Fd, Fd),
+}
+
+impl PipeTellWait {
+    /// TELL_WAIT: create both pipes
+    pub fn new() -> Result<PipeTellWait> {
+        Ok(PipeTellWait {
+            pfd1: fd::pipe()?,
+            pfd2: fd::pipe()?,
+        })
+    }
+}
+
+fn read_char(fd: &Fd, expected: u8) -> Result<()> {
+    let mut c = [0];
+    loop {
+        match fd.read(&mut c) {
+            Ok(1) if c[0] == expected => return Ok(()),
+            Ok(1) => return Err(Error::new(ErrorKind::InvalidData, "incorrect data")),
+            Ok(_) => return Err(Error::new(ErrorKind::UnexpectedEof, "read error")),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+impl TellWait for PipeTellWait {
+    fn tell_parent(&self, _: pid_t) -> Result<()> {
+        (self.pfd2.1).write(b"c").map(|_| ())
+    }
+
+    fn wait_parent(&self) -> Result<()> {
+        read_char(&self.pfd1.0, b'p')
+    }
+
+    fn tell_child(&self, _: pid_t) -> Result<()> {
+        (self.pfd1.1).write(b"p").map(|_| ())
+    }
+
+    fn wait_child(&self) -> Result<()> {
+        read_char(&self.pfd2.0, b'c')
+    }
+}
+
+/// pick the implementation by name ("signal" or "pipe"), for the examples
+pub fn by_name(name: &str) -> Result<Box<dyn TellWait>> {
+    match name {
+        "signal" => Ok(Box::new(SignalTellWait::new()?)),
+        "pipe" => Ok(Box::new(PipeTellWait::new()?)),
+        _ => Err(Error::new(ErrorKind::InvalidInput, format!("unknown TELL_WAIT variant {}", name))),
+    }
+}
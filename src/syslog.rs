@@ -0,0 +1,309 @@
+//! System logging, section 13.4
+//!
+//! `openlog`, `syslog`, `setlogmask` and `closelog` wrap the functions of the C library,
+//! which send to the syslogd of the system via /dev/log. `Client` is a replacement
+//! written in Rust: it formats the frames itself (RFC 3164 or RFC 5424) and sends them
+//! to a configurable UNIX datagram socket, e.g. to the stand-in syslogd
+//! `f02-syslogd` in tests. After `use_client` the functions of this module log with
+//! the client instead of the C library.
+
+use libc::{c_char, c_int, time_t, tm};
+use libc::{LOG_ALERT, LOG_CRIT, LOG_DEBUG, LOG_EMERG, LOG_ERR, LOG_INFO, LOG_NOTICE,
+           LOG_WARNING};
+use libc::{LOG_AUTH, LOG_AUTHPRIV, LOG_CRON, LOG_DAEMON, LOG_FTP, LOG_KERN, LOG_LOCAL0,
+           LOG_LOCAL1, LOG_LOCAL2, LOG_LOCAL3, LOG_LOCAL4, LOG_LOCAL5, LOG_LOCAL6, LOG_LOCAL7,
+           LOG_LPR, LOG_MAIL, LOG_NEWS, LOG_SYSLOG, LOG_USER, LOG_UUCP};
+use libc::{getpid, gethostname, localtime_r};
+use std::ffi::{CStr, CString};
+use std::io::Result;
+use std::mem;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// severity of a message, most severe first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Emerg,
+    Alert,
+    Crit,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl Priority {
+    pub fn as_raw(&self) -> c_int {
+        match *self {
+            Priority::Emerg => LOG_EMERG,
+            Priority::Alert => LOG_ALERT,
+            Priority::Crit => LOG_CRIT,
+            Priority::Error => LOG_ERR,
+            Priority::Warning => LOG_WARNING,
+            Priority::Notice => LOG_NOTICE,
+            Priority::Info => LOG_INFO,
+            Priority::Debug => LOG_DEBUG,
+        }
+    }
+}
+
+/// kind of program sending a message, Figure 13.15
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Facility {
+    Kern,
+    User,
+    Mail,
+    Daemon,
+    Auth,
+    Syslog,
+    Lpr,
+    News,
+    Uucp,
+    Cron,
+    AuthPriv,
+    Ftp,
+    Local0,
+    Local1,
+    Local2,
+    Local3,
+    Local4,
+    Local5,
+    Local6,
+    Local7,
+}
+
+impl Facility {
+    pub fn as_raw(&self) -> c_int {
+        match *self {
+            Facility::Kern => LOG_KERN,
+            Facility::User => LOG_USER,
+            Facility::Mail => LOG_MAIL,
+            Facility::Daemon => LOG_DAEMON,
+            Facility::Auth => LOG_AUTH,
+            Facility::Syslog => LOG_SYSLOG,
+            Facility::Lpr => LOG_LPR,
+            Facility::News => LOG_NEWS,
+            Facility::Uucp => LOG_UUCP,
+            Facility::Cron => LOG_CRON,
+            Facility::AuthPriv => LOG_AUTHPRIV,
+            Facility::Ftp => LOG_FTP,
+            Facility::Local0 => LOG_LOCAL0,
+            Facility::Local1 => LOG_LOCAL1,
+            Facility::Local2 => LOG_LOCAL2,
+            Facility::Local3 => LOG_LOCAL3,
+            Facility::Local4 => LOG_LOCAL4,
+            Facility::Local5 => LOG_LOCAL5,
+            Facility::Local6 => LOG_LOCAL6,
+            Facility::Local7 => LOG_LOCAL7,
+        }
+    }
+}
+
+/// LOG_MASK: mask for `setlogmask` with only `priority`
+pub fn log_mask(priority: Priority) -> c_int {
+    1 << priority.as_raw()
+}
+
+/// LOG_UPTO: mask for `setlogmask` with `priority` and all more severe ones
+pub fn log_upto(priority: Priority) -> c_int {
+    (1 << (priority.as_raw() + 1)) - 1
+}
+
+// openlog keeps the pointer to the identifier, so it has to live until the next openlog
+static IDENT: Mutex<Option<CString>> = Mutex::new(None);
+static CLIENT: Mutex<Option<Client>> = Mutex::new(None);
+
+// C strings end at the first NUL, keep what's before it
+fn truncate_at_nul(s: &str) -> CString {
+    let end = s.find('\0').unwrap_or(s.len());
+    CString::new(&s[..end]).unwrap()
+}
+
+/// openlog(3): `ident` is prepended to every message, `option` is a combination of
+/// `LOG_CONS`, `LOG_NDELAY`, `LOG_PID`, ... and `facility` is used for all messages
+pub fn openlog(ident: &str, option: c_int, facility: Facility) {
+    let mut saved = IDENT.lock().unwrap();
+    let ident = truncate_at_nul(ident);
+    unsafe { libc::openlog(ident.as_ptr(), option, facility.as_raw()) };
+    *saved = Some(ident);
+}
+
+/// syslog(3) with `msg` as it is, it isn't interpreted as a format string. Errors of
+/// the client (e.g. nobody listening) are ignored like syslog(3) does
+pub fn syslog(priority: Priority, msg: &str) {
+    if let Some(ref client) = *CLIENT.lock().unwrap() {
+        let _ = client.log(priority, msg);
+        return;
+    }
+    let msg = truncate_at_nul(msg);
+    unsafe { libc::syslog(priority.as_raw(), b"%s\0".as_ptr() as *const _, msg.as_ptr()) };
+}
+
+/// setlogmask(3): only log priorities in `mask` (see `log_mask` and `log_upto`),
+/// returns the previous mask. 0 only returns the current mask
+pub fn setlogmask(mask: c_int) -> c_int {
+    if let Some(ref mut client) = *CLIENT.lock().unwrap() {
+        return client.set_mask(mask);
+    }
+    unsafe { libc::setlogmask(mask) }
+}
+
+/// closelog(3), also drops the client of `use_client`
+pub fn closelog() {
+    let mut saved = IDENT.lock().unwrap();
+    unsafe { libc::closelog() };
+    *saved = None;
+    *CLIENT.lock().unwrap() = None;
+}
+
+/// let `syslog` and `setlogmask` use `client` instead of the C library
+pub fn use_client(client: Client) {
+    *CLIENT.lock().unwrap() = Some(client);
+}
+
+/// frame format of `Client`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// BSD syslog as sent by the C library to /dev/log:
+    /// `<PRI>Mmm dd hh:mm:ss IDENT[PID]: MSG`
+    Rfc3164,
+    /// `<PRI>1 TIMESTAMP HOSTNAME IDENT PID - - MSG`
+    Rfc5424,
+}
+
+/// syslog client writing to a UNIX datagram socket, /dev/log by default. The socket
+/// isn't connected, every message is sent to the path, so a restarted server still
+/// gets the next one
+#[derive(Debug)]
+pub struct Client {
+    socket: UnixDatagram,
+    path: PathBuf,
+    ident: String,
+    facility: Facility,
+    format: Format,
+    pid: bool,
+    mask: c_int,
+}
+
+impl Client {
+    /// client sending RFC 3164 frames with facility `User` to /dev/log
+    pub fn new(ident: &str) -> Result<Client> {
+        Ok(Client {
+            socket: UnixDatagram::unbound()?,
+            path: PathBuf::from("/dev/log"),
+            ident: ident.to_string(),
+            facility: Facility::User,
+            format: Format::Rfc3164,
+            pid: false,
+            mask: log_upto(Priority::Debug),
+        })
+    }
+
+    pub fn path<P: AsRef<Path>>(&mut self, path: P) -> &mut Client {
+        self.path = path.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn facility(&mut self, facility: Facility) -> &mut Client {
+        self.facility = facility;
+        self
+    }
+
+    pub fn format(&mut self, format: Format) -> &mut Client {
+        self.format = format;
+        self
+    }
+
+    /// add the pid to every message, LOG_PID
+    pub fn pid(&mut self, pid: bool) -> &mut Client {
+        self.pid = pid;
+        self
+    }
+
+    /// like `setlogmask`, returns the previous mask
+    pub fn set_mask(&mut self, mask: c_int) -> c_int {
+        let old = self.mask;
+        if mask != 0 {
+            self.mask = mask;
+        }
+        old
+    }
+
+    /// the frame `log` sends
+    pub fn frame(&self, priority: Priority, msg: &str) -> String {
+        let pri = self.facility.as_raw() | priority.as_raw();
+        let (tm, usec) = local_time();
+        let pid = unsafe { getpid() };
+        match self.format {
+            Format::Rfc3164 => {
+                const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul",
+                                            "Aug", "Sep", "Oct", "Nov", "Dec"];
+                let ident = if self.pid {
+                    format!("{}[{}]", self.ident, pid)
+                } else {
+                    self.ident.clone()
+                };
+                format!("<{}>{} {:2} {:02}:{:02}:{:02} {}: {}",
+                        pri,
+                        MONTHS[tm.tm_mon as usize],
+                        tm.tm_mday,
+                        tm.tm_hour,
+                        tm.tm_min,
+                        tm.tm_sec,
+                        ident,
+                        msg)
+            }
+            Format::Rfc5424 => {
+                let offset = tm.tm_gmtoff / 60;
+                format!("<{}>1 {:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}{}{:02}:{:02} {} {} {} - - {}",
+                        pri,
+                        tm.tm_year + 1900,
+                        tm.tm_mon + 1,
+                        tm.tm_mday,
+                        tm.tm_hour,
+                        tm.tm_min,
+                        tm.tm_sec,
+                        usec,
+                        if offset < 0 { '-' } else { '+' },
+                        offset.abs() / 60,
+                        offset.abs() % 60,
+                        hostname().unwrap_or_else(|| "-".to_string()),
+                        if self.ident.is_empty() { "-" } else { &self.ident },
+                        if self.pid { pid.to_string() } else { "-".to_string() },
+                        msg)
+            }
+        }
+    }
+
+    /// send `msg` unless `priority` is masked out
+    pub fn log(&self, priority: Priority, msg: &str) -> Result<()> {
+        if self.mask & log_mask(priority) == 0 {
+            return Ok(());
+        }
+        self.socket.send_to(self.frame(priority, msg).as_bytes(), &self.path)?;
+        Ok(())
+    }
+}
+
+// broken down local time and microseconds
+fn local_time() -> (tm, u32) {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs() as time_t;
+    unsafe {
+        let mut tm: tm = mem::zeroed();
+        localtime_r(&secs, &mut tm);
+        (tm, now.subsec_micros())
+    }
+}
+
+fn hostname() -> Option<String> {
+    let mut buf = [0 as c_char; 256];
+    if unsafe { gethostname(buf.as_mut_ptr(), buf.len() - 1) } < 0 {
+        return None;
+    }
+    let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
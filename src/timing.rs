@@ -0,0 +1,75 @@
+//! Timing the benchmarks of chapters 14 and 15
+//!
+//! `Timer` measures the real time and, with getrusage(2), the user and system CPU time
+//! used by the process and the children it waited for. `Times` formats as the last
+//! three columns of the markdown tables the benchmarks print.
+
+use libc::{c_int, rusage, timeval, RUSAGE_CHILDREN, RUSAGE_SELF};
+use libc::getrusage;
+use std::fmt;
+use std::time::Instant;
+
+/// user and system CPU time in seconds of `who` (`RUSAGE_SELF` or `RUSAGE_CHILDREN`)
+pub fn cpu_time(who: c_int) -> (f64, f64) {
+    let mut usage: rusage = unsafe { ::std::mem::zeroed() };
+    unsafe { getrusage(who, &mut usage) };
+    let secs = |t: timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1e6;
+    (secs(usage.ru_utime), secs(usage.ru_stime))
+}
+
+// children only count once they have been waited for
+fn cpu_total() -> (f64, f64) {
+    let (su, ss) = cpu_time(RUSAGE_SELF);
+    let (cu, cs) = cpu_time(RUSAGE_CHILDREN);
+    (su + cu, ss + cs)
+}
+
+/// real, user and system time in seconds
+#[derive(Clone, Copy, Debug)]
+pub struct Times {
+    pub real: f64,
+    pub user: f64,
+    pub sys: f64,
+}
+
+impl Times {
+    /// titles of the columns, e.g. `println!("| method | {}", Times::HEADER)`
+    pub const HEADER: &'static str = "real time | user time | sys time |";
+    /// the line below `HEADER`, e.g. `println!("|--------|{}", Times::SEPARATOR)`
+    pub const SEPARATOR: &'static str = "-----------|-----------|----------|";
+}
+
+impl fmt::Display for Times {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>9.2} | {:>9.2} | {:>8.2} |", self.real, self.user, self.sys)
+    }
+}
+
+/// measures from `start` on, `elapsed` can be called repeatedly
+pub struct Timer {
+    start: Instant,
+    user: f64,
+    sys: f64,
+}
+
+impl Timer {
+    pub fn start() -> Timer {
+        let (user, sys) = cpu_total();
+        Timer {
+            start: Instant::now(),
+            user: user,
+            sys: sys,
+        }
+    }
+
+    /// the times since `start`
+    pub fn elapsed(&self) -> Times {
+        let real = self.start.elapsed();
+        let (user, sys) = cpu_total();
+        Times {
+            real: real.as_secs() as f64 + real.subsec_nanos() as f64 / 1e9,
+            user: user - self.user,
+            sys: sys - self.sys,
+        }
+    }
+}
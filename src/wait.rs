@@ -0,0 +1,108 @@
+//! Decoded wait status
+//!
+//! Figure 8.5 only prints why a child terminated, `WaitStatus` keeps that information
+//! around so callers can branch on it. It can be created from the status int of
+//! wait/waitpid and from the siginfo_t filled in by waitid (Exercise 8.3).
+
+use libc::{c_int, siginfo_t};
+use libc::{CLD_CONTINUED, CLD_DUMPED, CLD_EXITED, CLD_KILLED, CLD_STOPPED, CLD_TRAPPED};
+use libc::{WCOREDUMP, WEXITSTATUS, WIFCONTINUED, WIFEXITED, WIFSIGNALED, WIFSTOPPED, WSTOPSIG,
+           WTERMSIG};
+use signals::sig2str;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitStatus {
+    /// normal termination with the exit status
+    Exited(c_int),
+    /// terminated by a signal
+    Signaled { sig: c_int, core: bool },
+    /// stopped by a signal (only reported with WUNTRACED/WSTOPPED)
+    Stopped(c_int),
+    /// continued after a stop (only reported with WCONTINUED)
+    Continued,
+    /// a status none of the W* macros recognizes, kept as it is
+    Unknown(c_int),
+}
+
+impl WaitStatus {
+    /// decode the status filled in by wait/waitpid
+    pub fn from_raw(status: c_int) -> WaitStatus {
+        if WIFEXITED(status) {
+            WaitStatus::Exited(WEXITSTATUS(status))
+        } else if WIFSIGNALED(status) {
+            WaitStatus::Signaled {
+                sig: WTERMSIG(status),
+                core: WCOREDUMP(status),
+            }
+        } else if WIFSTOPPED(status) {
+            WaitStatus::Stopped(WSTOPSIG(status))
+        } else if WIFCONTINUED(status) {
+            WaitStatus::Continued
+        } else {
+            WaitStatus::Unknown(status)
+        }
+    }
+
+    /// decode the siginfo_t filled in by waitid, None if it doesn't describe a child
+    /// (e.g. WNOHANG and no child changed its state)
+    pub fn from_siginfo(info: &siginfo_t) -> Option<WaitStatus> {
+        let status = unsafe { info.si_status() };
+        match info.si_code {
+            CLD_EXITED => Some(WaitStatus::Exited(status)),
+            CLD_KILLED => Some(WaitStatus::Signaled { sig: status, core: false }),
+            CLD_DUMPED => Some(WaitStatus::Signaled { sig: status, core: true }),
+            CLD_STOPPED | CLD_TRAPPED => Some(WaitStatus::Stopped(status)),
+            CLD_CONTINUED => Some(WaitStatus::Continued),
+            _ => None,
+        }
+    }
+
+    /// true if the child exited with status 0
+    pub fn success(&self) -> bool {
+        *self == WaitStatus::Exited(0)
+    }
+
+    pub fn exit_code(&self) -> Option<c_int> {
+        match *self {
+            WaitStatus::Exited(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// the signal that terminated or stopped the child
+    pub fn signal(&self) -> Option<c_int> {
+        match *self {
+            WaitStatus::Signaled { sig, .. } | WaitStatus::Stopped(sig) => Some(sig),
+            _ => None,
+        }
+    }
+}
+
+struct SigName(c_int);
+
+impl fmt::Display for SigName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match sig2str(self.0) {
+            Some(name) => write!(f, "{} ({})", self.0, name),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl fmt::Display for WaitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WaitStatus::Exited(code) => write!(f, "normal termination, exit status = {}", code),
+            WaitStatus::Signaled { sig, core } => {
+                write!(f,
+                       "abnormal termination, signal number = {}{}",
+                       SigName(sig),
+                       if core { " (core file generated)" } else { "" })
+            }
+            WaitStatus::Stopped(sig) => write!(f, "child stopped, signal number = {}", SigName(sig)),
+            WaitStatus::Continued => write!(f, "child continued"),
+            WaitStatus::Unknown(status) => write!(f, "unknown wait status {:#x}", status),
+        }
+    }
+}